@@ -363,6 +363,14 @@ export function subscribeToCssChunkUpdates(
         link.replaceWith(link);
         break;
       }
+      case "removed": {
+        console.info(`Removing CSS chunk \`${chunkPath}\``);
+        link.remove();
+        break;
+      }
+      case "moved":
+        // CSS chunks don't contain modules which need to be kept.
+        break;
       case "partial":
         throw new Error(`partial CSS chunk updates are not supported`);
       default:
@@ -50,10 +50,25 @@ type IpcIncomingMessage = {
   data: RenderData;
 };
 
-type IpcOutgoingMessage = {
-  type: "result";
-  result: string | { body: string; contentType?: string };
-};
+type IpcOutgoingMessage =
+  | {
+      type: "result";
+      result: string | { body: string; contentType?: string };
+    }
+  | {
+      type: "headers";
+      data: {
+        status: number;
+        headers: string[];
+      };
+    }
+  | {
+      type: "bodyChunk";
+      data: number[];
+    }
+  | {
+      type: "bodyEnd";
+    };
 
 (async () => {
   while (true) {
@@ -77,10 +92,31 @@ type IpcOutgoingMessage = {
       throw new Error("no html returned");
     }
 
-    ipc.send({
-      type: "result",
-      result: html,
-    });
+    if ("stream" in html) {
+      await ipc.send({
+        type: "headers",
+        data: {
+          status: 200,
+          headers: ["content-type", html.contentType],
+        },
+      });
+      // Each chunk is sent only after the previous one has been written, so
+      // rendering doesn't run ahead of the client
+      for await (const chunk of html.stream) {
+        await ipc.send({
+          type: "bodyChunk",
+          data: chunk.toJSON().data,
+        });
+      }
+      await ipc.send({
+        type: "bodyEnd",
+      });
+    } else {
+      ipc.send({
+        type: "result",
+        result: html,
+      });
+    }
   }
 })().catch((err) => {
   ipc.sendError(err);
@@ -241,11 +277,18 @@ async function runOperation(renderData: RenderData) {
 
   if (!result) throw new Error("rendering was not successful");
 
+  const contentType = result.contentType() ?? "text/html; charset=utf-8";
   let body;
   if (result.isDynamic()) {
     const stream = new PassThrough();
     result.pipe(stream);
 
+    // Pages are streamed while they are rendered. Other responses, like
+    // flight data, are subscribed to for updates and need the complete body.
+    if (contentType.startsWith("text/html")) {
+      return { contentType, stream };
+    }
+
     const chunks = [];
     for await (const chunk of stream) {
       chunks.push(chunk);
@@ -255,7 +298,7 @@ async function runOperation(renderData: RenderData) {
     body = result.toUnchunkedString();
   }
   return {
-    contentType: result.contentType(),
+    contentType,
     body,
   };
 }
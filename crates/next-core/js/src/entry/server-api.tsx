@@ -33,9 +33,10 @@ type IpcOutgoingMessage =
       data: ResponseHeaders;
     }
   | {
-      type: "body";
+      type: "bodyChunk";
       data: Array<number>;
-    };
+    }
+  | { type: "bodyEnd" };
 
 type RenderData = {
   method: string;
@@ -139,7 +140,6 @@ async function createOperation(renderData: RenderData): Promise<Operation> {
 }
 
 function handleClientResponse(server: Server, clientResponse: IncomingMessage) {
-  const responseData: Buffer[] = [];
   const responseHeaders: ResponseHeaders = {
     status: clientResponse.statusCode!,
     headers: clientResponse.rawHeaders,
@@ -150,14 +150,22 @@ function handleClientResponse(server: Server, clientResponse: IncomingMessage) {
     data: responseHeaders,
   });
 
-  clientResponse.on("data", (chunk) => {
-    responseData.push(chunk);
+  // Chunks are forwarded as they arrive, so the response can be streamed.
+  // Reading is paused until a chunk has been written, so the handler doesn't
+  // run ahead of the client.
+  clientResponse.on("data", (chunk: Buffer) => {
+    clientResponse.pause();
+    ipc
+      .send({
+        type: "bodyChunk",
+        data: chunk.toJSON().data,
+      })
+      .then(() => clientResponse.resume());
   });
 
   clientResponse.once("end", () => {
     ipc.send({
-      type: "body",
-      data: Buffer.concat(responseData).toJSON().data,
+      type: "bodyEnd",
     });
     server.close();
   });
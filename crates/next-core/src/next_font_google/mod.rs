@@ -4,7 +4,7 @@ use indoc::formatdoc;
 use once_cell::sync::Lazy;
 use turbo_tasks::primitives::{OptionStringVc, OptionU16Vc, StringVc, U32Vc};
 use turbo_tasks_env::{CommandLineProcessEnvVc, ProcessEnv};
-use turbo_tasks_fetch::fetch;
+use turbo_tasks_fetch::{fetch, FetchErrorVc};
 use turbo_tasks_fs::{FileContent, FileSystemPathVc};
 use turbo_tasks_hash::hash_xxh3_hash64;
 use turbopack_core::{
@@ -154,7 +154,7 @@ impl ImportMappingReplacement for NextFontGoogleCssModuleReplacer {
             get_request_id(*query_vc).await?
         ));
 
-        let stylesheet_res = fetch(
+        let stylesheet_res = fetch_stylesheet(
             stylesheet_url,
             OptionStringVc::cell(Some(
                 "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like \
@@ -165,10 +165,14 @@ impl ImportMappingReplacement for NextFontGoogleCssModuleReplacer {
         .await?;
 
         let stylesheet = match &*stylesheet_res {
-            Ok(r) => Some(
-                update_stylesheet(r.await?.body.to_string(), options, scoped_font_family)
-                    .await?
-                    .clone(),
+            Ok(stylesheet) => Some(
+                update_stylesheet(
+                    StringVc::cell(stylesheet.clone()),
+                    options,
+                    scoped_font_family,
+                )
+                .await?
+                .clone(),
             ),
             Err(err) => {
                 // Inform the user of the failure to retreive the stylesheet, but don't
@@ -222,6 +226,26 @@ impl ImportMappingReplacement for NextFontGoogleCssModuleReplacer {
     }
 }
 
+#[turbo_tasks::value(transparent)]
+struct FetchedStylesheet(Result<String, FetchErrorVc>);
+
+/// Fetches a stylesheet from Google Fonts. It's cached on disk, since the
+/// stylesheet only changes with the url and user agent. Failed fetches
+/// reference an error cell, so they are not cached and retried in the next
+/// session.
+#[turbo_tasks::function(cache = "disk")]
+async fn fetch_stylesheet(
+    url: StringVc,
+    user_agent: OptionStringVc,
+) -> Result<FetchedStylesheetVc> {
+    Ok(FetchedStylesheetVc::cell(
+        match &*fetch(url, user_agent).await? {
+            Ok(response) => Ok(response.await?.body.to_string().await?.clone()),
+            Err(err) => Err(*err),
+        },
+    ))
+}
+
 #[turbo_tasks::function]
 async fn update_stylesheet(
     stylesheet: StringVc,
@@ -73,17 +73,57 @@ pub struct DevServerOptions {
     #[cfg_attr(feature = "serializable", serde(default))]
     pub log_detail: bool,
 
-    /// Whether to enable full task stats recording in Turbo Engine.
+    /// Whether to enable full task stats recording in Turbo Engine, including
+    /// the reasons why tasks have been invalidated.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub full_stats: bool,
 
     /// Enable experimental garbage collection with the provided memory limit in
-    /// MB.
+    /// MB. Defaults to a share of the container memory limit when running in a
+    /// cgroup with a memory limit.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub memory_limit: Option<usize>,
 
+    /// Record a timeline of all task executions to the provided file. The
+    /// trace uses the Chrome Trace Event format and can be loaded into
+    /// Perfetto.
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub trace: Option<PathBuf>,
+
+    /// Serve the application over HTTPS and HTTP/2. A certificate signed by a
+    /// local certificate authority is generated in `.next/certificates`
+    /// unless a certificate and key are provided.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub experimental_https: bool,
+
+    /// Path to a PEM encoded certificate to serve HTTPS with. Implies
+    /// `--experimental-https`.
+    #[cfg_attr(
+        feature = "cli",
+        clap(long, value_parser, requires = "experimental_https_key")
+    )]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub experimental_https_cert: Option<PathBuf>,
+
+    /// Path to the PEM encoded private key of `--experimental-https-cert`.
+    #[cfg_attr(
+        feature = "cli",
+        clap(long, value_parser, requires = "experimental_https_cert")
+    )]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub experimental_https_key: Option<PathBuf>,
+
+    /// Forwards requests below a path prefix to another HTTP server, given as
+    /// `PREFIX=URL`, e. g. `/api=http://localhost:4000/api`. WebSocket
+    /// upgrades are forwarded as well. Can be repeated.
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub experimental_proxy: Vec<String>,
+
     // ==
     // = Inherited options from next-dev, need revisit later.
     // ==
@@ -9,13 +9,14 @@ use std::{
     env::current_dir,
     future::{join, Future},
     io::{stdout, Write},
+    iter,
     net::{IpAddr, SocketAddr},
-    path::MAIN_SEPARATOR,
+    path::{Path, MAIN_SEPARATOR},
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use devserver_options::DevServerOptions;
 use next_core::{
     create_app_source, create_page_source, create_web_entry_source, env::load_env,
@@ -29,7 +30,7 @@ use turbo_tasks::{
     RawVc, StatsType, TransientInstance, TransientValue, TurboTasks, TurboTasksBackendApi, Value,
 };
 use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
-use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_memory::{default_memory_limit, MemoryBackend};
 use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc, LogOptions};
 use turbopack_core::{
     environment::ServerAddr,
@@ -40,10 +41,11 @@ use turbopack_core::{
 use turbopack_dev_server::{
     introspect::IntrospectionSource,
     source::{
-        combined::CombinedContentSourceVc, router::RouterContentSource,
-        source_maps::SourceMapContentSourceVc, static_assets::StaticAssetsContentSourceVc,
-        ContentSourceVc,
+        combined::CombinedContentSourceVc, proxy::ProxyContentSourceVc,
+        router::RouterContentSource, source_maps::SourceMapContentSourceVc,
+        static_assets::StaticAssetsContentSourceVc, ContentSourceVc,
     },
+    tls::TlsCertificate,
     DevServer, DevServerBuilder,
 };
 use turbopack_node::execution_context::ExecutionContextVc;
@@ -67,6 +69,9 @@ pub struct NextDevServerBuilder {
     show_all: bool,
     log_detail: bool,
     allow_retry: bool,
+    https: bool,
+    https_certificate: Option<TlsCertificate>,
+    proxies: Vec<(String, String)>,
 }
 
 impl NextDevServerBuilder {
@@ -90,6 +95,9 @@ impl NextDevServerBuilder {
             show_all: false,
             log_detail: false,
             allow_retry: false,
+            https: false,
+            https_certificate: None,
+            proxies: vec![],
         }
     }
 
@@ -138,6 +146,23 @@ impl NextDevServerBuilder {
         self
     }
 
+    pub fn https(mut self, https: bool) -> NextDevServerBuilder {
+        self.https = https;
+        self
+    }
+
+    /// Uses the given certificate for HTTPS instead of generating one.
+    pub fn https_certificate(mut self, certificate: TlsCertificate) -> NextDevServerBuilder {
+        self.https_certificate = Some(certificate);
+        self
+    }
+
+    /// Forwards requests below the path `prefix` to the `upstream` URL.
+    pub fn proxy(mut self, prefix: String, upstream: String) -> NextDevServerBuilder {
+        self.proxies.push((prefix, upstream));
+        self
+    }
+
     /// Attempts to find an open port to bind.
     fn find_port(&self, host: IpAddr, port: u16, max_attempts: u16) -> Result<DevServerBuilder> {
         // max_attempts of 1 means we loop 0 times.
@@ -182,7 +207,18 @@ impl NextDevServerBuilder {
         let port = self.port.context("port must be set")?;
         let host = self.hostname.context("hostname must be set")?;
 
-        let server = self.find_port(host, port, 10)?;
+        let mut server = self.find_port(host, port, 10)?;
+        if self.https {
+            let certificate = match self.https_certificate {
+                Some(certificate) => certificate,
+                None => TlsCertificate::self_signed(
+                    &Path::new(&self.project_dir).join(".next/certificates"),
+                    &[host.to_string()],
+                )?,
+            };
+            server = server.tls(&certificate)?;
+        }
+        let https = server.is_tls();
 
         let turbo_tasks = self.turbo_tasks;
         let project_dir = self.project_dir;
@@ -198,6 +234,7 @@ impl NextDevServerBuilder {
             log_level: self.log_level,
         };
         let entry_requests = Arc::new(self.entry_requests);
+        let proxies = Arc::new(self.proxies);
         let console_ui = Arc::new(ConsoleUi::new(log_options));
         let console_ui_to_dev_server = console_ui.clone();
         let server_addr = Arc::new(server.addr);
@@ -212,6 +249,8 @@ impl NextDevServerBuilder {
                 console_ui.clone().into(),
                 browserslist_query.clone(),
                 server_addr.clone().into(),
+                https,
+                proxies.clone().into(),
             )
         };
 
@@ -258,6 +297,8 @@ async fn source(
     console_ui: TransientInstance<ConsoleUi>,
     browserslist_query: String,
     server_addr: TransientInstance<SocketAddr>,
+    https: bool,
+    proxies: TransientInstance<Vec<(String, String)>>,
 ) -> Result<ContentSourceVc> {
     let console_ui = (*console_ui).clone().cell();
     let output_fs = output_fs(&project_dir, console_ui);
@@ -277,7 +318,12 @@ async fn source(
     let next_config = load_next_config(execution_context.join("next_config"));
 
     let output_root = output_fs.root().join(".next/server");
-    let server_addr = ServerAddr::new(*server_addr).cell();
+    let server_addr = if https {
+        ServerAddr::new_https(*server_addr)
+    } else {
+        ServerAddr::new(*server_addr)
+    }
+    .cell();
 
     let dev_server_fs = ServerFileSystemVc::new().as_file_system();
     let dev_server_root = dev_server_fs.root();
@@ -346,6 +392,22 @@ async fn source(
     .cell()
     .into();
     let main_source = main_source.into();
+    // Proxied paths take precedence over the app, proxies return an exact
+    // match for every path below their prefix
+    let fallback = if proxies.is_empty() {
+        main_source
+    } else {
+        CombinedContentSourceVc::new(
+            proxies
+                .iter()
+                .map(|(prefix, upstream)| {
+                    ProxyContentSourceVc::new(upstream.clone(), prefix.clone(), None).into()
+                })
+                .chain(iter::once(main_source))
+                .collect(),
+        )
+        .into()
+    };
     let source_maps = SourceMapContentSourceVc::new(main_source).into();
     let source_map_trace = NextSourceMapTraceContentSourceVc::new(main_source).into();
     let img_source = NextImageContentSourceVc::new(
@@ -364,7 +426,7 @@ async fn source(
             ("_next/image".to_string(), img_source),
             ("__turbopack_sourcemap__/".to_string(), source_maps),
         ],
-        fallback: main_source,
+        fallback,
     }
     .cell()
     .into();
@@ -410,7 +472,9 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
     };
 
     let tt = TurboTasks::new(MemoryBackend::new(
-        options.memory_limit.map_or(usize::MAX, |l| l * 1024 * 1024),
+        options
+            .memory_limit
+            .map_or_else(default_memory_limit, |l| l * 1024 * 1024),
     ));
 
     let stats_type = match options.full_stats {
@@ -419,9 +483,17 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
     };
     tt.set_stats_type(stats_type);
 
+    // Results of functions with `cache = "disk"`, e. g. fetched Google Fonts
+    // stylesheets, are kept across restarts
+    turbo_tasks::enable_disk_cache(Path::new(&dir).join(".next/cache/turbopack"));
+
+    if let Some(trace) = &options.trace {
+        tt.start_trace_recording(trace)?;
+    }
+
     let tt_clone = tt.clone();
 
-    #[allow(unused_mut)]
+    let https = options.experimental_https || options.experimental_https_cert.is_some();
     let mut server = NextDevServerBuilder::new(tt, dir, root_dir)
         .entry_request(EntryRequest::Relative("src/index".into()))
         .eager_compile(options.eager_compile)
@@ -429,12 +501,34 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
         .port(options.port)
         .log_detail(options.log_detail)
         .show_all(options.show_all)
+        .https(https)
         .log_level(
             options
                 .log_level
                 .map_or_else(|| IssueSeverity::Warning, |l| l.0),
         );
 
+    match (
+        &options.experimental_https_cert,
+        &options.experimental_https_key,
+    ) {
+        (Some(cert), Some(key)) => {
+            server = server.https_certificate(TlsCertificate::new(cert.clone(), key.clone()));
+        }
+        (None, None) => {}
+        _ => bail!("both a certificate and a key are needed to serve HTTPS"),
+    }
+
+    for proxy in options.experimental_proxy.iter() {
+        let Some((prefix, upstream)) = proxy.split_once('=') else {
+            bail!("invalid proxy {proxy:?}, expected PREFIX=URL");
+        };
+        if !upstream.starts_with("http://") {
+            bail!("invalid proxy {proxy:?}, the upstream must be an http:// URL");
+        }
+        server = server.proxy(prefix.to_string(), upstream.to_string());
+    }
+
     #[cfg(feature = "serializable")]
     {
         server = server.allow_retry(options.allow_retry);
@@ -443,7 +537,12 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
     let server = server.build().await?;
 
     {
-        let index_uri = ServerAddr::new(server.addr).to_string()?;
+        let index_uri = if https {
+            ServerAddr::new_https(server.addr)
+        } else {
+            ServerAddr::new(server.addr)
+        }
+        .to_string()?;
         println!(
             "{} - started server on {}:{}, url: {}",
             "ready".green(),
@@ -1,10 +1,11 @@
 use std::{sync::Arc, time::Duration};
 
 use anyhow::Result;
-use mime::TEXT_HTML_UTF_8;
+use mime::{TEXT_HTML_UTF_8, TEXT_PLAIN_UTF_8};
 use turbo_tasks::{get_invalidator, TurboTasks, TurboTasksBackendApi, Value};
 use turbo_tasks_fs::File;
 use turbo_tasks_memory::{
+    snapshot::Snapshot,
     stats::{ReferenceType, Stats},
     viz, MemoryBackend,
 };
@@ -96,6 +97,30 @@ impl ContentSource for TurboTasksSource {
                     })));
                 }
             }
+            "invalidations" => {
+                let table = viz::invalidations::create_table(tt.backend());
+                viz::table::wrap_html(&table)
+            }
+            "gc" => {
+                let table = viz::gc::create_table(tt.backend());
+                viz::table::wrap_html(&table)
+            }
+            "snapshot" => {
+                // A plain text snapshot that can be saved and compared with
+                // `turbo-tasks-snapshot-diff`
+                let mut snapshot = Vec::new();
+                Snapshot::capture(tt.backend()).write(&mut snapshot)?;
+                return Ok(ContentSourceResultVc::exact(
+                    ContentSourceContent::Static(
+                        AssetContentVc::from(
+                            File::from(snapshot).with_content_type(TEXT_PLAIN_UTF_8),
+                        )
+                        .into(),
+                    )
+                    .cell()
+                    .into(),
+                ));
+            }
             "reset" => {
                 let b = tt.backend();
                 b.with_all_cached_tasks(|task| {
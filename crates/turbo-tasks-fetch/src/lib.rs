@@ -35,7 +35,8 @@ impl HttpResponseBodyVc {
     }
 }
 
-#[turbo_tasks::function]
+// Limits parallel requests, so many fetches don't run into rate limits
+#[turbo_tasks::function(concurrency = 8)]
 pub async fn fetch(url: StringVc, user_agent: OptionStringVc) -> Result<FetchResultVc> {
     let url = url.await?.clone();
     let user_agent = &*user_agent.await?;
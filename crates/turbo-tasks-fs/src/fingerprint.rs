@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    fs::{FileType, Metadata},
+    io::{self, ErrorKind},
+    path::Path,
+    sync::{LockResult, Mutex, MutexGuard},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::{hash_xxh3_hash64, Xxh3Hash64Hasher};
+
+use crate::rope::Rope;
+
+/// The state of a path on disk at the time a read was served. The fingerprint
+/// changes whenever the file watcher would report a change for that path.
+///
+/// Comparing the recorded fingerprint with the current state of the path tells
+/// if a read result has become outdated while no watcher was running, e. g.
+/// between two sessions that share a persisted graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathFingerprint {
+    NotFound,
+    File {
+        mtime: Option<u128>,
+        size: u64,
+        content_hash: u64,
+    },
+    Directory {
+        mtime: Option<u128>,
+        entries_hash: u64,
+    },
+    Symlink {
+        mtime: Option<u128>,
+        target_hash: u64,
+    },
+    Other,
+}
+
+impl PathFingerprint {
+    /// Computes the current fingerprint of a path. Hashes of the `previous`
+    /// fingerprint are reused when modification time and size are unchanged,
+    /// so only modified files need to be read.
+    pub fn compute(path: &Path, previous: Option<&PathFingerprint>) -> io::Result<Self> {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::NotADirectory => {
+                return Ok(PathFingerprint::NotFound)
+            }
+            Err(e) => return Err(e),
+        };
+        Self::from_metadata(path, &metadata, previous)
+    }
+
+    /// Computes the fingerprint of a path from its metadata, see
+    /// [PathFingerprint::compute].
+    pub(crate) fn from_metadata(
+        path: &Path,
+        metadata: &Metadata,
+        previous: Option<&PathFingerprint>,
+    ) -> io::Result<Self> {
+        if let Some(unchanged) = Self::unchanged(metadata, previous) {
+            return Ok(unchanged);
+        }
+        let mtime = modified(metadata);
+        let file_type = metadata.file_type();
+        Ok(if file_type.is_file() {
+            PathFingerprint::File {
+                mtime,
+                size: metadata.len(),
+                content_hash: hash_content(&Rope::from(std::fs::read(path)?)),
+            }
+        } else if file_type.is_dir() {
+            let mut entries = Vec::new();
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+                entries.push((
+                    entry.file_name().to_string_lossy().to_string(),
+                    EntryKind::from(entry.file_type()?),
+                ));
+            }
+            PathFingerprint::Directory {
+                mtime,
+                entries_hash: hash_entries(entries),
+            }
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(path)?;
+            PathFingerprint::Symlink {
+                mtime,
+                target_hash: hash_target(&target),
+            }
+        } else {
+            PathFingerprint::Other
+        })
+    }
+
+    /// Returns the `previous` fingerprint when the metadata shows that the path
+    /// hasn't changed since then.
+    pub(crate) fn unchanged(
+        metadata: &Metadata,
+        previous: Option<&PathFingerprint>,
+    ) -> Option<Self> {
+        let mtime = Some(modified(metadata)?);
+        match *previous? {
+            PathFingerprint::File {
+                mtime: previous_mtime,
+                size,
+                content_hash,
+            } if metadata.is_file() && mtime == previous_mtime && metadata.len() == size => {
+                Some(PathFingerprint::File {
+                    mtime,
+                    size,
+                    content_hash,
+                })
+            }
+            PathFingerprint::Directory {
+                mtime: previous_mtime,
+                entries_hash,
+            } if metadata.is_dir() && mtime == previous_mtime => Some(PathFingerprint::Directory {
+                mtime,
+                entries_hash,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Creates the fingerprint of a file from its content that was just
+    /// read. The metadata need to be taken before reading the content, so the
+    /// fingerprint is rather outdated than too new when the file changes in
+    /// between.
+    pub(crate) fn from_file(metadata: &Metadata, content: &Rope) -> Option<Self> {
+        metadata.is_file().then(|| PathFingerprint::File {
+            mtime: modified(metadata),
+            size: metadata.len(),
+            content_hash: hash_content(content),
+        })
+    }
+
+    /// Creates the fingerprint of a directory from the entries that were just
+    /// listed. It has no modification time, so the directory is listed again
+    /// when the fingerprint is validated.
+    pub(crate) fn from_dir_entries(entries: Vec<(String, EntryKind)>) -> Self {
+        PathFingerprint::Directory {
+            mtime: None,
+            entries_hash: hash_entries(entries),
+        }
+    }
+
+    /// Creates the fingerprint of a symlink from the target that was just
+    /// read.
+    pub(crate) fn from_link(target: &Path) -> Self {
+        PathFingerprint::Symlink {
+            mtime: None,
+            target_hash: hash_target(target),
+        }
+    }
+
+    /// Compares two fingerprints ignoring the modification time. Touching a
+    /// file or restoring its previous content doesn't count as a change.
+    pub fn same_content(&self, other: &PathFingerprint) -> bool {
+        match (self, other) {
+            (
+                PathFingerprint::File {
+                    size, content_hash, ..
+                },
+                PathFingerprint::File {
+                    size: other_size,
+                    content_hash: other_content_hash,
+                    ..
+                },
+            ) => size == other_size && content_hash == other_content_hash,
+            (
+                PathFingerprint::Directory { entries_hash, .. },
+                PathFingerprint::Directory {
+                    entries_hash: other_entries_hash,
+                    ..
+                },
+            ) => entries_hash == other_entries_hash,
+            (
+                PathFingerprint::Symlink { target_hash, .. },
+                PathFingerprint::Symlink {
+                    target_hash: other_target_hash,
+                    ..
+                },
+            ) => target_hash == other_target_hash,
+            (PathFingerprint::NotFound, PathFingerprint::NotFound)
+            | (PathFingerprint::Other, PathFingerprint::Other) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
+
+impl From<FileType> for EntryKind {
+    fn from(file_type: FileType) -> Self {
+        if file_type.is_file() {
+            EntryKind::File
+        } else if file_type.is_dir() {
+            EntryKind::Directory
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::Other
+        }
+    }
+}
+
+fn modified(metadata: &Metadata) -> Option<u128> {
+    let modified = metadata.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+fn hash_content(content: &Rope) -> u64 {
+    hash_xxh3_hash64(content)
+}
+
+fn hash_target(target: &Path) -> u64 {
+    hash_xxh3_hash64(target.to_string_lossy().as_bytes())
+}
+
+fn hash_entries(mut entries: Vec<(String, EntryKind)>) -> u64 {
+    entries.sort();
+    let mut hasher = Xxh3Hash64Hasher::new();
+    for (name, kind) in entries {
+        hasher.write_ref(&name);
+        hasher.write_value(kind as u8);
+    }
+    hasher.finish()
+}
+
+/// Fingerprints of all paths that reads have been served for, keyed like the
+/// invalidator maps.
+#[derive(Default, Serialize, Deserialize)]
+pub struct FingerprintMap {
+    map: Mutex<HashMap<String, PathFingerprint>>,
+}
+
+impl FingerprintMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, HashMap<String, PathFingerprint>>> {
+        self.map.lock()
+    }
+
+    /// Records the fingerprint for a served read. Without a fingerprint the
+    /// path will always be considered as changed.
+    pub fn insert(&self, key: String, fingerprint: Option<PathFingerprint>) {
+        let mut map = self.map.lock().unwrap();
+        match fingerprint {
+            Some(fingerprint) => {
+                map.insert(key, fingerprint);
+            }
+            None => {
+                map.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{EntryKind, PathFingerprint};
+
+    #[test]
+    fn detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.js");
+        fs::write(&file, "export default 1;").unwrap();
+
+        let dir_fingerprint = PathFingerprint::compute(dir.path(), None).unwrap();
+        let file_fingerprint = PathFingerprint::compute(&file, None).unwrap();
+        let recomputed = PathFingerprint::compute(&file, Some(&file_fingerprint)).unwrap();
+        assert!(file_fingerprint.same_content(&recomputed));
+
+        fs::write(&file, "export default 2;").unwrap();
+        let changed = PathFingerprint::compute(&file, None).unwrap();
+        assert!(!file_fingerprint.same_content(&changed));
+
+        fs::write(&file, "export default 1;").unwrap();
+        let restored = PathFingerprint::compute(&file, None).unwrap();
+        assert!(file_fingerprint.same_content(&restored));
+
+        let listed =
+            PathFingerprint::from_dir_entries(vec![("file.js".to_string(), EntryKind::File)]);
+        assert!(dir_fingerprint.same_content(&listed));
+
+        fs::write(dir.path().join("other.js"), "").unwrap();
+        let dir_changed = PathFingerprint::compute(dir.path(), Some(&listed)).unwrap();
+        assert!(!dir_fingerprint.same_content(&dir_changed));
+
+        fs::remove_file(&file).unwrap();
+        let removed = PathFingerprint::compute(&file, Some(&restored)).unwrap();
+        assert_eq!(removed, PathFingerprint::NotFound);
+    }
+}
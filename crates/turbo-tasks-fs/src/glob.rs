@@ -9,7 +9,7 @@ enum GlobPart {
     /// `/**/`: Matches any path of directories
     AnyDirectories,
 
-    /// `*`: Matches any filename (no path separator)
+    /// `*`: Matches any filename, including an empty one (no path separator)
     AnyFile,
 
     /// `?`: Matches a single filename character (no path separator)
@@ -18,8 +18,12 @@ enum GlobPart {
     /// `/`: Matches the path separator
     PathSeparator,
 
-    /// `[abc]`: Matches any char of the list
-    FileChar(Vec<char>),
+    /// `[abc]`, `[a-z]`: Matches any char of the list or ranges, `[!abc]` or
+    /// `[^abc]` matches any char not in the list (never the path separator)
+    FileChar {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
 
     /// `abc`: Matches literal filename
     File(String),
@@ -34,12 +38,18 @@ enum GlobPart {
 // - file*.js = File(file), AnyFile, File(.js)
 // - dir/file.js = File(dir), PathSeparator, File(file.js)
 // - **/*.js = AnyDirectories, PathSeparator, AnyFile, File(.js)
+// - file?.[!t]s = File(file), AnyFileChar, File(.), FileChar { negated: true,
+//   ranges: [(t, t)] }, File(s)
 // - {a/**,*}/file = Alternatives([File(a), PathSeparator, AnyDirectories],
 //   [AnyFile]), PathSeparator, File(file)
 
 // Note: a/**/b does match a/b, so we need some special logic about path
 // separators
 
+/// A glob that is matched against whole paths: `*.so` matches `lib.so`, but
+/// not `lib.so.1`. `*` also matches an empty filename, so `file*.js` matches
+/// `file.js`. Globs which should match a prefix need to say so, e. g.
+/// `*.so{,.*}`.
 #[turbo_tasks::value]
 #[derive(Debug, Clone)]
 pub struct Glob {
@@ -47,11 +57,15 @@ pub struct Glob {
 }
 
 impl Glob {
+    /// Checks if the glob matches the whole `path`. A path that only starts
+    /// with a match (e. g. `file.js` for the glob `file`) doesn't match.
+    ///
+    /// A `path` ending with `/` is a directory and matches when the glob could
+    /// match something inside of it (e. g. `dir/` for the glob `dir/*.js`).
     pub fn execute(&self, path: &str) -> bool {
         let match_partial = path.ends_with('/');
         self.iter_matches(path, true, match_partial)
-            .next()
-            .is_some()
+            .any(|(remainder, _)| remainder.is_empty())
     }
 
     fn iter_matches<'a>(
@@ -173,8 +187,7 @@ impl GlobPart {
             ('*', Some('*')) => Ok((GlobPart::AnyDirectories, &input[2..])),
             ('*', _) => Ok((GlobPart::AnyFile, &input[1..])),
             ('?', _) => Ok((GlobPart::AnyFileChar, &input[1..])),
-            ('[', Some('[')) => todo!("glob char classes are not implemented yet"),
-            ('[', _) => todo!("glob char sequences are not implemented yet"),
+            ('[', _) => GlobPart::parse_char_class(&input[1..]),
             ('{', Some(_)) => {
                 let mut current = &input[1..];
                 let mut alternatives = Vec::new();
@@ -210,12 +223,13 @@ impl GlobPart {
             _ => {
                 let mut is_escaped = false;
                 let mut literal = String::new();
-                let mut index = 0;
-                for c in input.chars() {
+                let mut end = input.len();
+                for (index, c) in input.char_indices() {
                     if is_escaped {
                         is_escaped = false;
                     } else if c == '\\' {
                         is_escaped = true;
+                        continue;
                     } else if c == '/'
                         || c == '*'
                         || c == '?'
@@ -223,14 +237,64 @@ impl GlobPart {
                         || c == '{'
                         || (inside_of_braces && (c == ',' || c == '}'))
                     {
+                        end = index;
                         break;
                     }
                     literal.push(c);
-                    index += 1;
                 }
-                Ok((GlobPart::File(literal), &input[index..]))
+                if is_escaped {
+                    bail!("Unterminated glob escape sequence");
+                }
+                Ok((GlobPart::File(literal), &input[end..]))
+            }
+        }
+    }
+
+    /// Parses a `[...]` char class. `input` starts right after the opening
+    /// bracket. A `]` directly after the (optionally negated) opening bracket
+    /// is part of the list, `\` escapes the following char.
+    fn parse_char_class(input: &str) -> Result<(GlobPart, &str)> {
+        let mut chars = input.char_indices().peekable();
+        let negated = matches!(chars.peek(), Some((_, '!' | '^')));
+        if negated {
+            chars.next();
+        }
+        let mut ranges = Vec::new();
+        let mut is_first = true;
+        while let Some((index, c)) = chars.next() {
+            let start = match c {
+                ']' if !is_first => {
+                    return Ok((GlobPart::FileChar { negated, ranges }, &input[index + 1..]));
+                }
+                '\\' => match chars.next() {
+                    Some((_, c)) => c,
+                    None => break,
+                },
+                c => c,
+            };
+            is_first = false;
+            let mut lookahead = chars.clone();
+            match (lookahead.next(), lookahead.next()) {
+                (Some((_, '-')), Some((_, end))) if end != ']' => {
+                    chars.next();
+                    chars.next();
+                    let end = if end == '\\' {
+                        match chars.next() {
+                            Some((_, c)) => c,
+                            None => break,
+                        }
+                    } else {
+                        end
+                    };
+                    if end < start {
+                        bail!("Invalid glob char range {start}-{end}");
+                    }
+                    ranges.push((start, end));
+                }
+                _ => ranges.push((start, start)),
             }
         }
+        bail!("Unterminated glob char class")
     }
 }
 
@@ -271,22 +335,25 @@ impl<'a> Iterator for GlobPartMatchesIterator<'a> {
                 }
             }
             GlobPart::AnyFile => {
-                self.index += 1;
-                // TODO verify if `*` does match zero chars?
-                if let Some(slice) = self.path.get(0..self.index) {
-                    if slice.ends_with('/') {
-                        None
-                    } else {
-                        Some((
-                            &self.path[self.index..],
-                            self.previous_part_is_path_separator_equivalent && self.index == 1,
-                        ))
+                // `*` matches zero or more chars, but never crosses a path separator
+                let remainder = self.path.get(self.index..)?;
+                match remainder.chars().next() {
+                    Some(c) if c != '/' => self.index += c.len_utf8(),
+                    _ => self.index = self.path.len() + 1,
+                }
+                Some((remainder, false))
+            }
+            GlobPart::AnyFileChar => {
+                if self.index == 0 {
+                    self.index = 1;
+                    if let Some(c) = self.path.chars().next() {
+                        if c != '/' {
+                            return Some((&self.path[c.len_utf8()..], false));
+                        }
                     }
-                } else {
-                    None
                 }
+                None
             }
-            GlobPart::AnyFileChar => todo!(),
             GlobPart::PathSeparator => {
                 if self.index == 0 {
                     self.index = 1;
@@ -301,16 +368,20 @@ impl<'a> Iterator for GlobPartMatchesIterator<'a> {
                     None
                 }
             }
-            GlobPart::FileChar(chars) => loop {
-                if let Some(c) = chars.get(self.index) {
-                    self.index += 1;
-                    if self.path.starts_with(*c) {
-                        return Some((&self.path[1..], false));
+            GlobPart::FileChar { negated, ranges } => {
+                if self.index == 0 {
+                    self.index = 1;
+                    if let Some(c) = self.path.chars().next() {
+                        let in_ranges = ranges
+                            .iter()
+                            .any(|(start, end)| (*start..=*end).contains(&c));
+                        if c != '/' && in_ranges != *negated {
+                            return Some((&self.path[c.len_utf8()..], false));
+                        }
                     }
-                } else {
-                    return None;
                 }
-            },
+                None
+            }
             GlobPart::File(name) => {
                 if self.index == 0 && self.path.starts_with(name) {
                     self.index += 1;
@@ -359,6 +430,7 @@ impl GlobVc {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
     use rstest::*;
 
     use super::Glob;
@@ -391,6 +463,25 @@ mod tests {
         "**/*/next/dist/server/next.js",
         "node_modules/next/dist/server/next.js"
     )]
+    #[case::star_empty("file*.js", "file.js")]
+    #[case::any_char("file?.js", "file1.js")]
+    #[case::any_char_multiple("??.js", "ab.js")]
+    #[case::any_char_unicode("?.js", "ä.js")]
+    #[case::char_list("file.[jt]s", "file.ts")]
+    #[case::char_range("src/[a-z]*.ts", "src/index.ts")]
+    #[case::char_range_digits("chunk-[0-9][0-9].js", "chunk-42.js")]
+    #[case::char_negated("[!_]*.tsx", "page.tsx")]
+    #[case::char_negated_caret("[^_]*.tsx", "page.tsx")]
+    #[case::char_bracket("[]a].js", "].js")]
+    #[case::char_escaped("[\\]]x.js", "]x.js")]
+    #[case::escaped_star("file\\*.js", "file*.js")]
+    #[case::escaped_bracket("\\[id\\].js", "[id].js")]
+    #[case::char_in_braces("{[a-c],x}/file.js", "b/file.js")]
+    #[case::char_range_partial("[a-c]/*.js", "b/")]
+    #[case::globstar_only("**", "file.js")]
+    #[case::globstar_only("**", "dir/sub/file.js")]
+    #[case::optional_suffix("*.so{,.*}", "lib.so")]
+    #[case::optional_suffix("*.so{,.*}", "lib.so.1")]
     fn glob_match(#[case] glob: &str, #[case] path: &str) {
         let glob = Glob::parse(glob).unwrap();
 
@@ -398,4 +489,78 @@ mod tests {
 
         assert!(glob.execute(path));
     }
+
+    #[rstest]
+    #[case::file_prefix("file", "file.js")]
+    #[case::star_separator("*.js", "dir/file.js")]
+    #[case::star_suffix("*.so", "lib.so.1")]
+    #[case::any_char_empty("file?.js", "file.js")]
+    #[case::any_char_separator("dir?file.js", "dir/file.js")]
+    #[case::char_range("src/[a-z]*.ts", "src/Index.ts")]
+    #[case::char_negated("[!_]*.tsx", "_app.tsx")]
+    #[case::char_negated_separator("dir[!a]file.js", "dir/file.js")]
+    #[case::escaped_star("file\\*.js", "file1.js")]
+    fn glob_not_match(#[case] glob: &str, #[case] path: &str) {
+        let glob = Glob::parse(glob).unwrap();
+
+        assert!(!glob.execute(path));
+    }
+
+    #[test]
+    fn execute_matches_whole_path() {
+        let glob = Glob::parse("dir/file").unwrap();
+        assert!(glob.execute("dir/file"));
+        assert!(!glob.execute("dir/file.js"));
+        assert!(!glob.execute("dir/file/nested.js"));
+        // directories match when they could contain a match
+        assert!(glob.execute("dir/"));
+        assert!(!glob.execute("other/"));
+
+        let glob = Glob::parse("*.js").unwrap();
+        assert!(glob.execute("file.js"));
+        assert!(!glob.execute("file.js.map"));
+    }
+
+    #[rstest]
+    #[case::unterminated_char_class("[abc")]
+    #[case::unterminated_negated_char_class("[!]")]
+    #[case::invalid_range("[z-a]")]
+    #[case::unterminated_escape("file\\")]
+    fn glob_parse_error(#[case] glob: &str) {
+        assert!(Glob::parse(glob).is_err());
+    }
+
+    fn segments(token: impl Strategy<Value = &'static str>) -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            prop::collection::vec(token, 1..4).prop_map(|tokens| tokens.concat()),
+            1..4,
+        )
+        .prop_map(|segments| segments.join("/"))
+    }
+
+    proptest! {
+        /// Compares the matcher against the `glob` crate for the subset of the
+        /// syntax both support.
+        #[test]
+        fn glob_matches_reference(
+            glob in segments(prop::sample::select(vec![
+                "a", "b", ".", "*", "?", "[ab]", "[a-b]", "[!a]", "[!a-b]", "[]a]",
+            ])).prop_filter("`**` is only valid as a path segment", |glob| !glob.contains("**")),
+            path in segments(prop::sample::select(vec!["a", "b", "c", ".", "]"])),
+        ) {
+            let reference = glob::Pattern::new(&glob).unwrap();
+            let options = glob::MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: true,
+                require_literal_leading_dot: false,
+            };
+            prop_assert_eq!(
+                Glob::parse(&glob).unwrap().execute(&path),
+                reference.matches_with(&path, options),
+                "{} {}",
+                glob,
+                path
+            );
+        }
+    }
 }
@@ -7,6 +7,7 @@
 
 pub mod attach;
 pub mod embed;
+mod fingerprint;
 pub mod glob;
 mod invalidator_map;
 mod mutex_map;
@@ -32,6 +33,8 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use auto_hash_map::AutoMap;
 use bitflags::bitflags;
+use fingerprint::FingerprintMap;
+pub use fingerprint::PathFingerprint;
 use glob::GlobVc;
 use invalidator_map::InvalidatorMap;
 use jsonc_parser::{parse_to_serde_value, ParseOptions};
@@ -85,16 +88,44 @@ pub struct DiskFileSystem {
     invalidator_map: Arc<InvalidatorMap>,
     #[turbo_tasks(debug_ignore, trace_ignore)]
     dir_invalidator_map: Arc<InvalidatorMap>,
+    /// Fingerprints of the paths in the invalidator maps at the time they were
+    /// read. They are persisted together with the invalidators to detect
+    /// changes that happened while the process wasn't running. Only recorded
+    /// for file systems created with [DiskFileSystemVc::new_persisted].
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    fingerprints: Option<Arc<FingerprintMap>>,
     #[turbo_tasks(debug_ignore, trace_ignore)]
     #[serde(skip)]
     watcher: Mutex<Option<RecommendedWatcher>>,
 }
 
 impl DiskFileSystem {
+    async fn create(name: String, root: String, persisted: bool) -> Result<Self> {
+        mark_stateful();
+        // create the directory for the filesystem on disk, if it doesn't exist
+        fs::create_dir_all(&root).await?;
+
+        Ok(DiskFileSystem {
+            name,
+            root,
+            mutex_map: Default::default(),
+            invalidator_map: Arc::new(InvalidatorMap::new()),
+            dir_invalidator_map: Arc::new(InvalidatorMap::new()),
+            fingerprints: persisted.then(|| Arc::new(FingerprintMap::new())),
+            watcher: Mutex::new(None),
+        })
+    }
+
     /// registers the path as an invalidator for the current task,
     /// has to be called within a turbo-tasks function
     fn register_invalidator(&self, path: impl AsRef<Path>, file: bool) {
-        let invalidator = turbo_tasks::get_invalidator();
+        let invalidator = if self.fingerprints.is_some() {
+            // The invalidators are persisted with the file system and validated by
+            // their fingerprints, so the task doesn't need to be volatile.
+            turbo_tasks::get_persisted_invalidator()
+        } else {
+            turbo_tasks::get_invalidator()
+        };
         if file {
             self.invalidator_map.insert(path_to_key(path), invalidator);
         } else {
@@ -103,6 +134,90 @@ impl DiskFileSystem {
         }
     }
 
+    /// Returns the fingerprint recorded by the last read of the path, so
+    /// hashes can be reused when the path hasn't changed.
+    fn previous_fingerprint(&self, path: &Path) -> Option<PathFingerprint> {
+        let fingerprints = self.fingerprints.as_ref()?;
+        let map = fingerprints.lock().unwrap();
+        map.get(&path_to_key(path)).copied()
+    }
+
+    /// Computes the fingerprint of the path from metadata that was just read.
+    /// Hashes are only computed when the path has changed since the previous
+    /// read.
+    async fn fingerprint_from_metadata(
+        &self,
+        path: &Path,
+        metadata: &std::fs::Metadata,
+    ) -> Option<PathFingerprint> {
+        let previous = self.previous_fingerprint(path);
+        if let Some(unchanged) = PathFingerprint::unchanged(metadata, previous.as_ref()) {
+            return Some(unchanged);
+        }
+        let metadata = metadata.clone();
+        retry_blocking(path, move |path| {
+            PathFingerprint::from_metadata(path, &metadata, None)
+        })
+        .await
+        .ok()
+    }
+
+    /// Records the fingerprint of the path for the read that is served.
+    fn record_fingerprint(&self, path: &Path, fingerprint: Option<PathFingerprint>) {
+        if let Some(fingerprints) = &self.fingerprints {
+            fingerprints.insert(path_to_key(path), fingerprint);
+        }
+    }
+
+    /// Returns the [Invalidator]s of all reads whose path has changed since the
+    /// read was served, e. g. while the process wasn't running. The returned
+    /// invalidators are removed from the file system.
+    ///
+    /// This need to be called when the file system is restored from a
+    /// persisted graph. `start_watching` does that automatically.
+    ///
+    /// Without fingerprints all invalidators are returned.
+    pub fn invalidators_for_changed_paths(&self) -> Vec<Invalidator> {
+        let Some(fingerprints) = &self.fingerprints else {
+            let mut invalidators = Vec::new();
+            for map in [&self.invalidator_map, &self.dir_invalidator_map] {
+                invalidators.extend(
+                    take(&mut *map.lock().unwrap())
+                        .into_iter()
+                        .flat_map(|(_, invalidators)| invalidators),
+                );
+            }
+            return invalidators;
+        };
+        let mut fingerprints = fingerprints.lock().unwrap();
+        let mut changed_paths = HashMap::new();
+        let mut is_changed = |key: &String| {
+            *changed_paths.entry(key.clone()).or_insert_with(|| {
+                let Some(fingerprint) = fingerprints.get(key) else {
+                    return true;
+                };
+                let changed = match PathFingerprint::compute(Path::new(key), Some(fingerprint)) {
+                    Ok(current) => !fingerprint.same_content(&current),
+                    Err(_) => true,
+                };
+                if changed {
+                    fingerprints.remove(key);
+                }
+                changed
+            })
+        };
+        let mut invalidators = Vec::new();
+        for map in [&self.invalidator_map, &self.dir_invalidator_map] {
+            invalidators.extend(
+                map.lock()
+                    .unwrap()
+                    .drain_filter(|key, _| is_changed(key))
+                    .flat_map(|(_, invalidators)| invalidators),
+            );
+        }
+        invalidators
+    }
+
     pub fn invalidate(&self) {
         for (_, invalidators) in take(&mut *self.invalidator_map.lock().unwrap()).into_iter() {
             invalidators.into_iter().for_each(|i| i.invalidate());
@@ -129,13 +244,11 @@ impl DiskFileSystem {
         // below will be monitored for changes.
         watcher.watch(&root, RecursiveMode::Recursive)?;
 
-        // We need to invalidate all reads that happened before watching
-        // Best is to start_watching before starting to read
-        for (_, invalidators) in take(&mut *invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
-        }
-        for (_, invalidators) in take(&mut *dir_invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
+        // We need to invalidate all reads that happened before watching and
+        // might have changed since then. This includes reads restored from a
+        // persisted graph.
+        for invalidator in self.invalidators_for_changed_paths() {
+            invalidator.invalidate();
         }
 
         watcher_guard.replace(watcher);
@@ -215,7 +328,9 @@ impl DiskFileSystem {
                     for path in paths {
                         let key = path_to_key(path);
                         if let Some(invalidators) = invalidator_map.remove(&key) {
-                            invalidators.into_iter().for_each(|i| i.invalidate());
+                            invalidators
+                                .into_iter()
+                                .for_each(|i| i.invalidate_with_reason(format!("{key} changed")));
                         }
                     }
                 }
@@ -223,12 +338,14 @@ impl DiskFileSystem {
                     invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
                     paths: &mut HashSet<PathBuf>,
                 ) {
-                    for (_, invalidators) in invalidator_map.drain_filter(|key, _| {
+                    for (key, invalidators) in invalidator_map.drain_filter(|key, _| {
                         paths
                             .iter()
                             .any(|path_key| key.starts_with(&path_to_key(path_key)))
                     }) {
-                        invalidators.into_iter().for_each(|i| i.invalidate());
+                        invalidators
+                            .into_iter()
+                            .for_each(|i| i.invalidate_with_reason(format!("{key} changed")));
                     }
                     paths.clear()
                 }
@@ -282,20 +399,15 @@ pub fn path_to_key(path: impl AsRef<Path>) -> String {
 impl DiskFileSystemVc {
     #[turbo_tasks::function]
     pub async fn new(name: String, root: String) -> Result<Self> {
-        mark_stateful();
-        // create the directory for the filesystem on disk, if it doesn't exist
-        fs::create_dir_all(&root).await?;
-
-        let instance = DiskFileSystem {
-            name,
-            root,
-            mutex_map: Default::default(),
-            invalidator_map: Arc::new(InvalidatorMap::new()),
-            dir_invalidator_map: Arc::new(InvalidatorMap::new()),
-            watcher: Mutex::new(None),
-        };
+        Ok(Self::cell(DiskFileSystem::create(name, root, false).await?))
+    }
 
-        Ok(Self::cell(instance))
+    /// Creates a file system whose reads are restored from a persisted graph.
+    /// It records the fingerprints of all paths it reads, so reads can be
+    /// validated against changes made while the process wasn't running.
+    #[turbo_tasks::function]
+    pub async fn new_persisted(name: String, root: String) -> Result<Self> {
+        Ok(Self::cell(DiskFileSystem::create(name, root, true).await?))
     }
 }
 
@@ -313,6 +425,13 @@ impl FileSystem for DiskFileSystem {
         self.register_invalidator(&full_path, true);
 
         let _lock = self.mutex_map.lock(full_path.clone()).await;
+        // The metadata is taken before reading, so the fingerprint is rather
+        // outdated than too new when the file changes in between.
+        let metadata = if self.fingerprints.is_some() {
+            Some(fs::symlink_metadata(&full_path).await)
+        } else {
+            None
+        };
         let content = match retry_future(|| File::from_path(full_path.clone())).await {
             Ok(file) => FileContent::new(file),
             Err(e) if e.kind() == ErrorKind::NotFound => FileContent::NotFound,
@@ -320,6 +439,18 @@ impl FileSystem for DiskFileSystem {
                 bail!(anyhow!(e).context(format!("reading file {}", full_path.display())))
             }
         };
+        if let Some(metadata) = metadata {
+            let fingerprint = match (&metadata, &content) {
+                (Ok(metadata), FileContent::Content(file)) => {
+                    PathFingerprint::from_file(metadata, file.content())
+                }
+                (Err(e), FileContent::NotFound) if e.kind() == ErrorKind::NotFound => {
+                    Some(PathFingerprint::NotFound)
+                }
+                _ => None,
+            };
+            self.record_fingerprint(&full_path, fingerprint);
+        }
 
         Ok(content.cell())
     }
@@ -339,13 +470,20 @@ impl FileSystem for DiskFileSystem {
                     || e.kind() == ErrorKind::NotADirectory
                     || e.kind() == ErrorKind::InvalidFilename =>
             {
-                return Ok(DirectoryContentVc::not_found())
+                let fingerprint =
+                    (e.kind() == ErrorKind::NotFound).then_some(PathFingerprint::NotFound);
+                self.record_fingerprint(&full_path, fingerprint);
+                return Ok(DirectoryContentVc::not_found());
             }
             Err(e) => {
+                self.record_fingerprint(&full_path, None);
                 bail!(anyhow!(e).context(format!("reading dir {}", full_path.display())))
             }
         };
 
+        // The fingerprint is computed from the listed entries, including those
+        // that are filtered out below
+        let mut fingerprint_entries = self.fingerprints.as_ref().map(|_| Vec::new());
         let entries = read_dir
             .filter_map(|r| {
                 let e = match r {
@@ -353,6 +491,14 @@ impl FileSystem for DiskFileSystem {
                     Err(err) => return Some(Err(err.into())),
                 };
 
+                if let Some(fingerprint_entries) = &mut fingerprint_entries {
+                    match e.file_type() {
+                        Ok(t) => fingerprint_entries
+                            .push((e.file_name().to_string_lossy().to_string(), t.into())),
+                        Err(err) => return Some(Err(err.into())),
+                    }
+                }
+
                 let path = e.path();
 
                 // we filter out any non unicode names and paths without the same root here
@@ -372,10 +518,18 @@ impl FileSystem for DiskFileSystem {
 
                 Some(anyhow::Ok((file_name, entry)))
             })
-            .collect::<Result<_>>()
-            .with_context(|| format!("reading directory item in {}", full_path.display()))?;
+            .collect::<Result<AutoMap<_, _>>>()
+            .with_context(|| format!("reading directory item in {}", full_path.display()));
+        self.record_fingerprint(
+            &full_path,
+            entries
+                .as_ref()
+                .ok()
+                .and(fingerprint_entries)
+                .map(PathFingerprint::from_dir_entries),
+        );
 
-        Ok(DirectoryContentVc::new(entries))
+        Ok(DirectoryContentVc::new(entries?))
     }
 
     #[turbo_tasks::function]
@@ -385,8 +539,26 @@ impl FileSystem for DiskFileSystem {
 
         let _lock = self.mutex_map.lock(full_path.clone()).await;
         let link_path = match retry_future(|| fs::read_link(&full_path)).await {
-            Ok(res) => res,
-            Err(_) => return Ok(LinkContent::NotFound.cell()),
+            Ok(res) => {
+                self.record_fingerprint(&full_path, Some(PathFingerprint::from_link(&res)));
+                res
+            }
+            Err(e) => {
+                if self.fingerprints.is_some() {
+                    // The path might exist, but not be a symlink
+                    let fingerprint = if e.kind() == ErrorKind::NotFound {
+                        Ok(PathFingerprint::NotFound)
+                    } else {
+                        let previous = self.previous_fingerprint(&full_path);
+                        retry_blocking(&full_path, move |path| {
+                            PathFingerprint::compute(path, previous.as_ref())
+                        })
+                        .await
+                    };
+                    self.record_fingerprint(&full_path, fingerprint.ok());
+                }
+                return Ok(LinkContent::NotFound.cell());
+            }
         };
         let is_link_absolute = link_path.is_absolute();
 
@@ -617,9 +789,16 @@ impl FileSystem for DiskFileSystem {
         self.register_invalidator(&full_path, true);
 
         let _lock = self.mutex_map.lock(full_path.clone()).await;
-        let meta = retry_future(|| fs::metadata(full_path.clone()))
-            .await
-            .with_context(|| format!("reading metadata for {}", full_path.display()))?;
+        let meta = retry_future(|| fs::metadata(full_path.clone())).await;
+        if self.fingerprints.is_some() {
+            let fingerprint = match &meta {
+                Ok(meta) => self.fingerprint_from_metadata(&full_path, meta).await,
+                Err(e) if e.kind() == ErrorKind::NotFound => Some(PathFingerprint::NotFound),
+                Err(_) => None,
+            };
+            self.record_fingerprint(&full_path, fingerprint);
+        }
+        let meta = meta.with_context(|| format!("reading metadata for {}", full_path.display()))?;
 
         Ok(FileMetaVc::cell(meta.into()))
     }
@@ -11,7 +11,8 @@ pub struct ReadGlobResult {
     pub inner: HashMap<String, ReadGlobResultVc>,
 }
 
-/// Reads matches of a glob pattern.
+/// Reads matches of a glob pattern. The glob is matched against the whole
+/// path relative to `directory`.
 ///
 /// DETERMINISM: Result is in random order. Either sort result or do not depend
 /// on the order.
@@ -96,6 +96,12 @@ deterministic_hash_number! {
     (i128, write_i128),
 }
 
+impl DeterministicHash for bool {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        state.write_u8(*self as u8);
+    }
+}
+
 impl<T: ?Sized + DeterministicHash> DeterministicHash for &T {
     fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
         (**self).deterministic_hash(state);
@@ -2,12 +2,126 @@ use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use proc_macro_error::abort;
 use quote::quote;
 use syn::{
-    punctuated::Punctuated, spanned::Spanned, FnArg, Pat, PatIdent, PatType, Receiver, ReturnType,
-    Signature, Token, Type, TypePath, TypeReference,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Error, FnArg, Lit, LitStr, Meta, MetaNameValue, Pat, PatIdent, PatType, Receiver,
+    Result, ReturnType, Signature, Token, Type, TypePath, TypeReference,
 };
 
 use crate::util::*;
 
+enum CacheMode {
+    Memory,
+    Disk,
+}
+
+impl TryFrom<LitStr> for CacheMode {
+    type Error = Error;
+
+    fn try_from(lit: LitStr) -> std::result::Result<Self, Self::Error> {
+        match lit.value().as_str() {
+            "memory" => Ok(CacheMode::Memory),
+            "disk" => Ok(CacheMode::Disk),
+            _ => Err(Error::new_spanned(&lit, "expected \"memory\" or \"disk\"")),
+        }
+    }
+}
+
+/// The arguments of `#[turbo_tasks::function(...)]`, which are also accepted
+/// on methods in `#[turbo_tasks::value_impl]` and
+/// `#[turbo_tasks::value_trait]`.
+pub struct FunctionArguments {
+    cache_mode: CacheMode,
+    concurrency: Option<usize>,
+}
+
+impl Parse for FunctionArguments {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut result = FunctionArguments {
+            cache_mode: CacheMode::Memory,
+            concurrency: None,
+        };
+        let punctuated: Punctuated<Meta, Token![,]> = input.parse_terminated(Meta::parse)?;
+        for meta in punctuated {
+            match (
+                meta.path()
+                    .get_ident()
+                    .map(ToString::to_string)
+                    .as_deref()
+                    .unwrap_or_default(),
+                meta,
+            ) {
+                (
+                    "cache",
+                    Meta::NameValue(MetaNameValue {
+                        lit: Lit::Str(str), ..
+                    }),
+                ) => {
+                    result.cache_mode = CacheMode::try_from(str)?;
+                }
+                (
+                    "concurrency",
+                    Meta::NameValue(MetaNameValue {
+                        lit: Lit::Int(int), ..
+                    }),
+                ) => {
+                    let concurrency = int.base10_parse::<usize>()?;
+                    if concurrency == 0 {
+                        return Err(Error::new_spanned(&int, "concurrency must be at least 1"));
+                    }
+                    result.concurrency = Some(concurrency);
+                }
+                (_, meta) => {
+                    return Err(Error::new_spanned(
+                        &meta,
+                        format!(
+                            "unexpected {:?}, expected \"cache\" or \"concurrency\"",
+                            meta
+                        ),
+                    ))
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl FunctionArguments {
+    /// Parses the arguments of a `#[turbo_tasks::function]` attribute on a
+    /// method.
+    pub fn from_attribute(attr: &Attribute) -> Self {
+        if attr.tokens.is_empty() {
+            return FunctionArguments {
+                cache_mode: CacheMode::Memory,
+                concurrency: None,
+            };
+        }
+        match attr.parse_args() {
+            Ok(args) => args,
+            Err(err) => abort!(err.span(), "{}", err),
+        }
+    }
+
+    /// Builder calls appended to `NativeFunction::new(...)`.
+    pub fn native_function_options(&self) -> TokenStream2 {
+        let disk_cache_code = match self.cache_mode {
+            CacheMode::Memory => quote! {},
+            // The crate version is part of the cache key, so updating the
+            // crate doesn't restore results of an older implementation
+            CacheMode::Disk => quote! {
+                .with_disk_cache(concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION")))
+            },
+        };
+        let concurrency_code = match self.concurrency {
+            Some(limit) => quote! { .with_concurrency_limit(#limit) },
+            None => quote! {},
+        };
+        quote! { #disk_cache_code #concurrency_code }
+    }
+}
+
 /// The underlying type of the `self` identifier.
 pub enum SelfType<'a> {
     Value(&'a Ident),
@@ -24,6 +138,8 @@ pub fn gen_native_function_code(
     inputs: &Punctuated<FnArg, Token![,]>,
     output_type: &Type,
     self_ref_type: Option<(&Ident, SelfType<'_>)>,
+    // Builder calls appended to `NativeFunction::new(...)`
+    options_code: TokenStream2,
 ) -> (TokenStream2, Vec<TokenStream2>) {
     let mut input_extraction = Vec::new();
     let mut input_convert = Vec::new();
@@ -189,7 +305,7 @@ pub fn gen_native_function_code(
                                 #original_call_code
                             })
                         }))
-                    })
+                    })#options_code
                 });
 
             #[doc(hidden)]
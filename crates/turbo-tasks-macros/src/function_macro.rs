@@ -4,7 +4,7 @@ use quote::quote;
 use syn::{parse_macro_input, ItemFn};
 use turbo_tasks_macros_shared::get_function_ident;
 
-use crate::func::{gen_native_function_code, split_signature};
+use crate::func::{gen_native_function_code, split_signature, FunctionArguments};
 
 fn get_function_id_ident(ident: &Ident) -> Ident {
     Ident::new(
@@ -13,7 +13,8 @@ fn get_function_id_ident(ident: &Ident) -> Ident {
     )
 }
 
-pub fn function(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn function(args: TokenStream, input: TokenStream) -> TokenStream {
+    let function_arguments = parse_macro_input!(args as FunctionArguments);
     let item = parse_macro_input!(input as ItemFn);
     let ItemFn {
         attrs,
@@ -36,6 +37,7 @@ pub fn function(_args: TokenStream, input: TokenStream) -> TokenStream {
         &sig.inputs,
         &output_type,
         None,
+        function_arguments.native_function_options(),
     );
 
     quote! {
@@ -67,6 +67,29 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
     value_trait_macro::value_trait(args, input)
 }
 
+/// Turns a function into a turbo-tasks function, which is executed as a task
+/// and cached by its inputs.
+///
+/// `cache` argument (`#[turbo_tasks::function(cache = "disk")]`)
+///
+/// `cache = "memory"`: The result is cached in memory (default).
+///
+/// `cache = "disk"`: The result is additionally cached on disk, keyed by the
+/// values of the inputs, when enabled with `turbo_tasks::enable_disk_cache`.
+/// Only use it for pure functions with serializable output, whose inputs are
+/// primitives or Vcs of types deriving `DeterministicHash`.
+///
+/// The arguments are also accepted on methods in `#[turbo_tasks::value_impl]`
+/// and on trait methods with a default implementation in
+/// `#[turbo_tasks::value_trait]`.
+///
+/// `concurrency` argument (`#[turbo_tasks::function(concurrency = 4)]`)
+///
+/// Limits the number of concurrent executions of the function. Further calls
+/// wait in the scheduler until a running execution finishes. Useful for
+/// functions that spawn processes or make network requests. An execution
+/// releases its slot while it waits for other tasks, so the function can
+/// (indirectly) await calls to itself.
 #[allow_internal_unstable(min_specialization, into_future, trivial_bounds)]
 #[proc_macro_error]
 #[proc_macro_attribute]
@@ -1,6 +1,7 @@
 use syn::{
-    punctuated::Punctuated, token::Paren, AngleBracketedGenericArguments, GenericArgument, Ident,
-    Path, PathArguments, PathSegment, ReturnType, Type, TypePath, TypeTuple,
+    punctuated::Punctuated, token::Paren, AngleBracketedGenericArguments, Attribute,
+    GenericArgument, Ident, Path, PathArguments, PathSegment, ReturnType, Type, TypePath,
+    TypeTuple,
 };
 use turbo_tasks_macros_shared::get_ref_ident;
 
@@ -63,3 +64,19 @@ pub fn get_ref_path(path: &Path) -> Path {
     }
     path
 }
+
+/// Checks if the attribute is `#[turbo_tasks::<name>]`.
+pub fn is_attribute(attr: &Attribute, name: &str) -> bool {
+    let path = &attr.path;
+    if path.leading_colon.is_some() {
+        return false;
+    }
+    let mut iter = path.segments.iter();
+    match iter.next() {
+        Some(seg) if seg.arguments.is_empty() && seg.ident == "turbo_tasks" => match iter.next() {
+            Some(seg) if seg.arguments.is_empty() && seg.ident == name => iter.next().is_none(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
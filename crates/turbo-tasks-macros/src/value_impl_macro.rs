@@ -2,8 +2,8 @@ use proc_macro::TokenStream;
 use proc_macro2::{Ident, Literal, Span, TokenStream as TokenStream2};
 use quote::quote;
 use syn::{
-    parse_macro_input, spanned::Spanned, Attribute, FnArg, ImplItem, ImplItemMethod, ItemImpl,
-    Path, Receiver, ReturnType, Signature, Token, Type, TypePath,
+    parse_macro_input, spanned::Spanned, FnArg, ImplItem, ImplItemMethod, ItemImpl, Path, Receiver,
+    ReturnType, Signature, Token, Type, TypePath,
 };
 use turbo_tasks_macros_shared::{
     get_impl_function_ident, get_ref_ident, get_register_trait_methods_ident,
@@ -11,7 +11,7 @@ use turbo_tasks_macros_shared::{
 };
 
 use crate::{
-    func::{gen_native_function_code, split_signature, SelfType},
+    func::{gen_native_function_code, split_signature, FunctionArguments, SelfType},
     util::*,
 };
 
@@ -49,21 +49,6 @@ fn get_trait_impl_function_id_ident(
     )
 }
 
-fn is_attribute(attr: &Attribute, name: &str) -> bool {
-    let path = &attr.path;
-    if path.leading_colon.is_some() {
-        return false;
-    }
-    let mut iter = path.segments.iter();
-    match iter.next() {
-        Some(seg) if seg.arguments.is_empty() && seg.ident == "turbo_tasks" => match iter.next() {
-            Some(seg) if seg.arguments.is_empty() && seg.ident == name => iter.next().is_none(),
-            _ => false,
-        },
-        _ => false,
-    }
-}
-
 pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
     fn generate_for_vc_impl(vc_ident: &Ident, items: &[ImplItem]) -> TokenStream2 {
         let mut functions = Vec::new();
@@ -91,6 +76,9 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                         .cloned()
                         .collect()
                 };
+                let options_code = function_attr
+                    .map(|attr| FunctionArguments::from_attribute(attr).native_function_options())
+                    .unwrap_or_default();
                 let Signature { ident, .. } = sig;
 
                 let (external_sig, inline_sig, output_type, convert_result_code) =
@@ -110,6 +98,7 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                     &sig.inputs,
                     &output_type,
                     Some((vc_ident, SelfType::Ref)),
+                    options_code,
                 );
 
                 functions.push(quote! {
@@ -168,6 +157,9 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                         .cloned()
                         .collect()
                 };
+                let options_code = function_attr
+                    .map(|attr| FunctionArguments::from_attribute(attr).native_function_options())
+                    .unwrap_or_default();
                 let Signature {
                     ident,
                     inputs,
@@ -195,6 +187,7 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                     inputs,
                     &output_type,
                     Some((&ref_ident, SelfType::Value(struct_ident))),
+                    options_code,
                 );
                 let mut new_sig = sig.clone();
                 new_sig.ident = internal_function_ident;
@@ -6,8 +6,8 @@ use syn::{
     parse_macro_input,
     punctuated::Punctuated,
     spanned::Spanned,
-    Error, Fields, FieldsUnnamed, Item, ItemEnum, ItemStruct, Lit, LitStr, Meta, MetaNameValue,
-    Result, Token,
+    Attribute, Error, Fields, FieldsUnnamed, Item, ItemEnum, ItemStruct, Lit, LitStr, Meta,
+    MetaList, MetaNameValue, NestedMeta, Result, Token,
 };
 use turbo_tasks_macros_shared::{get_ref_ident, get_register_value_type_ident};
 
@@ -36,6 +36,25 @@ fn get_value_type_init_ident(ident: &Ident) -> Ident {
     )
 }
 
+/// Whether the attributes contain `#[derive(DeterministicHash)]`.
+fn derives_deterministic_hash(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(MetaList { nested, .. })) => Some(nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path
+                .segments
+                .last()
+                .map_or(false, |segment| segment.ident == "DeterministicHash"),
+            _ => false,
+        })
+}
+
 enum IntoMode {
     None,
     New,
@@ -220,9 +239,13 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         transparent,
     } = parse_macro_input!(args as ValueArguments);
 
-    let (vis, ident) = match &item {
-        Item::Enum(ItemEnum { vis, ident, .. }) => (vis, ident),
-        Item::Struct(ItemStruct { vis, ident, .. }) => (vis, ident),
+    let (vis, ident, attrs) = match &item {
+        Item::Enum(ItemEnum {
+            vis, ident, attrs, ..
+        }) => (vis, ident, attrs),
+        Item::Struct(ItemStruct {
+            vis, ident, attrs, ..
+        }) => (vis, ident, attrs),
         _ => {
             item.span().unwrap().error("unsupported syntax").emit();
 
@@ -388,6 +411,16 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     };
 
+    // Values can only be part of disk cache keys when they can be hashed
+    // deterministically
+    let register_deterministic_hash = if derives_deterministic_hash(attrs) {
+        quote! {
+            value.register_deterministic_hash::<#ident>();
+        }
+    } else {
+        quote! {}
+    };
+
     let for_input_marker = match serialization_mode {
         SerializationMode::None | SerializationMode::Auto | SerializationMode::Custom => quote! {},
         SerializationMode::AutoForInput | SerializationMode::CustomForInput => quote! {
@@ -468,9 +501,11 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
             ///
             /// When you read a task with `.strongly_consistent()` it will make that one read
             /// operation strongly consistent. That means it will only return a result when all
-            /// children and grandchildren in that graph have been settled. This means your
-            /// current task will recompute less often, but it might also need to wait for
-            /// slower operations in the graph and can't continue with partial applied changes.
+            /// children and grandchildren in that graph have been settled. This also applies to
+            /// all tasks the read is forwarded to, and to the task owning the cell when a
+            /// resolved value is read. This means your current task will recompute less often,
+            /// but it might also need to wait for slower operations in the graph and can't
+            /// continue with partial applied changes.
             ///
             /// Reading strongly consistent is also far more expensive compared to normal
             /// reading, so it should be used with care.
@@ -569,6 +604,7 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         ) {
             #value_type_init_ident.get_or_init(|| {
                 let mut value = #new_value_type;
+                #register_deterministic_hash
                 f(&mut value);
                 value
             }).register(global_name);
@@ -10,7 +10,7 @@ use turbo_tasks_macros_shared::{
 };
 
 use crate::{
-    func::{gen_native_function_code, split_signature, SelfType},
+    func::{gen_native_function_code, split_signature, FunctionArguments, SelfType},
     util::*,
 };
 
@@ -90,7 +90,22 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut native_functions = Vec::new();
 
     for item in items.iter_mut() {
-        if let TraitItem::Method(TraitItemMethod { sig, default, .. }) = item {
+        if let TraitItem::Method(TraitItemMethod {
+            sig,
+            default,
+            attrs: method_attrs,
+            ..
+        }) = item
+        {
+            // Options of `#[turbo_tasks::function(...)]` apply to the default
+            // implementation
+            let options_code = method_attrs
+                .iter()
+                .find(|attr| is_attribute(attr, "function"))
+                .map(|attr| FunctionArguments::from_attribute(attr).native_function_options())
+                .unwrap_or_default();
+            method_attrs.retain(|attr| !is_attribute(attr, "function"));
+
             let Signature {
                 ident: method_ident,
                 inputs,
@@ -115,6 +130,7 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
                 inputs,
                 &output_type,
                 Some((&ref_ident, SelfType::ValueTrait)),
+                options_code,
             );
 
             trait_fns.push(quote! {
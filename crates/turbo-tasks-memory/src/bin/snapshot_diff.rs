@@ -0,0 +1,67 @@
+//! Compares two task graph snapshots and prints the task types that changed,
+//! including the changes of their references to other task types.
+//!
+//! Usage: turbo-tasks-snapshot-diff <before> <after> [--sort
+//! memory|count|duration]
+
+use std::{cmp::Reverse, fs::File, io::BufReader, path::Path};
+
+use anyhow::{bail, Context, Result};
+use turbo_tasks_memory::snapshot::Snapshot;
+
+fn read_snapshot(path: &Path) -> Result<Snapshot> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    Snapshot::read(BufReader::new(file)).with_context(|| format!("reading {}", path.display()))
+}
+
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:+.1}{}", value, UNITS[unit])
+}
+
+fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (paths, sort) = match args.as_slice() {
+        [before, after] => ([before, after], "memory"),
+        [before, after, flag, sort] if flag == "--sort" => ([before, after], sort.as_str()),
+        _ => bail!(
+            "usage: turbo-tasks-snapshot-diff <before> <after> [--sort memory|count|duration]"
+        ),
+    };
+    let before = read_snapshot(Path::new(paths[0]))?;
+    let after = read_snapshot(Path::new(paths[1]))?;
+
+    let mut diffs = before.diff(&after);
+    match sort {
+        "memory" => {}
+        "count" => diffs.sort_by_key(|diff| Reverse(diff.count_change())),
+        "duration" => diffs.sort_by_key(|diff| Reverse(diff.duration_change())),
+        _ => bail!("unknown sort order {sort}, expected memory, count or duration"),
+    }
+
+    println!(
+        "{:>16} {:>12} {:>12} {:>12} {:>12}  task type",
+        "count", "cells", "memory", "duration", "edges"
+    );
+    for diff in diffs {
+        println!(
+            "{:>16} {:>+12} {:>12} {:>+10}ms {:>+12}  {}",
+            format!("{} -> {}", diff.before.count, diff.after.count),
+            diff.after.cells as i64 - diff.before.cells as i64,
+            format_bytes(diff.memory_change()),
+            diff.duration_change() / 1000,
+            diff.edge_count_change(),
+            diff.name
+        );
+        for ((ref_type, target), change) in diff.edge_changes() {
+            println!("{:>68}  {ref_type} -> {target}", format!("{change:+}"));
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,14 @@
 use std::{
     fmt::Debug,
-    mem::{replace, take},
+    io::{self, Write},
+    mem::{replace, size_of_val, take},
 };
 
 use auto_hash_map::AutoSet;
 use turbo_tasks::{
     backend::CellContent,
     event::{Event, EventListener},
-    TaskId, TurboTasksBackendApi,
+    registry, SharedReference, TaskId, TurboTasksBackendApi,
 };
 
 #[derive(Default, Debug)]
@@ -55,6 +56,28 @@ impl Cell {
         }
     }
 
+    /// Estimated memory used by the value of the cell. That's the serialized
+    /// size for serializable values, which approximates the memory behind
+    /// pointers, and the size of the value itself otherwise. Returns `None`
+    /// when the cell has no value.
+    pub fn value_size(&self) -> Option<usize> {
+        let Cell::Value {
+            content: CellContent(Some(SharedReference(ty, value))),
+            ..
+        } = self else {
+            return None;
+        };
+        let shallow_size = size_of_val(&**value);
+        let serializable =
+            ty.and_then(|ty| registry::get_value_type(ty).any_as_serializable(value));
+        let serialized_size = serializable.and_then(|serializable| {
+            let mut counter = ByteCounter(0);
+            serde_json::to_writer(&mut counter, serializable).ok()?;
+            Some(counter.0)
+        });
+        Some(serialized_size.map_or(shallow_size, |size| size.max(shallow_size)))
+    }
+
     /// Removes a task from the list of dependent tasks.
     pub fn remove_dependent_task(&mut self, task: TaskId) {
         match self {
@@ -310,3 +333,17 @@ impl Cell {
         }
     }
 }
+
+/// Counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
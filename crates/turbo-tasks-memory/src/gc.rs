@@ -49,7 +49,7 @@ pub enum GcPriority {
 }
 
 /// Statistics about actions performed during garbage collection.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct GcStats {
     /// How many tasks were unloaded.
     pub unloaded: usize,
@@ -70,6 +70,59 @@ pub struct GcStats {
     pub no_gc_possible: usize,
 }
 
+impl GcStats {
+    fn add(&mut self, other: &GcStats) {
+        self.unloaded += other.unloaded;
+        self.empty_unused += other.empty_unused;
+        self.empty_unused_fast += other.empty_unused_fast;
+        self.empty_cells += other.empty_cells;
+        self.priority_updated += other.priority_updated;
+        self.priority_updated_fast += other.priority_updated_fast;
+        self.no_gc_needed += other.no_gc_needed;
+        self.no_gc_possible += other.no_gc_possible;
+    }
+}
+
+/// Aggregated statistics about garbage collection runs. Only recorded with
+/// full stats enabled, see [turbo_tasks::StatsType].
+#[derive(Default, Debug, Clone)]
+pub struct GcReport {
+    /// How many garbage collection runs processed tasks.
+    pub runs: usize,
+    /// How many of these runs were escalated, since the memory limit was
+    /// exceeded.
+    pub escalated_runs: usize,
+    /// How many tasks were processed.
+    pub tasks: usize,
+    /// The lowest priority that has been processed.
+    pub lowest_priority: Option<GcPriority>,
+    /// How many bytes were freed, as reported by the allocator. This is an
+    /// approximation since other threads allocate concurrently.
+    pub freed_bytes: usize,
+    /// The actions performed during all runs.
+    pub stats: GcStats,
+}
+
+impl GcReport {
+    pub(crate) fn record(
+        &mut self,
+        escalated: bool,
+        priority: GcPriority,
+        tasks: usize,
+        freed_bytes: usize,
+        stats: &GcStats,
+    ) {
+        self.runs += 1;
+        if escalated {
+            self.escalated_runs += 1;
+        }
+        self.tasks += tasks;
+        self.lowest_priority = self.lowest_priority.max(Some(priority));
+        self.freed_bytes += freed_bytes;
+        self.stats.add(stats);
+    }
+}
+
 /// State about garbage collection for a task.
 #[derive(Debug, Default)]
 pub struct GcTaskState {
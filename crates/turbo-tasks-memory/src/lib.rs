@@ -14,12 +14,16 @@ mod gc;
 mod map_guard;
 mod memory_backend;
 mod memory_backend_with_pg;
+mod memory_limit;
 mod output;
 mod priority_pair;
 pub mod scope;
+pub mod snapshot;
 pub mod stats;
 mod task;
 pub mod viz;
 
-pub use memory_backend::MemoryBackend;
+pub use gc::{GcPriority, GcReport, GcStats};
+pub use memory_backend::{CancellationStats, MemoryBackend};
 pub use memory_backend_with_pg::MemoryBackendWithPersistedGraph;
+pub use memory_limit::{cgroup_memory_limit, default_memory_limit};
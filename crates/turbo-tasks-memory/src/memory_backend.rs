@@ -7,7 +7,7 @@ use std::{
     hash::BuildHasherDefault,
     pin::Pin,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     time::{Duration, Instant},
@@ -15,9 +15,12 @@ use std::{
 
 use anyhow::{bail, Result};
 use auto_hash_map::AutoSet;
-use dashmap::{mapref::entry::Entry, DashMap};
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
+use nohash_hasher::BuildNoHashHasher;
+use parking_lot::Mutex;
 use rustc_hash::FxHasher;
 use tokio::task::futures::TaskLocalFuture;
+use turbo_malloc::TurboMalloc;
 use turbo_tasks::{
     backend::{
         Backend, BackendJobId, CellContent, PersistentTaskType, TaskExecutionSpec,
@@ -25,12 +28,12 @@ use turbo_tasks::{
     },
     event::EventListener,
     util::{IdFactory, NoMoveVec},
-    CellId, RawVc, TaskId, TraitTypeId, TurboTasksBackendApi,
+    CellId, ConcurrencyLimit, InvalidationReason, RawVc, TaskId, TraitTypeId, TurboTasksBackendApi,
 };
 
 use crate::{
     cell::RecomputingCell,
-    gc::GcQueue,
+    gc::{GcQueue, GcReport},
     output::Output,
     priority_pair::PriorityPair,
     scope::{TaskScope, TaskScopeId},
@@ -40,6 +43,17 @@ use crate::{
     },
 };
 
+/// Statistics about cancelled task executions.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancellationStats {
+    /// How many executions were cancelled since the task was invalidated
+    /// while executing.
+    pub invalidated: usize,
+    /// How many executions were cancelled since all scopes of the task became
+    /// inactive while executing.
+    pub inactive: usize,
+}
+
 pub struct MemoryBackend {
     memory_tasks: NoMoveVec<Task, 13>,
     memory_task_scopes: NoMoveVec<TaskScope>,
@@ -51,7 +65,13 @@ pub struct MemoryBackend {
     memory_limit: usize,
     gc_queue: Option<GcQueue>,
     idle_gc_active: AtomicBool,
+    gc_report: Mutex<GcReport>,
     scope_add_remove_priority: PriorityPair,
+    in_progress_tasks: DashSet<TaskId, BuildNoHashHasher<TaskId>>,
+    cancelled_invalidated: AtomicUsize,
+    cancelled_inactive: AtomicUsize,
+    /// A [Job::CancelInactiveExecutions] is scheduled and hasn't started yet
+    cancel_inactive_scheduled: AtomicBool,
 }
 
 impl Default for MemoryBackend {
@@ -79,7 +99,12 @@ impl MemoryBackend {
             memory_limit,
             gc_queue: (memory_limit != usize::MAX).then(|| GcQueue::new()),
             idle_gc_active: AtomicBool::new(false),
+            gc_report: Mutex::new(GcReport::default()),
             scope_add_remove_priority: PriorityPair::new(),
+            in_progress_tasks: DashSet::default(),
+            cancelled_invalidated: AtomicUsize::new(0),
+            cancelled_inactive: AtomicUsize::new(0),
+            cancel_inactive_scheduled: AtomicBool::new(false),
         }
     }
 
@@ -195,21 +220,84 @@ impl MemoryBackend {
         scope_id: TaskScopeId,
         task_id: TaskId,
         count: usize,
-        _turbo_tasks: &dyn TurboTasksBackendApi,
+        turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
         let mut queue = Vec::new();
-        self.with_scope(scope_id, |scope| {
-            if scope.state.lock().decrement_active_by(count, &mut queue) {
-                if let Some(gc_queue) = &self.gc_queue {
-                    gc_queue.task_might_become_inactive(task_id);
-                }
-            }
+        let became_inactive = self.with_scope(scope_id, |scope| {
+            scope.state.lock().decrement_active_by(count, &mut queue)
         });
+        if became_inactive {
+            if let Some(gc_queue) = &self.gc_queue {
+                gc_queue.task_might_become_inactive(task_id);
+            }
+        }
         while let Some(scope) = queue.pop() {
             self.with_scope(scope, |scope| {
                 scope.state.lock().decrement_active_by(count, &mut queue)
             });
         }
+        if became_inactive
+            && !self.in_progress_tasks.is_empty()
+            && !self.cancel_inactive_scheduled.swap(true, Ordering::AcqRel)
+        {
+            // The caller might hold locks of tasks, so cancelling is done in a separate
+            // job. Scopes often become inactive in bulk, so a single job
+            // handles all of them.
+            let job = self.create_backend_job(Job::CancelInactiveExecutions);
+            turbo_tasks.schedule_backend_background_job(job);
+        }
+    }
+
+    /// Cancels running executions of tasks which are not needed anymore since
+    /// all their scopes became inactive.
+    fn cancel_inactive_executions(&self, turbo_tasks: &dyn TurboTasksBackendApi) {
+        // Scopes that become inactive from now on need another job
+        self.cancel_inactive_scheduled
+            .store(false, Ordering::Release);
+        let tasks = self
+            .in_progress_tasks
+            .iter()
+            .map(|task| *task)
+            .collect::<Vec<_>>();
+        for task in tasks {
+            let cancelled = self.with_task(task, |task| {
+                task.cancel_execution_if_inactive(self, turbo_tasks)
+            });
+            if cancelled {
+                self.cancelled_inactive.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Cancels the running execution of a task which has been invalidated
+    /// while executing.
+    pub(crate) fn cancel_invalidated_execution(
+        &self,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        if turbo_tasks.cancel_task_execution(task) {
+            self.cancelled_invalidated.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The memory budget that garbage collection keeps the process in.
+    /// `usize::MAX` means that garbage collection is disabled.
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit
+    }
+
+    /// Returns statistics about garbage collection runs. They are only
+    /// recorded with full stats enabled.
+    pub fn gc_report(&self) -> GcReport {
+        self.gc_report.lock().clone()
+    }
+
+    pub fn cancellation_stats(&self) -> CancellationStats {
+        CancellationStats {
+            invalidated: self.cancelled_invalidated.load(Ordering::Relaxed),
+            inactive: self.cancelled_inactive.load(Ordering::Relaxed),
+        }
     }
 
     pub fn on_task_might_become_inactive(&self, task: TaskId) {
@@ -230,7 +318,7 @@ impl MemoryBackend {
 
             let mem_limit = self.memory_limit;
 
-            let usage = turbo_malloc::TurboMalloc::memory_usage();
+            let usage = TurboMalloc::memory_usage();
             let target = if idle {
                 mem_limit * 3 / 4
             } else {
@@ -244,15 +332,34 @@ impl MemoryBackend {
                 return;
             }
 
+            // Escalate when the memory limit is exceeded. Below the limit only a small
+            // share of the queue is processed, which mostly empties unused cells. Above
+            // the limit the whole queue can be processed, which also unloads tasks and
+            // empties used cells.
+            let escalated = usage >= mem_limit;
+            let max_collect_factor = if escalated {
+                u8::MAX
+            } else {
+                MAX_COLLECT_FACTOR
+            };
             let collect_factor = min(
-                MAX_COLLECT_FACTOR as usize,
+                max_collect_factor as usize,
                 (usage - target) * u8::MAX as usize / (mem_limit - target),
             ) as u8;
 
             let collected = gc_queue.run_gc(collect_factor, self, turbo_tasks);
 
+            if let Some((priority, count, stats)) = &collected {
+                if turbo_tasks.stats_type().is_full() {
+                    let freed_bytes = usage.saturating_sub(TurboMalloc::memory_usage());
+                    self.gc_report
+                        .lock()
+                        .record(escalated, *priority, *count, freed_bytes, stats);
+                }
+            }
+
             if idle {
-                if let Some((_collected, _count, _stats)) = collected {
+                if collected.is_some() {
                     let job = self.create_backend_job(Job::GarbageCollection);
                     turbo_tasks.schedule_backend_background_job(job);
                 } else {
@@ -276,21 +383,53 @@ impl Backend for MemoryBackend {
     }
 
     fn invalidate_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {
-        self.with_task(task, |task| task.invalidate(self, turbo_tasks));
+        self.with_task(task, |task| task.invalidate(None, self, turbo_tasks));
     }
 
     fn invalidate_tasks(&self, tasks: Vec<TaskId>, turbo_tasks: &dyn TurboTasksBackendApi) {
         for task in tasks.into_iter() {
             self.with_task(task, |task| {
-                task.invalidate(self, turbo_tasks);
+                task.invalidate(None, self, turbo_tasks);
             });
         }
     }
 
+    fn invalidate_task_with_reason(
+        &self,
+        task: TaskId,
+        reason: Arc<InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.with_task(task, |task| {
+            task.invalidate(Some(reason), self, turbo_tasks)
+        });
+    }
+
+    fn invalidate_tasks_with_reason(
+        &self,
+        tasks: Vec<TaskId>,
+        reason: Arc<InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        for task in tasks.into_iter() {
+            self.with_task(task, |task| {
+                task.invalidate(Some(reason.clone()), self, turbo_tasks);
+            });
+        }
+    }
+
+    fn get_invalidation_reason(&self, task: TaskId) -> Option<Arc<InvalidationReason>> {
+        self.with_task(task, |task| task.invalidation_reason())
+    }
+
     fn get_task_description(&self, task: TaskId) -> String {
         self.with_task(task, |task| task.get_description())
     }
 
+    fn get_task_concurrency_limit(&self, task: TaskId) -> Option<&'static ConcurrencyLimit> {
+        self.with_task(task, |task| task.get_concurrency_limit())
+    }
+
     type ExecutionScopeFuture<T: Future<Output = Result<()>> + Send + 'static> =
         TaskLocalFuture<RefCell<AutoSet<TaskDependency>>, T>;
     fn execution_scope<T: Future<Output = Result<()>> + Send + 'static>(
@@ -306,7 +445,11 @@ impl Backend for MemoryBackend {
         task: TaskId,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> Option<TaskExecutionSpec> {
-        self.with_task(task, |task| task.execute(self, turbo_tasks))
+        let execution = self.with_task(task, |task| task.execute(self, turbo_tasks));
+        if execution.is_some() {
+            self.in_progress_tasks.insert(task);
+        }
+        execution
     }
 
     fn task_execution_result(
@@ -326,8 +469,10 @@ impl Backend for MemoryBackend {
         duration: Duration,
         instant: Instant,
         stateful: bool,
+        _volatile: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool {
+        self.in_progress_tasks.remove(&task_id);
         let reexecute = self.with_task(task_id, |task| {
             task.execution_completed(duration, instant, stateful, self, turbo_tasks)
         });
@@ -599,6 +744,9 @@ pub(crate) enum Job {
     /// are done.
     UnloadRootScope(TaskScopeId),
     GarbageCollection,
+    /// Cancels running executions of tasks that became inactive. Scheduled
+    /// when a scope became inactive.
+    CancelInactiveExecutions,
 }
 
 impl Job {
@@ -670,6 +818,9 @@ impl Job {
             Job::GarbageCollection => {
                 backend.run_gc(true, turbo_tasks);
             }
+            Job::CancelInactiveExecutions => {
+                backend.cancel_inactive_executions(turbo_tasks);
+            }
         }
     }
 }
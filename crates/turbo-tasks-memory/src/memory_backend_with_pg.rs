@@ -1,12 +1,12 @@
 use std::{
     borrow::Cow,
-    collections::{BinaryHeap, HashMap},
+    collections::{BinaryHeap, HashMap, HashSet},
     fmt::Debug,
     future::Future,
     mem::{replace, take},
     pin::Pin,
     sync::{
-        atomic::{AtomicU32, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
         Mutex, MutexGuard,
     },
     time::{Duration, Instant},
@@ -27,7 +27,7 @@ use turbo_tasks::{
         PersistedGraphApi, ReadTaskState, TaskCell, TaskData,
     },
     util::{IdFactory, NoMoveVec, SharedError},
-    CellId, RawVc, TaskId, TraitTypeId, TurboTasksBackendApi,
+    CellId, ConcurrencyLimit, RawVc, TaskId, TraitTypeId, TurboTasksBackendApi,
 };
 
 type RootTaskFn =
@@ -64,16 +64,48 @@ impl Default for TaskFreshness {
     }
 }
 
+/// Collectibles emitted or unemitted by a single task.
+#[derive(Default, Debug, PartialEq, Eq)]
+struct Collectibles {
+    emitted: AutoSet<(TraitTypeId, RawVc)>,
+    unemitted: AutoSet<(TraitTypeId, RawVc)>,
+}
+
+/// Collectibles of a task and all its children, cached on that task.
+#[derive(Debug)]
+struct AggregatedCollectibles {
+    collectibles: AutoSet<RawVc>,
+    /// The tasks of the subgraph the collectibles were aggregated from
+    tasks: AutoSet<TaskId>,
+}
+
 #[derive(Debug)]
 struct MemoryTaskState {
     need_persist: bool,
     has_changes: bool,
+    /// The task used an Invalidator during its last execution
+    volatile: bool,
+    /// The task marked itself as stateful during its last execution
+    stateful: bool,
     freshness: TaskFreshness,
     cells: HashMap<CellId, (TaskCell, AutoSet<TaskId>)>,
     output: Option<Result<RawVc, SharedError>>,
     output_dependent: AutoSet<TaskId>,
     dependencies: AutoSet<RawVc>,
     children: AutoSet<TaskId>,
+    collectibles: Collectibles,
+    /// Tasks that have read collectibles from this task or its children
+    collectibles_dependent: AutoSet<TaskId>,
+    /// Children and collectibles before the current execution started,
+    /// to detect changes to the collectibles of this subgraph.
+    before_execution: Option<(AutoSet<TaskId>, Collectibles)>,
+    /// Cached collectibles of this task and its children by trait
+    aggregated_collectibles: HashMap<TraitTypeId, AggregatedCollectibles>,
+    /// Incremented whenever `aggregated_collectibles` is invalidated, to
+    /// avoid caching an aggregation that raced with an invalidation.
+    aggregated_collectibles_generation: u32,
+    /// Tasks that have aggregated the collectibles of this task
+    aggregated_in: AutoSet<TaskId>,
     event: Event,
     event_cells: Event,
 }
@@ -84,11 +116,19 @@ impl MemoryTaskState {
             freshness,
             need_persist: Default::default(),
             has_changes: Default::default(),
+            volatile: Default::default(),
+            stateful: Default::default(),
             cells: Default::default(),
             output: Default::default(),
             output_dependent: Default::default(),
             dependencies: Default::default(),
             children: Default::default(),
+            collectibles: Default::default(),
+            collectibles_dependent: Default::default(),
+            before_execution: Default::default(),
+            aggregated_collectibles: Default::default(),
+            aggregated_collectibles_generation: Default::default(),
+            aggregated_in: Default::default(),
             event: Event::new(move || format!("MemoryTaskState({task})::event")),
             event_cells: Event::new(move || format!("MemoryTaskState({task})::event_cells")),
         }
@@ -152,6 +192,12 @@ pub struct MemoryBackendWithPersistedGraph<P: PersistedGraph> {
     partial_lookup: turbo_tasks::util::OnceConcurrentlyMap<PersistentTaskType, bool>,
     #[cfg(not(feature = "unsafe_once_map"))]
     partial_lookup: turbo_tasks::util::SafeOnceConcurrentlyMap<PersistentTaskType, bool>,
+    /// Stateful tasks in memory. Their cells can change without re-execution,
+    /// so they are persisted again when idle and on stop.
+    stateful_tasks: DashSet<TaskId>,
+    /// External keep alives of the previous session has been removed
+    outdated_externally_active_removed: AtomicBool,
+    executed_tasks: AtomicUsize,
 
     #[cfg(feature = "log_running_tasks")]
     in_progress_tasks: Mutex<HashSet<TaskId>>,
@@ -179,11 +225,21 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
             partial_lookup: turbo_tasks::util::OnceConcurrentlyMap::new(),
             #[cfg(not(feature = "unsafe_once_map"))]
             partial_lookup: turbo_tasks::util::SafeOnceConcurrentlyMap::new(),
+            stateful_tasks: DashSet::new(),
+            outdated_externally_active_removed: AtomicBool::new(false),
+            executed_tasks: AtomicUsize::new(0),
             #[cfg(feature = "log_running_tasks")]
             in_progress_tasks: Mutex::new(HashSet::new()),
         }
     }
 
+    /// Returns the number of task executions since the backend was created.
+    /// Tasks that are restored from the persisted graph don't count unless they
+    /// need to be executed again.
+    pub fn executed_tasks(&self) -> usize {
+        self.executed_tasks.load(Ordering::Relaxed)
+    }
+
     fn state_mut(
         &self,
         task: TaskId,
@@ -277,8 +333,19 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                     output_dependent: AutoSet::new(),
                     dependencies: data.dependencies.into_iter().collect(),
                     children: data.children.into_iter().collect(),
+                    collectibles: Collectibles {
+                        emitted: data.emitted_collectibles.into_iter().collect(),
+                        unemitted: data.unemitted_collectibles.into_iter().collect(),
+                    },
+                    collectibles_dependent: AutoSet::new(),
+                    before_execution: None,
+                    aggregated_collectibles: HashMap::new(),
+                    aggregated_collectibles_generation: 0,
+                    aggregated_in: AutoSet::new(),
                     need_persist: Default::default(),
                     has_changes: Default::default(),
+                    volatile: Default::default(),
+                    stateful: data.stateful,
                     event: Event::new(move || format!("MemoryTaskState({task})::event")),
                     event_cells: Event::new(move || {
                         format!("MemoryTaskState({task})::event_cells")
@@ -299,6 +366,9 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                     delayed_activate.push(task);
                 }
                 task_state.persisted_to_mem_active = state.keeps_external_active;
+                if mem_state.stateful {
+                    self.stateful_tasks.insert(task);
+                }
                 task_state.memory = Some(mem_state);
                 if !state.clean && task_state.active && !task_state.scheduled {
                     task_state.scheduled = true;
@@ -755,6 +825,9 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                                 ref children,
                                 ref dependencies,
                                 ref cells,
+                                ref collectibles,
+                                ref volatile,
+                                ref stateful,
                                 ..
                             }),
                         ..
@@ -792,12 +865,24 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                                                 .map(|(k, (s, _))| (*k, s.clone()))
                                                 .collect(),
                                             output: *output,
+                                            emitted_collectibles: collectibles
+                                                .emitted
+                                                .iter()
+                                                .copied()
+                                                .collect(),
+                                            unemitted_collectibles: collectibles
+                                                .unemitted
+                                                .iter()
+                                                .copied()
+                                                .collect(),
+                                            stateful: *stateful,
                                         };
                                         let externally_active =
                                             task_info.active_parents.load(Ordering::Acquire) > 0;
                                         let task_state =
                                             turbo_tasks::persisted_graph::PersistTaskState {
                                                 externally_active,
+                                                volatile: *volatile,
                                             };
                                         if let Some(PersistResult {
                                             tasks_to_activate,
@@ -869,6 +954,33 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
         }
     }
 
+    /// Queues all stateful tasks for persisting, since their state might have
+    /// changed without re-executing them.
+    fn queue_stateful_tasks_for_persisting(&self, turbo_tasks: &dyn TurboTasksBackendApi) -> usize {
+        let mut count = 0;
+        let tasks = self.stateful_tasks.iter().map(|t| *t).collect::<Vec<_>>();
+        for task in tasks {
+            let (mut state, _) = self.state_mut(task, turbo_tasks);
+            if let Some(MemoryTaskState {
+                freshness: TaskFreshness::Done,
+                ref mut need_persist,
+                ref mut has_changes,
+                ..
+            }) = state.memory
+            {
+                *need_persist = true;
+                *has_changes = true;
+                drop(state);
+                self.need_persisting.insert(task);
+                if self.persist_queue1_queued.insert(task) {
+                    self.persist_queue1.push(task).unwrap();
+                }
+                count += 1;
+            }
+        }
+        count
+    }
+
     fn has_persist_work(&self) -> bool {
         !self.persist_queue1.is_empty()
             || self
@@ -900,6 +1012,81 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
             }
         }
     }
+
+    /// Collects the collectibles of `root` and all its children. Returns a
+    /// listener when a task of the subgraph isn't executed yet.
+    fn aggregate_collectibles(
+        &self,
+        root: TaskId,
+        trait_id: TraitTypeId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<AggregatedCollectibles, EventListener> {
+        let mut counts: HashMap<RawVc, i32> = HashMap::new();
+        let mut visited = AutoSet::new();
+        let mut queue = vec![root];
+        visited.insert(root);
+        while let Some(task) = queue.pop() {
+            let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+            let TaskState {
+                ref mut scheduled,
+                ref mut memory,
+                ..
+            } = *state;
+            let mem_state = memory.as_mut().unwrap();
+            if mem_state.freshness != TaskFreshness::Done {
+                let listener = mem_state.event.listen();
+                if !*scheduled {
+                    *scheduled = true;
+                    #[cfg(feature = "log_scheduled_tasks")]
+                    println!("schedule({task}) in aggregate_collectibles");
+                    turbo_tasks.schedule(task);
+                }
+                return Err(listener);
+            }
+            mem_state.aggregated_in.insert(root);
+            for (emitted_trait_id, collectible) in mem_state.collectibles.emitted.iter() {
+                if *emitted_trait_id == trait_id {
+                    *counts.entry(*collectible).or_default() += 1;
+                }
+            }
+            for (unemitted_trait_id, collectible) in mem_state.collectibles.unemitted.iter() {
+                if *unemitted_trait_id == trait_id {
+                    *counts.entry(*collectible).or_default() -= 1;
+                }
+            }
+            for child in mem_state.children.iter() {
+                if visited.insert(*child) {
+                    queue.push(*child);
+                }
+            }
+        }
+        Ok(AggregatedCollectibles {
+            collectibles: counts
+                .into_iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(collectible, _)| collectible)
+                .collect(),
+            tasks: visited,
+        })
+    }
+
+    /// Drops the collectibles cached on `root` after the collectibles or
+    /// children of a task in its subgraph changed and notifies their readers.
+    fn invalidate_aggregated_collectibles(
+        &self,
+        root: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        let (mut state, _) = self.mem_state_mut(root, turbo_tasks);
+        let mem_state = state.memory.as_mut().unwrap();
+        mem_state.aggregated_collectibles.clear();
+        mem_state.aggregated_collectibles_generation += 1;
+        let dependent = take(&mut mem_state.collectibles_dependent);
+        drop(state);
+        if !dependent.is_empty() {
+            turbo_tasks.schedule_notify_tasks_set(&dependent);
+        }
+    }
 }
 
 impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
@@ -971,9 +1158,36 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             }
             self.schedule_background_job(BackgroundJob::DeactivatePersisted(task), turbo_tasks);
         }
+        // Invalidators don't survive a restart. Volatile tasks need to be executed
+        // again to validate them against the current external state, e.g. files
+        // that changed while the process was offline.
+        for task in self.pg_get_volatile_tasks(turbo_tasks) {
+            self.invalidate_task(task, turbo_tasks);
+        }
+    }
+
+    fn idle_start(&self, turbo_tasks: &dyn TurboTasksBackendApi) {
+        // The first idle phase marks the end of the initial build. All tasks that are
+        // still needed by the memory graph have renewed their external keep alive.
+        if !self
+            .outdated_externally_active_removed
+            .swap(true, Ordering::AcqRel)
+        {
+            for task in self.pg_remove_outdated_externally_active(turbo_tasks) {
+                self.schedule_background_job(BackgroundJob::DeactivatePersisted(task), turbo_tasks);
+            }
+        }
+        let count = self.queue_stateful_tasks_for_persisting(turbo_tasks);
+        if count > 0 {
+            self.increase_persist_workers(count, turbo_tasks);
+        }
     }
 
     fn stop(&self, turbo_tasks: &dyn TurboTasksBackendApi) {
+        // Background jobs are no longer executed at this point,
+        // so the final state of stateful tasks is persisted here.
+        self.queue_stateful_tasks_for_persisting(turbo_tasks);
+        while self.persist(turbo_tasks) {}
         self.pg_stop(turbo_tasks);
     }
 
@@ -1011,6 +1225,14 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         format!("{:?}", task_info.task_type)
     }
 
+    fn get_task_concurrency_limit(&self, task: TaskId) -> Option<&'static ConcurrencyLimit> {
+        let task_info = self.tasks.get(*task).unwrap();
+        match &task_info.task_type {
+            TaskType::Persistent(ty) => ty.concurrency_limit(),
+            TaskType::Root(_) | TaskType::Once(_) => None,
+        }
+    }
+
     type ExecutionScopeFuture<T: Future<Output = Result<()>> + Send + 'static> = T;
     fn execution_scope<T: Future<Output = Result<()>> + Send + 'static>(
         &self,
@@ -1037,9 +1259,14 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             t.insert(task);
             println!("start {} {:?}", task, task_info.task_type);
         }
+        self.executed_tasks.fetch_add(1, Ordering::Relaxed);
         mem_state.freshness = TaskFreshness::NeverExecuted;
         let deps = take(&mut mem_state.dependencies);
         let children = take(&mut mem_state.children);
+        let collectibles = take(&mut mem_state.collectibles);
+        if mem_state.before_execution.is_none() {
+            mem_state.before_execution = Some((children.clone(), collectibles));
+        }
         drop(state);
         for dep in deps {
             let (mut state, _) = self.mem_state_mut(dep.get_task_id(), turbo_tasks);
@@ -1047,6 +1274,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             match dep {
                 RawVc::TaskOutput(_) => {
                     mem_state.output_dependent.remove(&task);
+                    mem_state.collectibles_dependent.remove(&task);
                 }
                 RawVc::TaskCell(_, i) => {
                     if let Some((_, dependent)) = mem_state.cells.get_mut(&i) {
@@ -1110,7 +1338,8 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         task: TaskId,
         duration: Duration,
         _instant: Instant,
-        _stateful: bool,
+        stateful: bool,
+        volatile: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool {
         #[cfg(feature = "log_running_tasks")]
@@ -1136,6 +1365,27 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         mem_state.event.notify(usize::MAX);
         mem_state.event_cells.notify(usize::MAX);
         mem_state.need_persist = true;
+        if mem_state.volatile != volatile {
+            mem_state.volatile = volatile;
+            mem_state.has_changes = true;
+        }
+        if mem_state.stateful != stateful {
+            mem_state.stateful = stateful;
+            mem_state.has_changes = true;
+        }
+        if stateful {
+            self.stateful_tasks.insert(task);
+        } else {
+            self.stateful_tasks.remove(&task);
+        }
+        let mut aggregated_in = AutoSet::new();
+        if let Some((children, collectibles)) = mem_state.before_execution.take() {
+            if children != mem_state.children || collectibles != mem_state.collectibles {
+                // Collectibles aggregated from this task might have changed
+                mem_state.has_changes = true;
+                aggregated_in = take(&mut mem_state.aggregated_in);
+            }
+        }
         let has_changes = mem_state.has_changes;
         let is_persisted = persisted.is_some();
         let is_dirty_persisted = persisted
@@ -1144,6 +1394,10 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             .unwrap_or_default();
         drop(state);
 
+        for root in aggregated_in {
+            self.invalidate_aggregated_collectibles(root, turbo_tasks);
+        }
+
         if let TaskType::Persistent(_) = task_info.task_type {
             if has_changes && (is_persisted || !self.only_known_to_memory_tasks.contains(&task)) {
                 for task in self.pg_make_dependent_dirty(RawVc::TaskOutput(task), turbo_tasks) {
@@ -1397,32 +1651,82 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
 
     fn try_read_task_collectibles(
         &self,
-        _task: TaskId,
-        _trait_id: TraitTypeId,
-        _reader: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi,
+        task: TaskId,
+        trait_id: TraitTypeId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> Result<Result<AutoSet<RawVc>, EventListener>> {
-        todo!()
+        let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+        let mem_state = state.memory.as_mut().unwrap();
+        // The reader is notified when the collectibles of any task in the
+        // subgraph change, see `invalidate_aggregated_collectibles`
+        mem_state.collectibles_dependent.insert(reader);
+        let generation = mem_state.aggregated_collectibles_generation;
+        let cached = mem_state
+            .aggregated_collectibles
+            .get(&trait_id)
+            .map(|aggregated| (aggregated.collectibles.clone(), aggregated.tasks.clone()));
+        drop(state);
+
+        let (collectibles, tasks) = match cached {
+            Some(cached) => cached,
+            None => {
+                let aggregated = match self.aggregate_collectibles(task, trait_id, turbo_tasks) {
+                    Ok(aggregated) => aggregated,
+                    Err(listener) => return Ok(Err(listener)),
+                };
+                let result = (aggregated.collectibles.clone(), aggregated.tasks.clone());
+                let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+                let mem_state = state.memory.as_mut().unwrap();
+                if mem_state.aggregated_collectibles_generation == generation {
+                    mem_state
+                        .aggregated_collectibles
+                        .insert(trait_id, aggregated);
+                }
+                result
+            }
+        };
+
+        // The output dependencies make the persisted reader dirty when the
+        // collectibles of these tasks change
+        let (mut state, _) = self.mem_state_mut(reader, turbo_tasks);
+        let mem_state = state.memory.as_mut().unwrap();
+        mem_state
+            .dependencies
+            .extend(tasks.into_iter().map(RawVc::TaskOutput));
+        Ok(Ok(collectibles))
     }
 
     fn emit_collectible(
         &self,
-        _trait_id: TraitTypeId,
-        _collectible: RawVc,
-        _task: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi,
+        trait_id: TraitTypeId,
+        collectible: RawVc,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
-        todo!()
+        // Readers are notified when the execution of the task has completed
+        let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+        let mem_state = state.memory.as_mut().unwrap();
+        mem_state
+            .collectibles
+            .emitted
+            .insert((trait_id, collectible));
     }
 
     fn unemit_collectible(
         &self,
-        _trait_id: TraitTypeId,
-        _collectible: RawVc,
-        _task: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi,
+        trait_id: TraitTypeId,
+        collectible: RawVc,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
-        todo!()
+        // Readers are notified when the execution of the task has completed
+        let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+        let mem_state = state.memory.as_mut().unwrap();
+        mem_state
+            .collectibles
+            .unemitted
+            .insert((trait_id, collectible));
     }
 
     fn update_task_cell(
@@ -1786,8 +2090,6 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
             .unwrap()
     }
 
-    // FIXME
-    #[allow(dead_code)]
     #[must_use]
     fn pg_remove_outdated_externally_active(
         &self,
@@ -1821,6 +2123,16 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
             .unwrap()
     }
 
+    #[must_use]
+    fn pg_get_volatile_tasks(&self, turbo_tasks: &dyn TurboTasksBackendApi) -> Vec<TaskId> {
+        self.pg
+            .get_volatile_tasks(&MemoryBackendPersistedGraphApi {
+                backend: self,
+                turbo_tasks,
+            })
+            .unwrap()
+    }
+
     #[must_use]
     fn pg_get_pending_active_update(
         &self,
@@ -0,0 +1,184 @@
+/// Share of a container memory limit that is used as memory budget. The rest
+/// is left for other processes in the container, e. g. Node.js workers, and
+/// for memory that isn't tracked by the allocator.
+const CONTAINER_LIMIT_SHARE_PERCENT: usize = 75;
+
+/// A memory budget for the [crate::MemoryBackend] derived from the cgroup
+/// memory limit when running in a container with a limit and unlimited
+/// otherwise.
+pub fn default_memory_limit() -> usize {
+    cgroup_memory_limit().map_or(usize::MAX, |limit| {
+        limit / 100 * CONTAINER_LIMIT_SHARE_PERCENT
+    })
+}
+
+/// Returns the memory limit of the cgroup of the current process. Supports
+/// cgroup v2 and v1.
+#[cfg(target_os = "linux")]
+pub fn cgroup_memory_limit() -> Option<usize> {
+    use std::path::Path;
+
+    let cgroup = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    let limit = linux::cgroup_memory_limit(&cgroup, Path::new("/sys/fs/cgroup"), |path| {
+        std::fs::read_to_string(path).ok()
+    })?;
+    usize::try_from(limit).ok()
+}
+
+/// Returns the memory limit of the cgroup of the current process. Supports
+/// cgroup v2 and v1.
+#[cfg(not(target_os = "linux"))]
+pub fn cgroup_memory_limit() -> Option<usize> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::path::Path;
+
+    /// Limits above this are treated as unlimited. cgroup v1 reports a page
+    /// aligned `i64::MAX` when no limit is set.
+    const UNLIMITED_THRESHOLD: u64 = 1 << 60;
+
+    /// `cgroup` is the content of `/proc/self/cgroup` and `root` the mount
+    /// point of the cgroup filesystem. `read` returns the content of a file
+    /// in the cgroup filesystem.
+    pub fn cgroup_memory_limit(
+        cgroup: &str,
+        root: &Path,
+        read: impl Fn(&Path) -> Option<String>,
+    ) -> Option<u64> {
+        cgroup_v2_limit(cgroup, root, &read).or_else(|| cgroup_v1_limit(cgroup, root, &read))
+    }
+
+    /// cgroup v2 is listed as a `0::<path>` line.
+    fn cgroup_v2_limit(
+        cgroup: &str,
+        root: &Path,
+        read: &impl Fn(&Path) -> Option<String>,
+    ) -> Option<u64> {
+        let path = cgroup.lines().find_map(|line| line.strip_prefix("0::"))?;
+        min_limit(root, path, "memory.max", read)
+    }
+
+    /// cgroup v1 lists the memory controller as a `<id>:<controllers>:<path>`
+    /// line and mounts it in a separate directory.
+    fn cgroup_v1_limit(
+        cgroup: &str,
+        root: &Path,
+        read: &impl Fn(&Path) -> Option<String>,
+    ) -> Option<u64> {
+        let path = cgroup.lines().find_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let _id = parts.next()?;
+            let controllers = parts.next()?;
+            let path = parts.next()?;
+            controllers
+                .split(',')
+                .any(|controller| controller == "memory")
+                .then_some(path)
+        })?;
+        min_limit(&root.join("memory"), path, "memory.limit_in_bytes", read)
+    }
+
+    /// Limits of parent cgroups apply too, so this returns the smallest limit
+    /// of the cgroup and its parents.
+    fn min_limit(
+        root: &Path,
+        cgroup: &str,
+        file: &str,
+        read: &impl Fn(&Path) -> Option<String>,
+    ) -> Option<u64> {
+        let dir = root.join(cgroup.trim_start_matches('/'));
+        dir.ancestors()
+            .take_while(|dir| dir.starts_with(root))
+            .filter_map(|dir| parse_limit(&read(&dir.join(file))?))
+            .min()
+    }
+
+    fn parse_limit(content: &str) -> Option<u64> {
+        // cgroup v2 reports "max" when no limit is set, which doesn't parse
+        let limit = content.trim().parse::<u64>().ok()?;
+        (limit < UNLIMITED_THRESHOLD).then_some(limit)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::{collections::HashMap, path::Path};
+
+        use super::cgroup_memory_limit;
+
+        fn limit(cgroup: &str, files: &[(&str, &str)]) -> Option<u64> {
+            let files = files
+                .iter()
+                .map(|(path, content)| (Path::new(path).to_path_buf(), content.to_string()))
+                .collect::<HashMap<_, _>>();
+            cgroup_memory_limit(cgroup, Path::new("/sys/fs/cgroup"), |path| {
+                files.get(path).cloned()
+            })
+        }
+
+        #[test]
+        fn cgroup_v2() {
+            assert_eq!(
+                limit(
+                    "0::/kubepods/pod1/container\n",
+                    &[
+                        ("/sys/fs/cgroup/kubepods/memory.max", "4294967296\n"),
+                        ("/sys/fs/cgroup/kubepods/pod1/container/memory.max", "max\n"),
+                        ("/sys/fs/cgroup/kubepods/pod1/memory.max", "2147483648\n"),
+                    ]
+                ),
+                Some(2147483648)
+            );
+        }
+
+        #[test]
+        fn cgroup_v2_max() {
+            assert_eq!(
+                limit(
+                    "0::/user.slice\n",
+                    &[
+                        ("/sys/fs/cgroup/memory.max", "max\n"),
+                        ("/sys/fs/cgroup/user.slice/memory.max", "max\n"),
+                    ]
+                ),
+                None
+            );
+        }
+
+        #[test]
+        fn cgroup_v1() {
+            assert_eq!(
+                limit(
+                    "12:cpu,cpuacct:/docker/abc\n11:memory:/docker/abc\n",
+                    &[
+                        (
+                            "/sys/fs/cgroup/memory/memory.limit_in_bytes",
+                            "9223372036854771712\n"
+                        ),
+                        (
+                            "/sys/fs/cgroup/memory/docker/abc/memory.limit_in_bytes",
+                            "536870912\n"
+                        ),
+                    ]
+                ),
+                Some(536870912)
+            );
+        }
+
+        #[test]
+        fn cgroup_v1_unlimited() {
+            assert_eq!(
+                limit(
+                    "11:memory:/\n",
+                    &[(
+                        "/sys/fs/cgroup/memory/memory.limit_in_bytes",
+                        "9223372036854771712\n"
+                    )]
+                ),
+                None
+            );
+        }
+    }
+}
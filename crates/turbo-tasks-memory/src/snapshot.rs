@@ -0,0 +1,361 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    io::{BufRead, Write},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{stats::ReferenceType, MemoryBackend};
+
+const HEADER: &str = "turbo-tasks-snapshot v2";
+
+/// A single task in a [Snapshot].
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct TaskSnapshot {
+    pub ty: String,
+    pub active: bool,
+    /// Estimated memory of the task, excluding the values of its cells
+    pub memory: usize,
+    /// Estimated memory of the value of each cell holding a value
+    pub cell_sizes: Vec<usize>,
+    /// Duration of the last execution
+    pub duration: Duration,
+}
+
+/// Aggregated information about all tasks of a type.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct TaskTypeSnapshot {
+    pub count: usize,
+    pub active_count: usize,
+    /// Number of cells holding a value
+    pub cells: usize,
+    /// Estimated memory, including the values of cells
+    pub memory: usize,
+    /// Sum of the durations of the last execution of each task
+    pub duration: Duration,
+    /// Number of references to other tasks, keyed by reference type and
+    /// target task type
+    pub edges: BTreeMap<(String, String), usize>,
+}
+
+/// A snapshot of the whole task graph with all tasks and the references
+/// between them. It can be written to a compact file and compared to another
+/// snapshot later, e. g. before and after a dependency upgrade.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Tasks keyed by task id. Ids are only meaningful within a snapshot.
+    pub tasks: BTreeMap<usize, TaskSnapshot>,
+    /// References between tasks as reference type, source and target task id
+    pub edges: BTreeSet<(String, usize, usize)>,
+}
+
+/// The change of a task type between two snapshots.
+#[derive(Clone, Debug)]
+pub struct TaskTypeDiff {
+    pub name: String,
+    pub before: TaskTypeSnapshot,
+    pub after: TaskTypeSnapshot,
+}
+
+fn reference_type_name(ty: ReferenceType) -> &'static str {
+    match ty {
+        ReferenceType::Child => "child",
+        ReferenceType::Dependency => "dependency",
+        ReferenceType::Input => "input",
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(field: Option<&str>, name: &str) -> Result<T> {
+    field
+        .with_context(|| format!("missing {name}"))?
+        .parse()
+        .ok()
+        .with_context(|| format!("invalid {name}"))
+}
+
+impl Snapshot {
+    /// Captures all tasks currently known to the backend.
+    pub fn capture(backend: &MemoryBackend) -> Self {
+        let mut snapshot = Snapshot::default();
+        backend.with_all_cached_tasks(|id| {
+            backend.with_task(id, |task| {
+                let info = task.get_stats_info(backend);
+                snapshot.tasks.insert(
+                    *id,
+                    TaskSnapshot {
+                        ty: task.get_stats_type().to_string(),
+                        active: info.active,
+                        memory: info.memory,
+                        cell_sizes: task.get_stats_cell_sizes(),
+                        duration: info.last_duration,
+                    },
+                );
+                for (ref_type, target) in task.get_stats_references().tasks {
+                    snapshot.edges.insert((
+                        reference_type_name(ref_type).to_string(),
+                        *id,
+                        *target,
+                    ));
+                }
+            })
+        });
+        snapshot
+    }
+
+    /// Writes the snapshot in a line based, tab separated format.
+    pub fn write(&self, mut writer: impl Write) -> Result<()> {
+        writeln!(writer, "{HEADER}")?;
+        for (id, task) in self.tasks.iter() {
+            let cell_sizes = task
+                .cell_sizes
+                .iter()
+                .map(|size| size.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            writeln!(
+                writer,
+                "task\t{}\t{}\t{}\t{}\t{}\t{}",
+                id,
+                task.ty,
+                task.active as u8,
+                task.memory,
+                task.duration.as_micros(),
+                cell_sizes
+            )?;
+        }
+        for (ref_type, source, target) in self.edges.iter() {
+            writeln!(writer, "edge\t{ref_type}\t{source}\t{target}")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [Snapshot::write].
+    pub fn read(reader: impl BufRead) -> Result<Self> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            bail!("not a task graph snapshot");
+        }
+        let mut snapshot = Snapshot::default();
+        for (i, line) in lines.enumerate() {
+            // Line numbers start at 1 and the header is skipped
+            snapshot
+                .read_line(&line?)
+                .with_context(|| format!("invalid snapshot in line {}", i + 2))?;
+        }
+        Ok(snapshot)
+    }
+
+    fn read_line(&mut self, line: &str) -> Result<()> {
+        let mut fields = line.split('\t');
+        match fields.next() {
+            Some("task") => {
+                let id = parse_field(fields.next(), "task id")?;
+                let task = TaskSnapshot {
+                    ty: parse_field(fields.next(), "task type")?,
+                    active: parse_field::<u8>(fields.next(), "active")? != 0,
+                    memory: parse_field(fields.next(), "memory")?,
+                    duration: Duration::from_micros(parse_field(fields.next(), "duration")?),
+                    cell_sizes: match fields.next() {
+                        Some("") | None => Vec::new(),
+                        Some(sizes) => sizes
+                            .split(',')
+                            .map(|size| parse_field(Some(size), "cell size"))
+                            .collect::<Result<_>>()?,
+                    },
+                };
+                self.tasks.insert(id, task);
+            }
+            Some("edge") => {
+                self.edges.insert((
+                    parse_field(fields.next(), "reference type")?,
+                    parse_field(fields.next(), "source")?,
+                    parse_field(fields.next(), "target")?,
+                ));
+            }
+            _ => bail!("unknown entry"),
+        }
+        Ok(())
+    }
+
+    /// Aggregates the tasks and their references by task type.
+    pub fn task_types(&self) -> BTreeMap<String, TaskTypeSnapshot> {
+        let mut task_types = BTreeMap::<String, TaskTypeSnapshot>::new();
+        for task in self.tasks.values() {
+            let stats = task_types.entry(task.ty.clone()).or_default();
+            stats.count += 1;
+            if task.active {
+                stats.active_count += 1;
+            }
+            stats.cells += task.cell_sizes.len();
+            stats.memory += task.memory + task.cell_sizes.iter().sum::<usize>();
+            stats.duration += task.duration;
+        }
+        for (ref_type, source, target) in self.edges.iter() {
+            // Edges to tasks that are not in the snapshot are skipped
+            if let (Some(source), Some(target)) = (self.tasks.get(source), self.tasks.get(target)) {
+                let stats = task_types.entry(source.ty.clone()).or_default();
+                *stats
+                    .edges
+                    .entry((ref_type.clone(), target.ty.clone()))
+                    .or_default() += 1;
+            }
+        }
+        task_types
+    }
+
+    /// Compares this snapshot with a later snapshot. Returns all task types
+    /// whose tasks or references changed, sorted by the largest growth in
+    /// memory first.
+    pub fn diff(&self, later: &Snapshot) -> Vec<TaskTypeDiff> {
+        let mut before = self.task_types();
+        let mut after = later.task_types();
+        let names = before
+            .keys()
+            .chain(after.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let mut diffs = names
+            .into_iter()
+            .filter_map(|name| {
+                let before = before.remove(&name).unwrap_or_default();
+                let after = after.remove(&name).unwrap_or_default();
+                (before != after).then_some(TaskTypeDiff {
+                    name,
+                    before,
+                    after,
+                })
+            })
+            .collect::<Vec<_>>();
+        diffs.sort_by_key(|diff| Reverse(diff.memory_change()));
+        diffs
+    }
+}
+
+impl TaskTypeDiff {
+    pub fn count_change(&self) -> i64 {
+        self.after.count as i64 - self.before.count as i64
+    }
+
+    pub fn memory_change(&self) -> i64 {
+        self.after.memory as i64 - self.before.memory as i64
+    }
+
+    pub fn duration_change(&self) -> i128 {
+        self.after.duration.as_micros() as i128 - self.before.duration.as_micros() as i128
+    }
+
+    /// The change of the number of references to other tasks.
+    pub fn edge_count_change(&self) -> i64 {
+        self.after.edges.values().sum::<usize>() as i64
+            - self.before.edges.values().sum::<usize>() as i64
+    }
+
+    /// The changes of the number of references by reference type and target
+    /// task type.
+    pub fn edge_changes(&self) -> Vec<((&str, &str), i64)> {
+        let keys = self
+            .before
+            .edges
+            .keys()
+            .chain(self.after.edges.keys())
+            .collect::<BTreeSet<_>>();
+        keys.into_iter()
+            .filter_map(|key| {
+                let before = self.before.edges.get(key).copied().unwrap_or_default();
+                let after = self.after.edges.get(key).copied().unwrap_or_default();
+                (before != after).then_some((
+                    (key.0.as_str(), key.1.as_str()),
+                    after as i64 - before as i64,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Snapshot, TaskSnapshot};
+
+    fn task(ty: &str, memory: usize, cell_sizes: Vec<usize>) -> TaskSnapshot {
+        TaskSnapshot {
+            ty: ty.to_string(),
+            active: true,
+            memory,
+            cell_sizes,
+            duration: Duration::from_micros(150),
+        }
+    }
+
+    fn snapshot(tasks: Vec<(usize, TaskSnapshot)>, edges: &[(&str, usize, usize)]) -> Snapshot {
+        Snapshot {
+            tasks: tasks.into_iter().collect(),
+            edges: edges
+                .iter()
+                .map(|&(ty, source, target)| (ty.to_string(), source, target))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = snapshot(
+            vec![
+                (1, task("parse", 100, vec![20, 30])),
+                (2, task("read", 50, vec![])),
+            ],
+            &[("dependency", 1, 2), ("child", 1, 2)],
+        );
+        let mut file = Vec::new();
+        snapshot.write(&mut file).unwrap();
+        assert_eq!(Snapshot::read(file.as_slice()).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn diff() {
+        let before = snapshot(
+            vec![
+                (1, task("parse", 100, vec![20])),
+                (2, task("read", 50, vec![10])),
+                (3, task("resolve", 10, vec![])),
+            ],
+            &[("dependency", 1, 2)],
+        );
+        // Task ids differ between sessions
+        let after = snapshot(
+            vec![
+                (10, task("parse", 100, vec![20])),
+                (11, task("read", 50, vec![10])),
+                (12, task("read", 50, vec![10])),
+                (13, task("resolve", 10, vec![])),
+            ],
+            &[
+                ("dependency", 10, 11),
+                ("dependency", 10, 12),
+                ("child", 13, 10),
+            ],
+        );
+        let diffs = before.diff(&after);
+        let names = diffs
+            .iter()
+            .map(|diff| diff.name.as_str())
+            .collect::<Vec<_>>();
+        // Sorted by memory growth, types that only changed edges come last
+        assert_eq!(names, vec!["read", "parse", "resolve"]);
+
+        let read = &diffs[0];
+        assert_eq!(read.count_change(), 1);
+        assert_eq!(read.memory_change(), 60);
+        assert_eq!(read.edge_count_change(), 0);
+
+        let parse = &diffs[1];
+        assert_eq!(parse.memory_change(), 0);
+        assert_eq!(parse.edge_changes(), vec![(("dependency", "read"), 1)]);
+
+        let resolve = &diffs[2];
+        assert_eq!(resolve.edge_changes(), vec![(("child", "parse"), 1)]);
+    }
+}
@@ -61,6 +61,9 @@ pub struct ExportedTaskStats {
     pub count: usize,
     pub active_count: usize,
     pub unloaded_count: usize,
+    /// Number of executions waiting for a free slot of the concurrency limit
+    /// of the function
+    pub waiting_count: usize,
     pub executions: Option<u32>,
     pub roots: usize,
     pub scopes: usize,
@@ -77,6 +80,7 @@ impl Default for ExportedTaskStats {
             count: 0,
             active_count: 0,
             unloaded_count: 0,
+            waiting_count: 0,
             executions: None,
             roots: 0,
             scopes: 0,
@@ -129,9 +133,15 @@ impl Stats {
             child_scopes,
             active,
             unloaded,
+            ..
         } = info;
+        let waiting_count = task
+            .get_concurrency_limit()
+            .map_or(0, |limit| limit.waiting());
         let stats = self.tasks.entry(ty).or_default();
         stats.count += 1;
+        // The count is tracked per function, so it's the same for all tasks
+        stats.waiting_count = waiting_count;
         if active {
             stats.active_count += 1
         }
@@ -9,7 +9,7 @@ use std::{
     fmt::{self, Debug, Display, Formatter, Write},
     future::Future,
     hash::Hash,
-    mem::{replace, take},
+    mem::{replace, size_of, take},
     pin::Pin,
     sync::Arc,
     time::{Duration, Instant},
@@ -24,8 +24,8 @@ use tokio::task_local;
 use turbo_tasks::{
     backend::{PersistentTaskType, TaskExecutionSpec},
     event::{Event, EventListener},
-    get_invalidator, registry, CellId, Invalidator, RawVc, StatsType, TaskId, TraitTypeId,
-    TurboTasksBackendApi, ValueTypeId,
+    get_invalidator, registry, CellId, ConcurrencyLimit, InvalidationReason, Invalidator, RawVc,
+    StatsType, TaskId, TraitTypeId, TurboTasksBackendApi, ValueTypeId,
 };
 
 use crate::{
@@ -164,6 +164,10 @@ struct TaskState {
 
     // Stats:
     stats: TaskStats,
+
+    /// Why the task has been invalidated, until it has been executed again.
+    /// Only tracked with full stats.
+    invalidation_reason: Option<Arc<InvalidationReason>>,
 }
 
 impl TaskState {
@@ -184,6 +188,7 @@ impl TaskState {
             cells: Default::default(),
             gc: Default::default(),
             stats: TaskStats::new(stats_type),
+            invalidation_reason: None,
             #[cfg(feature = "track_wait_dependencies")]
             last_waiting_task: Default::default(),
         }
@@ -207,6 +212,7 @@ impl TaskState {
             cells: Default::default(),
             gc: Default::default(),
             stats: TaskStats::new(stats_type),
+            invalidation_reason: None,
             #[cfg(feature = "track_wait_dependencies")]
             last_waiting_task: Default::default(),
         }
@@ -238,6 +244,7 @@ impl PartialTaskState {
             cells: Default::default(),
             gc: Default::default(),
             stats: TaskStats::new(self.stats_type),
+            invalidation_reason: None,
         }
     }
 }
@@ -271,6 +278,7 @@ impl UnloadedTaskState {
             cells: Default::default(),
             gc: Default::default(),
             stats: TaskStats::new(self.stats_type),
+            invalidation_reason: None,
         }
     }
 
@@ -440,6 +448,13 @@ impl Task {
         }
     }
 
+    pub(crate) fn get_concurrency_limit(&self) -> Option<&'static ConcurrencyLimit> {
+        match &self.ty {
+            TaskType::Persistent(ty) => ty.concurrency_limit(),
+            TaskType::Root(_) | TaskType::Once(_) => None,
+        }
+    }
+
     pub(crate) fn get_description(&self) -> String {
         match &self.ty {
             TaskType::Root(..) => format!("[{}] root", self.id),
@@ -746,6 +761,8 @@ impl Task {
                     state.cells.shrink_to_fit();
                     state.stateful = stateful;
                     state.state_type = Done { dependencies };
+                    // The reason is only kept while the task is invalidated
+                    state.invalidation_reason = None;
                     for scope in state.scopes.iter() {
                         backend.with_scope(scope, |scope| {
                             scope.decrement_unfinished_tasks(backend);
@@ -755,8 +772,20 @@ impl Task {
                 }
                 InProgressDirty { ref mut event } => {
                     let event = event.take();
-                    state.state_type = Scheduled { event };
-                    schedule_task = true;
+                    if self.scopes_dirty_or_active(false, &state.scopes, backend) {
+                        state.state_type = Scheduled { event };
+                        schedule_task = true;
+                    } else {
+                        // The task is not needed anymore, so it stays dirty until it's read
+                        // again. Waiting readers will schedule it when reading again.
+                        let description = self.get_event_description();
+                        state.state_type = Dirty {
+                            event: Event::new(move || {
+                                format!("TaskState({})::event", description())
+                            }),
+                        };
+                        event.notify(usize::MAX);
+                    }
                 }
                 Dirty { .. } | Scheduled { .. } | Done { .. } => {
                     panic!(
@@ -833,13 +862,19 @@ impl Task {
         false
     }
 
-    fn make_dirty(&self, backend: &MemoryBackend, turbo_tasks: &dyn TurboTasksBackendApi) {
-        self.make_dirty_internal(false, backend, turbo_tasks);
+    fn make_dirty(
+        &self,
+        reason: Option<Arc<InvalidationReason>>,
+        backend: &MemoryBackend,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.make_dirty_internal(false, reason, backend, turbo_tasks);
     }
 
     fn make_dirty_internal(
         &self,
         force_schedule: bool,
+        reason: Option<Arc<InvalidationReason>>,
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
@@ -865,6 +900,7 @@ impl Task {
                     ref mut dependencies,
                 } => {
                     clear_dependencies = take(dependencies);
+                    state.invalidation_reason = reason;
                     // add to dirty lists and potentially schedule
                     let description = self.get_event_description();
                     let active =
@@ -890,7 +926,13 @@ impl Task {
                     state.state_type = InProgressDirty {
                         event: event.take(),
                     };
+                    state.invalidation_reason = reason;
                     drop(state);
+                    if self.is_pure() {
+                        // The result of the running execution will be thrown away, so there is
+                        // no need to wait for it
+                        backend.cancel_invalidated_execution(self.id, turbo_tasks);
+                    }
                 }
             }
 
@@ -900,6 +942,33 @@ impl Task {
         }
     }
 
+    /// Cancels the running execution of the task when none of its scopes is
+    /// active anymore. Returns true when the execution has been cancelled.
+    pub(crate) fn cancel_execution_if_inactive(
+        &self,
+        backend: &MemoryBackend,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> bool {
+        if !self.is_pure() {
+            return false;
+        }
+        let mut state = self.full_state_mut();
+        let active = state
+            .scopes
+            .iter()
+            .any(|scope| backend.with_scope(scope, |scope| scope.state.lock().is_active()));
+        match state.state_type {
+            InProgress { ref mut event } if !active => {
+                state.state_type = InProgressDirty {
+                    event: event.take(),
+                };
+            }
+            _ => return false,
+        }
+        drop(state);
+        turbo_tasks.cancel_task_execution(self.id)
+    }
+
     pub(crate) fn schedule_when_dirty_from_scope(
         &self,
         backend: &MemoryBackend,
@@ -1473,10 +1542,21 @@ impl Task {
     /// active it will be scheduled for execution.
     pub(crate) fn invalidate(
         &self,
+        reason: Option<Arc<InvalidationReason>>,
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
-        self.make_dirty(backend, turbo_tasks)
+        self.make_dirty(reason, backend, turbo_tasks)
+    }
+
+    /// Returns why the task has been invalidated, while it's waiting for or
+    /// in the middle of its re-execution.
+    pub(crate) fn invalidation_reason(&self) -> Option<Arc<InvalidationReason>> {
+        if let TaskMetaStateReadGuard::Full(state) = self.state() {
+            state.invalidation_reason.clone()
+        } else {
+            None
+        }
     }
 
     /// Called when the task need to be recomputed because a gc'ed cell was
@@ -1486,7 +1566,7 @@ impl Task {
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
-        self.make_dirty_internal(true, backend, turbo_tasks)
+        self.make_dirty_internal(true, None, backend, turbo_tasks)
     }
 
     /// Access to the output cell.
@@ -1584,6 +1664,26 @@ impl Task {
                         backend.with_scope(scope, |scope| scope.state.lock().is_active())
                     }),
                     unloaded: false,
+                    cells: state
+                        .cells
+                        .values()
+                        .flatten()
+                        .filter(|cell| cell.has_value())
+                        .count(),
+                    memory: size_of::<Task>()
+                        + size_of::<TaskState>()
+                        + state
+                            .cells
+                            .values()
+                            .map(|cells| cells.capacity() * size_of::<Cell>())
+                            .sum::<usize>()
+                        + state.children.len() * size_of::<TaskId>()
+                        + match state.state_type {
+                            Done { ref dependencies } => {
+                                dependencies.len() * size_of::<TaskDependency>()
+                            }
+                            _ => 0,
+                        },
                 }
             }
             TaskMetaStateReadGuard::Partial(state) => TaskStatsInfo {
@@ -1598,6 +1698,8 @@ impl Task {
                 },
                 active: false,
                 unloaded: true,
+                cells: 0,
+                memory: size_of::<Task>() + size_of::<PartialTaskState>(),
             },
             TaskMetaStateReadGuard::Unloaded(_) => TaskStatsInfo {
                 total_duration: None,
@@ -1607,6 +1709,8 @@ impl Task {
                 child_scopes: 0,
                 active: false,
                 unloaded: true,
+                cells: 0,
+                memory: size_of::<Task>(),
             },
         }
     }
@@ -1625,6 +1729,20 @@ impl Task {
         }
     }
 
+    /// Returns the estimated memory used by the value of each cell, see
+    /// `Cell::value_size`.
+    pub fn get_stats_cell_sizes(&self) -> Vec<usize> {
+        match self.state() {
+            TaskMetaStateReadGuard::Full(state) => state
+                .cells
+                .values()
+                .flatten()
+                .filter_map(|cell| cell.value_size())
+                .collect(),
+            TaskMetaStateReadGuard::Partial(_) | TaskMetaStateReadGuard::Unloaded(_) => Vec::new(),
+        }
+    }
+
     pub fn get_stats_references(&self) -> StatsReferences {
         let mut refs = Vec::new();
         let mut scope_refs = Vec::new();
@@ -2308,6 +2426,8 @@ impl Task {
             state_type: _,
             // can be dropped as only gc meta info
             gc: _,
+            // can be dropped as only debugging info
+            invalidation_reason: _,
         } = old_state.into_full().unwrap();
 
         // Remove all children, as they will be added again when this task is executed
@@ -2505,4 +2625,8 @@ pub struct TaskStatsInfo {
     pub child_scopes: usize,
     pub active: bool,
     pub unloaded: bool,
+    /// Number of cells holding a value
+    pub cells: usize,
+    /// Estimated memory used by the task, excluding the values of its cells
+    pub memory: usize,
 }
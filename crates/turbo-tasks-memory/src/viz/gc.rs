@@ -0,0 +1,72 @@
+use turbo_malloc::TurboMalloc;
+
+use super::*;
+use crate::MemoryBackend;
+
+fn format_bytes(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Creates a table with the memory budget and the statistics of garbage
+/// collection runs. Runs are only recorded with full stats.
+pub fn create_table(backend: &MemoryBackend) -> String {
+    let report = backend.gc_report();
+    let limit = backend.memory_limit();
+    let mut rows = vec![
+        ("memory usage", format_bytes(TurboMalloc::memory_usage())),
+        (
+            "memory limit",
+            if limit == usize::MAX {
+                "none (gc disabled)".to_string()
+            } else {
+                format_bytes(limit)
+            },
+        ),
+        ("runs", report.runs.to_string()),
+        ("escalated runs", report.escalated_runs.to_string()),
+        ("processed tasks", report.tasks.to_string()),
+        (
+            "lowest processed priority",
+            report
+                .lowest_priority
+                .map_or_else(|| "-".to_string(), |priority| format!("{priority:?}")),
+        ),
+        ("freed", format_bytes(report.freed_bytes)),
+    ];
+    let stats = &report.stats;
+    rows.extend([
+        ("unloaded tasks", stats.unloaded.to_string()),
+        ("emptied unused cells", stats.empty_unused.to_string()),
+        (
+            "emptied unused cells (fast path)",
+            stats.empty_unused_fast.to_string(),
+        ),
+        ("emptied cells", stats.empty_cells.to_string()),
+        ("updated priorities", stats.priority_updated.to_string()),
+        (
+            "updated priorities (fast path)",
+            stats.priority_updated_fast.to_string(),
+        ),
+        ("no gc needed", stats.no_gc_needed.to_string()),
+        ("no gc possible", stats.no_gc_possible.to_string()),
+    ]);
+
+    let mut out = String::new();
+    out += r#"<table><thead><tr>"#;
+    out += r#"<th>garbage collection</th>"#;
+    out += r#"<th>value</th>"#;
+    out += r#"</tr></thead>"#;
+    out += r#"<tbody>"#;
+    for (name, value) in rows {
+        write!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            name,
+            escape_html(&value)
+        )
+        .unwrap();
+    }
+    out += r#"</tbody>"#;
+    out += r#"</table>"#;
+    out
+}
@@ -0,0 +1,67 @@
+use turbo_tasks::{backend::Backend, registry, InvalidationCause};
+
+use super::*;
+use crate::MemoryBackend;
+
+fn describe_cause(backend: &MemoryBackend, cause: &InvalidationCause) -> String {
+    match cause {
+        InvalidationCause::External(description) => description.to_string(),
+        InvalidationCause::CellChanged { task, cell } => format!(
+            "{} cell of {}",
+            registry::get_value_type(cell.type_id).name,
+            backend.get_task_description(*task)
+        ),
+        InvalidationCause::OutputChanged { task } => {
+            format!("output of {}", backend.get_task_description(*task))
+        }
+    }
+}
+
+/// Creates a table of all tasks that are currently invalidated, with the chain
+/// of causes that led to the invalidation. Reasons are only tracked with full
+/// stats. Tasks are grouped by the original
+/// cause, so a single change that invalidates too many tasks is easy to spot.
+pub fn create_table(backend: &MemoryBackend) -> String {
+    let mut rows = Vec::new();
+    backend.with_all_cached_tasks(|task| {
+        if let Some(reason) = backend.with_task(task, |task| task.invalidation_reason()) {
+            let chain = reason
+                .chain()
+                .map(|cause| describe_cause(backend, cause))
+                .collect::<Vec<_>>();
+            rows.push((
+                describe_cause(backend, reason.root_cause()),
+                backend.get_task_description(task),
+                chain,
+            ));
+        }
+    });
+    rows.sort();
+
+    let mut out = String::new();
+    out += r#"<table class="sortable"><thead><tr>"#;
+    out += r#"<th>root cause</th>"#;
+    out += r#"<th>task</th>"#;
+    out += r#"<th>chain of causes</th>"#;
+    out += r#"</tr></thead>"#;
+    out += r#"<tbody>"#;
+    for (root_cause, task, chain) in rows {
+        write!(
+            out,
+            "<tr><td bgcolor=\"{}\">{}</td><td>{}</td><td>{}</td></tr>",
+            as_hash_color(&root_cause),
+            escape_html(&root_cause),
+            escape_html(&task),
+            chain
+                .iter()
+                .map(String::as_str)
+                .map(escape_html)
+                .collect::<Vec<_>>()
+                .join(" &larr; ")
+        )
+        .unwrap();
+    }
+    out += r#"</tbody>"#;
+    out += r#"</table>"#;
+    out
+}
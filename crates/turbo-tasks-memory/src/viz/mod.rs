@@ -1,4 +1,6 @@
+pub mod gc;
 pub mod graph;
+pub mod invalidations;
 pub mod table;
 
 use std::{
@@ -37,6 +39,7 @@ struct MaxValues {
     pub count: usize,
     pub active_count: usize,
     pub unloaded_count: usize,
+    pub waiting_count: usize,
     pub updates: Option<usize>,
     pub roots: usize,
     /// stored as scopes * 100
@@ -83,6 +86,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
     let mut max_count = 0;
     let mut max_active_count = 0;
     let mut max_unloaded_count = 0;
+    let mut max_waiting_count = 0;
     let mut max_updates = None;
     let mut max_roots = 0;
     let mut max_scopes = 0;
@@ -109,6 +113,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         max_count = max(max_count, s.count);
         max_active_count = max(max_active_count, s.active_count);
         max_unloaded_count = max(max_unloaded_count, s.unloaded_count);
+        max_waiting_count = max(max_waiting_count, s.waiting_count);
         if let Some(executions) = s.executions {
             let updates = (executions as usize).saturating_sub(s.count);
             max_updates = max_updates
@@ -138,6 +143,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
             count,
             active_count,
             unloaded_count,
+            waiting_count,
             updates,
             roots,
             scopes,
@@ -155,6 +161,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         max_count = max(max_count, count);
         max_active_count = max(max_active_count, active_count);
         max_unloaded_count = max(max_unloaded_count, unloaded_count);
+        max_waiting_count = max(max_waiting_count, waiting_count);
         max_updates = max_updates.zip(updates).map(|(a, b)| max(a, b));
         max_roots = max(max_roots, roots);
         max_scopes = max(max_scopes, scopes);
@@ -171,6 +178,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         count: max_count,
         active_count: max_active_count,
         unloaded_count: max_unloaded_count,
+        waiting_count: max_waiting_count,
         updates: max_updates,
         roots: max_roots,
         scopes: max_scopes,
@@ -0,0 +1,119 @@
+#![feature(min_specialization)]
+
+use std::sync::Mutex;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use tokio::sync::Notify;
+use turbo_tasks::{get_invalidator, Invalidator, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+lazy_static! {
+    /// Notified when the first execution of `slow_double` is running.
+    static ref DOUBLE_STARTED: Notify = Notify::new();
+    /// Notified when `never_finish` is running.
+    static ref NEVER_FINISH_STARTED: Notify = Notify::new();
+    /// Notified when the execution of `never_finish` has been dropped.
+    static ref NEVER_FINISH_DROPPED: Notify = Notify::new();
+}
+
+#[tokio::test]
+async fn cancel_invalidated_execution() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let counter = CounterVc::cell(Counter {
+            value: Mutex::new((1, None)),
+        });
+        let doubled = slow_double(counter);
+        let (result, ()) = tokio::join!(doubled.strongly_consistent(), async {
+            DOUBLE_STARTED.notified().await;
+            counter.await.unwrap().incr();
+        });
+        assert_eq!(*result?, 4);
+        Ok(())
+    })
+    .await
+    .unwrap();
+    assert_eq!(tt.backend().cancellation_stats().invalidated, 1);
+}
+
+#[tokio::test]
+async fn cancel_inactive_execution() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        // Not awaited, so the once task finishes and its scope becomes inactive
+        let _ = never_finish();
+        NEVER_FINISH_STARTED.notified().await;
+        Ok(())
+    })
+    .await
+    .unwrap();
+    NEVER_FINISH_DROPPED.notified().await;
+    tt.wait_background_done().await;
+    let stats = tt.backend().cancellation_stats();
+    assert_eq!(stats.inactive, 1);
+    assert_eq!(stats.invalidated, 0);
+}
+
+#[turbo_tasks::value(transparent)]
+struct CounterValue(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Counter {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Option<Invalidator>)>,
+}
+
+impl Counter {
+    fn incr(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate();
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CounterVc {
+    #[turbo_tasks::function]
+    pub async fn get_value(self) -> Result<CounterValueVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(CounterValueVc::cell(lock.0))
+    }
+}
+
+#[turbo_tasks::function]
+async fn slow_double(counter: CounterVc) -> Result<CounterValueVc> {
+    let value = *counter.get_value().await?;
+    if value == 1 {
+        // The first execution is invalidated while pending and won't finish
+        DOUBLE_STARTED.notify_one();
+        std::future::pending::<()>().await;
+    }
+    Ok(CounterValueVc::cell(value * 2))
+}
+
+/// Notifies [NEVER_FINISH_DROPPED] when dropped with the execution.
+struct DropGuard;
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        NEVER_FINISH_DROPPED.notify_one();
+    }
+}
+
+#[turbo_tasks::function]
+async fn never_finish() -> Result<CounterValueVc> {
+    let _guard = DropGuard;
+    NEVER_FINISH_STARTED.notify_one();
+    std::future::pending::<()>().await;
+    Ok(CounterValueVc::cell(0))
+}
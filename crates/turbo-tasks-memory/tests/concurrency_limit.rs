@@ -0,0 +1,87 @@
+#![feature(min_specialization)]
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::Result;
+use turbo_tasks::{ConcurrencyLimit, TryJoinIterExt, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn limits_concurrent_executions() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let results = (0..8)
+            .map(|i| async move { Ok(*slow_double(i).await?) })
+            .try_join()
+            .await?;
+        assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+        Ok(())
+    })
+    .await
+    .unwrap();
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn awaits_indirect_self_calls() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::default());
+    // With a single slot this would deadlock if the caller kept its slot
+    // while waiting for the callee
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        tt.run_once(async {
+            assert_eq!(*countdown(3).await?, 3);
+            Ok(())
+        }),
+    )
+    .await
+    .expect("limited function deadlocked")
+    .unwrap();
+}
+
+#[tokio::test]
+async fn dropped_acquire_is_not_waiting() {
+    let limit = ConcurrencyLimit::new(1);
+    let _permit = limit.acquire().await;
+    // The timeout polls the acquire once and drops it while it's waiting
+    assert!(tokio::time::timeout(Duration::ZERO, limit.acquire())
+        .await
+        .is_err());
+    assert_eq!(limit.waiting(), 0);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function(concurrency = 2)]
+async fn slow_double(value: u32) -> Result<NumberVc> {
+    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+    Ok(NumberVc::cell(value * 2))
+}
+
+#[turbo_tasks::function(concurrency = 1)]
+async fn countdown(value: u32) -> Result<NumberVc> {
+    if value == 0 {
+        return Ok(NumberVc::cell(0));
+    }
+    Ok(NumberVc::cell(*countdown_indirect(value - 1).await? + 1))
+}
+
+#[turbo_tasks::function]
+async fn countdown_indirect(value: u32) -> Result<NumberVc> {
+    Ok(NumberVc::cell(*countdown(value).await?))
+}
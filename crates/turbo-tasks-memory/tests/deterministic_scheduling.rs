@@ -0,0 +1,57 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{ScheduleRecording, SchedulingMode, TryJoinIterExt, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::{register, run_with_seed};
+
+register!();
+
+#[tokio::test]
+async fn same_seed_same_order() {
+    let first = run_with_seed!(7,
+        assert_eq!(*sum(5).await?, 20);
+    );
+    let second = run_with_seed!(7,
+        assert_eq!(*sum(5).await?, 20);
+    );
+    assert_eq!(first, second);
+    assert!(first.tasks.iter().any(|task| task.ends_with("double")));
+}
+
+#[tokio::test]
+async fn replay() {
+    let recording = run_with_seed!(3,
+        assert_eq!(*sum(5).await?, 20);
+    );
+
+    // The seed is only used when the execution diverges from the recording
+    let tt = TurboTasks::new_with_scheduling_mode(
+        MemoryBackend::default(),
+        SchedulingMode::Replay(ScheduleRecording {
+            seed: 4,
+            ..recording.clone()
+        }),
+    );
+    tt.run_once(async {
+        assert_eq!(*sum(5).await?, 20);
+        Ok(())
+    })
+    .await
+    .unwrap();
+    assert_eq!(tt.schedule_recording().unwrap().tasks, recording.tasks);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn double(value: u32) -> NumberVc {
+    NumberVc::cell(value * 2)
+}
+
+#[turbo_tasks::function]
+async fn sum(count: u32) -> Result<NumberVc> {
+    let values = (0..count).map(double).try_join().await?;
+    Ok(NumberVc::cell(values.iter().map(|value| **value).sum()))
+}
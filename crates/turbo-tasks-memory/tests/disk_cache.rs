@@ -0,0 +1,88 @@
+#![feature(min_specialization)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Result;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_hash::DeterministicHash;
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+static DOUBLE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+static SUM_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+static SQUARE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+static FIRST_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn restored_from_disk() {
+    *REGISTER;
+    let dir = std::env::temp_dir().join(format!("turbo-tasks-disk-cache-{}", std::process::id()));
+    turbo_tasks::enable_disk_cache(&dir);
+
+    for a in [20, 20, 21] {
+        // A new instance has an empty memory cache
+        let tt = TurboTasks::new(MemoryBackend::default());
+        tt.run_once(async move {
+            assert_eq!(*double(21).await?, 42);
+            // Vcs are keyed by the values of their cells
+            assert_eq!(*sum(NumberVc::cell(a), NumberVc::cell(22)).await?, a + 22);
+            assert_eq!(*NumberVc::cell(6).square().await?, 36);
+            // The pair references another cell, so it can't be hashed
+            let pair = PairVc::cell(Pair {
+                a: NumberVc::cell(a),
+                b: 22,
+            });
+            assert_eq!(*first(pair).await?, a);
+            Ok(())
+        })
+        .await
+        .unwrap();
+    }
+    assert_eq!(DOUBLE_EXECUTIONS.load(Ordering::SeqCst), 1);
+    // Changing the referenced value is a cache miss
+    assert_eq!(SUM_EXECUTIONS.load(Ordering::SeqCst), 2);
+    assert_eq!(SQUARE_EXECUTIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(FIRST_EXECUTIONS.load(Ordering::SeqCst), 3);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
+struct Number(u32);
+
+#[turbo_tasks::value]
+struct Pair {
+    a: NumberVc,
+    b: u32,
+}
+
+#[turbo_tasks::function(cache = "disk")]
+fn double(value: u32) -> NumberVc {
+    DOUBLE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    NumberVc::cell(value * 2)
+}
+
+#[turbo_tasks::function(cache = "disk")]
+async fn sum(a: NumberVc, b: NumberVc) -> Result<NumberVc> {
+    SUM_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    Ok(NumberVc::cell(*a.await? + *b.await?))
+}
+
+#[turbo_tasks::function(cache = "disk")]
+async fn first(pair: PairVc) -> Result<NumberVc> {
+    FIRST_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    Ok(pair.await?.a)
+}
+
+#[turbo_tasks::value_impl]
+impl NumberVc {
+    #[turbo_tasks::function(cache = "disk")]
+    async fn square(self) -> Result<NumberVc> {
+        SQUARE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+        let value = *self.await?;
+        Ok(NumberVc::cell(value * value))
+    }
+}
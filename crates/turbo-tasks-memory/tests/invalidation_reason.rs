@@ -0,0 +1,115 @@
+#![feature(min_specialization)]
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use turbo_tasks::{
+    get_invalidator, test_helpers::current_task_for_testing, InvalidationCause, InvalidationReason,
+    Invalidator, RawVc, StatsType, TurboTasks, TurboTasksBackendApi,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+/// The invalidation reason of the `double` task during its last execution.
+static DOUBLE_REASON: Mutex<Option<Arc<InvalidationReason>>> = Mutex::new(None);
+
+#[tokio::test]
+async fn invalidation_reason() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.set_stats_type(StatsType::Full);
+    tt.run_once(async {
+        let counter = CounterVc::cell(Counter {
+            value: Mutex::new((1, None)),
+        });
+        let value = counter.get_value().resolve().await?;
+        let doubled = double(value);
+        assert_eq!(*doubled.strongly_consistent().await?, 2);
+        let value_task = RawVc::from(value).get_task_id();
+        let doubled_task = RawVc::from(doubled).get_task_id();
+        assert!(DOUBLE_REASON.lock().unwrap().is_none());
+
+        counter.await?.incr("counter changed");
+        assert_eq!(*doubled.strongly_consistent().await?, 4);
+
+        let reason = DOUBLE_REASON.lock().unwrap().take().unwrap();
+        let chain = reason.chain().collect::<Vec<_>>();
+        assert_eq!(chain.len(), 2);
+        assert!(
+            matches!(chain[0], InvalidationCause::CellChanged { task, .. } if *task == value_task)
+        );
+        assert_eq!(
+            chain[1],
+            &InvalidationCause::External("counter changed".into())
+        );
+        // The reason is dropped once the task has been executed again
+        assert!(turbo_tasks::turbo_tasks()
+            .invalidation_reason(doubled_task)
+            .is_none());
+        Ok(())
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn no_invalidation_reason_without_full_stats() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let counter = CounterVc::cell(Counter {
+            value: Mutex::new((1, None)),
+        });
+        let value = counter.get_value().resolve().await?;
+        assert_eq!(*value.strongly_consistent().await?, 1);
+        let value_task = RawVc::from(value).get_task_id();
+
+        counter.await?.incr("counter changed");
+        assert!(turbo_tasks::turbo_tasks()
+            .invalidation_reason(value_task)
+            .is_none());
+        assert_eq!(*value.strongly_consistent().await?, 2);
+        Ok(())
+    })
+    .await
+    .unwrap();
+}
+
+#[turbo_tasks::value(transparent)]
+struct CounterValue(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Counter {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Option<Invalidator>)>,
+}
+
+impl Counter {
+    fn incr(&self, reason: &'static str) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate_with_reason(reason);
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CounterVc {
+    #[turbo_tasks::function]
+    pub async fn get_value(self) -> Result<CounterValueVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(CounterValueVc::cell(lock.0))
+    }
+}
+
+#[turbo_tasks::function]
+async fn double(value: CounterValueVc) -> Result<CounterValueVc> {
+    *DOUBLE_REASON.lock().unwrap() =
+        turbo_tasks::turbo_tasks().invalidation_reason(current_task_for_testing());
+    Ok(CounterValueVc::cell(*value.await? * 2))
+}
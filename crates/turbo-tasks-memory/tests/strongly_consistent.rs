@@ -0,0 +1,57 @@
+#![feature(min_specialization)]
+
+use std::sync::Mutex;
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, Invalidator};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn strongly_consistent_resolved_read() {
+    run! {
+        let counter = CounterVc::cell(Counter { value: Mutex::new((1, None)) });
+        let doubled = double(counter).resolve().await?;
+        assert_eq!(*doubled.strongly_consistent().await?, 2);
+
+        counter.await?.incr();
+        // Reading the resolved cell waits for the task owning it to settle
+        assert_eq!(*doubled.strongly_consistent().await?, 4);
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct CounterValue(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Counter {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Option<Invalidator>)>,
+}
+
+impl Counter {
+    fn incr(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate();
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CounterVc {
+    #[turbo_tasks::function]
+    pub async fn get_value(self) -> Result<CounterValueVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(CounterValueVc::cell(lock.0))
+    }
+}
+
+#[turbo_tasks::function]
+async fn double(counter: CounterVc) -> Result<CounterValueVc> {
+    Ok(CounterValueVc::cell(*counter.get_value().await? * 2))
+}
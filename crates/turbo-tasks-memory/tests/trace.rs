@@ -0,0 +1,42 @@
+#![feature(min_specialization)]
+
+use std::fs;
+
+use anyhow::Result;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+#[tokio::test]
+async fn trace_recording() {
+    *REGISTER;
+    let path = std::env::temp_dir().join(format!("turbo-tasks-trace-{}.json", std::process::id()));
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.start_trace_recording(&path).unwrap();
+    tt.run_once(async {
+        let value = double(NumberVc::cell(21));
+        assert_eq!(*value.await?, 42);
+        Ok(())
+    })
+    .await
+    .unwrap();
+    tt.stop_trace_recording().unwrap();
+
+    let trace = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(trace.starts_with('['));
+    assert!(trace.trim_end().ends_with(']'));
+    assert!(trace.contains("double"));
+    assert!(trace.contains(r#""name":"scheduled""#));
+    assert!(trace.contains(r#""name":"read cell""#));
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+async fn double(number: NumberVc) -> Result<NumberVc> {
+    Ok(NumberVc::cell(*number.await? * 2))
+}
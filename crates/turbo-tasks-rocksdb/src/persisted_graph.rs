@@ -2,7 +2,10 @@ use std::{
     collections::HashSet,
     fmt::Debug,
     path::Path,
-    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Mutex,
+    },
 };
 
 use anyhow::{anyhow, Error, Result};
@@ -14,7 +17,7 @@ use turbo_tasks::{
         ActivateResult, DeactivateResult, PersistResult, PersistTaskState, PersistedGraph,
         PersistedGraphApi, ReadTaskState, TaskCell, TaskData,
     },
-    util::{InfiniteVec, SharedError},
+    util::{NoMoveVec, SharedError},
     with_task_id_mapping, FunctionId, IdMapping, TaskId,
 };
 
@@ -49,18 +52,19 @@ fn task_type_to_bytes(ty: &PersistentTaskType) -> Result<Vec<u8>, bincode::Error
 }
 
 #[derive(Default)]
-pub struct CountsByFunction(pub NoMoveVec<AtomicUsize>);
+pub struct CountsByFunction(Mutex<std::collections::HashMap<FunctionId, usize>>);
+
+impl CountsByFunction {
+    fn increment(&self, function: FunctionId) {
+        *self.0.lock().unwrap().entry(function).or_default() += 1;
+    }
+}
 
 impl Debug for CountsByFunction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut m = f.debug_map();
-        for (fn_id, count) in self.0.iter() {
-            let i = count.load(Ordering::Relaxed);
-            if i > 0 {
-                m.entry(&FunctionId::from(fn_id), &i);
-            }
-        }
-        m.finish()
+        f.debug_map()
+            .entries(self.0.lock().unwrap().iter())
+            .finish()
     }
 }
 
@@ -94,7 +98,9 @@ pub struct RocksDbPersistedGraph {
     cache_once: turbo_tasks::util::SafeOnceConcurrentlyMap<Vec<u8>, Result<usize, SharedError>>,
     stats: Stats,
     /// AC_UNKNOWN | AC_ACTIVE | AC_INACTIVE
-    active_cache: InfiniteVec<AtomicU8>,
+    active_cache: NoMoveVec<AtomicU8>,
+    /// Tasks that has been marked as externally active in this session
+    renewed_externally_active: flurry::HashSet<usize>,
 }
 
 impl RocksDbPersistedGraph {
@@ -111,7 +117,8 @@ impl RocksDbPersistedGraph {
             #[cfg(not(feature = "unsafe_once_map"))]
             cache_once: turbo_tasks::util::SafeOnceConcurrentlyMap::new(),
             stats: Stats::default(),
-            active_cache: InfiniteVec::new(),
+            active_cache: NoMoveVec::new_init_default(),
+            renewed_externally_active: flurry::HashSet::new(),
         })
     }
 
@@ -171,7 +178,7 @@ impl RocksDbPersistedGraph {
     }
 
     fn get_active(&self, db_task: usize) -> Result<bool> {
-        let ac = self.active_cache.get(db_task);
+        let ac = self.active_cache.get_init_default(db_task);
         let ac_value = ac.load(Ordering::Acquire);
         if ac_value != AC_UNKNOWN {
             return Ok(ac_value == AC_ACTIVE);
@@ -227,11 +234,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
                     match ty {
                         PersistentTaskType::Native(f, _)
                         | PersistentTaskType::ResolveNative(f, _) => {
-                            self.stats
-                                .reads_by_function
-                                .0
-                                .get_init_default(*f)
-                                .fetch_add(1, Ordering::Relaxed);
+                            self.stats.reads_by_function.increment(f);
                         }
                         _ => {}
                     }
@@ -319,7 +322,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
             let mut tasks_to_deactivate = Vec::new();
             let mut tasks_to_activate = Vec::new();
             let b = &mut db.batch();
-            let ac = self.active_cache.get(db_task);
+            let ac = self.active_cache.get_init_default(db_task);
             let old_active = {
                 match ac.load(Ordering::Acquire) {
                     AC_ACTIVE => true,
@@ -383,6 +386,17 @@ impl PersistedGraph for RocksDbPersistedGraph {
                 &db_task,
                 &TaskStateChange::Persist(state.externally_active),
             )?;
+            if state.externally_active {
+                db.externally_active_tasks.insert(b, &(), &db_task)?;
+                self.renewed_externally_active.pin().insert(db_task);
+            } else {
+                db.externally_active_tasks.remove(b, &(), &db_task)?;
+            }
+            if state.volatile {
+                db.volatile_tasks.insert(b, &(), &db_task)?;
+            } else {
+                db.volatile_tasks.remove(b, &(), &db_task)?;
+            }
             ac.store(AC_ACTIVE, Ordering::Release);
             for cell in data.cells.iter_mut() {
                 if let TaskCell::Content(ref c) = cell {
@@ -424,11 +438,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
             let ty = db.task_type.get(&db_task)?.unwrap();
             match ty {
                 PersistentTaskType::Native(f, _) | PersistentTaskType::ResolveNative(f, _) => {
-                    self.stats
-                        .persists_by_function
-                        .0
-                        .get_init_default(*f)
-                        .fetch_add(1, Ordering::Relaxed);
+                    self.stats.persists_by_function.increment(f);
                 }
                 _ => {}
             }
@@ -453,7 +463,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
             let db = &self.database;
             let b = &mut db.batch();
             db.pending_active_update.remove(b, &(), &db_task)?;
-            let ac = self.active_cache.get(db_task);
+            let ac = self.active_cache.get_init_default(db_task);
             if ac.load(Ordering::Acquire) != AC_ACTIVE {
                 if let Some(TaskState {
                     internal,
@@ -513,7 +523,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
             let db = &self.database;
             let b = &mut db.batch();
             db.pending_active_update.remove(b, &(), &db_task)?;
-            let ac = self.active_cache.get(db_task);
+            let ac = self.active_cache.get_init_default(db_task);
             if ac.load(Ordering::Acquire) != AC_INACTIVE {
                 if let Some(TaskState {
                     active,
@@ -563,7 +573,8 @@ impl PersistedGraph for RocksDbPersistedGraph {
                 .merge(b, &db_task, &TaskStateChange::SetExternallyActive)?;
             db.externally_active_tasks.insert(b, &(), &db_task)?;
             b.write()?;
-            let ac = self.active_cache.get(db_task);
+            self.renewed_externally_active.pin().insert(db_task);
+            let ac = self.active_cache.get_init_default(db_task);
             let ac_value = ac.load(Ordering::Acquire);
             if ac_value == AC_ACTIVE {
                 return Ok(false);
@@ -595,9 +606,10 @@ impl PersistedGraph for RocksDbPersistedGraph {
             let b = &mut db.batch();
             db.state
                 .merge(b, &db_task, &TaskStateChange::UnsetExternallyActive)?;
-            db.externally_active_tasks.insert(b, &(), &db_task)?;
+            db.externally_active_tasks.remove(b, &(), &db_task)?;
             b.write()?;
-            let ac = self.active_cache.get(db_task);
+            self.renewed_externally_active.pin().remove(&db_task);
+            let ac = self.active_cache.get_init_default(db_task);
             let ac_value = ac.load(Ordering::Acquire);
             if ac_value == AC_INACTIVE {
                 return Ok(false);
@@ -686,9 +698,40 @@ impl PersistedGraph for RocksDbPersistedGraph {
         &self,
         api: &dyn PersistedGraphApi,
     ) -> Result<Vec<TaskId>> {
+        // After startup
         self.with_task_id_mapping(api, || {
-            // For startup
-            todo!()
+            let mapping = PgApiMapping::new(self, api);
+            let mut tasks_to_deactivate = Vec::new();
+            let db = &self.database;
+            let b = &mut db.batch();
+            let renewed = self.renewed_externally_active.pin();
+            for db_task in db.externally_active_tasks.get_all(&())? {
+                if renewed.contains(&db_task) {
+                    continue;
+                }
+                // The keep alive is from a previous session and the consumer
+                // graph didn't ask for this task again
+                db.state
+                    .merge(b, &db_task, &TaskStateChange::UnsetExternallyActive)?;
+                db.externally_active_tasks.remove(b, &(), &db_task)?;
+                if let Some(TaskState {
+                    active,
+                    active_parents,
+                    ..
+                }) = db.state.get(&db_task)?
+                {
+                    if active && active_parents == 0 {
+                        db.pending_active_update.insert(b, &(), &db_task)?;
+                        tasks_to_deactivate.push(mapping.backward(db_task));
+                    }
+                }
+            }
+            b.write()?;
+            println!(
+                "{} outdated externally active tasks to deactivate",
+                tasks_to_deactivate.len()
+            );
+            Ok(tasks_to_deactivate)
         })
     }
 
@@ -704,7 +747,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
                     internal, active, ..
                 }) = db.state.get(&db_task)?
                 {
-                    self.active_cache.get(db_task).store(
+                    self.active_cache.get_init_default(db_task).store(
                         if active { AC_ACTIVE } else { AC_INACTIVE },
                         Ordering::Release,
                     );
@@ -735,7 +778,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
                     internal, active, ..
                 }) = db.state.get(&db_task)?
                 {
-                    self.active_cache.get(db_task).store(
+                    self.active_cache.get_init_default(db_task).store(
                         if active { AC_ACTIVE } else { AC_INACTIVE },
                         Ordering::Release,
                     );
@@ -753,6 +796,22 @@ impl PersistedGraph for RocksDbPersistedGraph {
         })
     }
 
+    fn get_volatile_tasks(&self, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        // For startup
+        let mapping = PgApiReadOnlyMapping::new(self, api);
+        with_task_id_mapping(&mapping, || {
+            let db = &self.database;
+            let result = db
+                .volatile_tasks
+                .get_all(&())?
+                .into_iter()
+                .map(|db_task| mapping.backward(db_task))
+                .collect::<Vec<_>>();
+            println!("{} volatile_tasks", result.len());
+            Ok(result)
+        })
+    }
+
     fn get_pending_active_update(
         &self,
         api: &dyn PersistedGraphApi,
@@ -772,7 +831,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
                     ..
                 }) = db.state.get(&db_task)?
                 {
-                    self.active_cache.get(db_task).store(
+                    self.active_cache.get_init_default(db_task).store(
                         if active { AC_ACTIVE } else { AC_INACTIVE },
                         Ordering::Release,
                     );
@@ -787,7 +846,7 @@ impl PersistedGraph for RocksDbPersistedGraph {
                         continue;
                     }
                 }
-                db.potential_dirty_active_tasks.remove(b, &(), &db_task)?;
+                db.pending_active_update.remove(b, &(), &db_task)?;
             }
             b.write()?;
             println!(
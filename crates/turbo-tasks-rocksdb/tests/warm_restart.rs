@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem, FileSystemVc};
+use turbo_tasks_memory::MemoryBackendWithPersistedGraph;
+use turbo_tasks_rocksdb::RocksDbPersistedGraph;
+use turbopack::{
+    module_options::ModuleOptionsContext, register, resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc, ModuleAssetContextVc,
+};
+use turbopack_core::{
+    context::AssetContext,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    reference::all_assets,
+    reference_type::ReferenceType,
+    source_asset::SourceAssetVc,
+};
+use turbopack_create_test_app::test_app_builder::TestAppBuilder;
+
+struct SessionResult {
+    assets: usize,
+    changed_file_content: String,
+    executed_tasks: usize,
+}
+
+/// Builds the module graph of the test app with a fresh [TurboTasks]
+/// instance on top of the persisted graph at `db`, like a new dev server
+/// process would do.
+fn run_session(db: &Path, app: &Path, changed_file: &str) -> Result<SessionResult> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    // Dropping the runtime drops all references to the TurboTasks instance,
+    // which closes the database again.
+    runtime.block_on(async {
+        let tt = TurboTasks::new(MemoryBackendWithPersistedGraph::new(
+            RocksDbPersistedGraph::new(db)?,
+        ));
+        let app = app.to_string_lossy().to_string();
+        let changed_file = changed_file.to_string();
+        let (assets, changed_file_content) = tt
+            .run_once(async move {
+                let disk_fs = DiskFileSystemVc::new_persisted("app".to_string(), app);
+                // Reads restored from the persisted graph might be outdated
+                for invalidator in disk_fs.await?.invalidators_for_changed_paths() {
+                    invalidator.invalidate();
+                }
+                let fs: FileSystemVc = disk_fs.into();
+                let root = fs.root();
+                let context = ModuleAssetContextVc::new(
+                    TransitionsByNameVc::cell(HashMap::new()),
+                    EnvironmentVc::new(
+                        Value::new(ExecutionEnvironment::NodeJsLambda(
+                            NodeJsEnvironment::default().into(),
+                        )),
+                        Value::new(EnvironmentIntention::ServerRendering),
+                    ),
+                    ModuleOptionsContext {
+                        enable_jsx: true,
+                        ..Default::default()
+                    }
+                    .cell(),
+                    ResolveOptionsContext {
+                        enable_node_modules: true,
+                        ..Default::default()
+                    }
+                    .cell(),
+                );
+                let entry = SourceAssetVc::new(root.join("src/triangle.jsx"));
+                let module = context.process(entry.into(), Value::new(ReferenceType::Undefined));
+                let assets = all_assets(module).await?.len();
+
+                let content = root.join(&changed_file).read().await?;
+                let FileContent::Content(file) = &*content else {
+                    bail!("{changed_file} not found");
+                };
+                Ok((assets, file.content().to_str()?.into_owned()))
+            })
+            .await?;
+        // Wait until everything is persisted
+        tt.wait_background_done().await;
+        let executed_tasks = tt.backend().executed_tasks();
+        tt.stop_and_wait().await;
+        Ok(SessionResult {
+            assets,
+            changed_file_content,
+            executed_tasks,
+        })
+    })
+}
+
+#[test]
+fn warm_restart() -> Result<()> {
+    register();
+
+    let app = TestAppBuilder {
+        module_count: 100,
+        directories_count: 5,
+        package_json: None,
+        ..Default::default()
+    }
+    .build()?;
+    let db = tempfile::tempdir()?;
+
+    let (changed_file, _) = app.modules().last().unwrap();
+    let changed_file_path: PathBuf = changed_file.clone();
+    let changed_file = changed_file_path
+        .strip_prefix(app.path())?
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    let initial = run_session(db.path(), app.path(), &changed_file)?;
+
+    // Change a file while no process is running
+    let mut file = OpenOptions::new().append(true).open(&changed_file_path)?;
+    writeln!(file, "// changed while offline")?;
+    drop(file);
+
+    let restarted = run_session(db.path(), app.path(), &changed_file)?;
+
+    assert_eq!(restarted.assets, initial.assets);
+    assert!(!initial
+        .changed_file_content
+        .contains("changed while offline"));
+    assert!(restarted
+        .changed_file_content
+        .contains("changed while offline"));
+    // Only tasks that depend on the changed file are executed again.
+    assert!(
+        restarted.executed_tasks * 2 < initial.executed_tasks,
+        "{} of {} tasks were executed again after restart",
+        restarted.executed_tasks,
+        initial.executed_tasks
+    );
+
+    Ok(())
+}
@@ -20,7 +20,7 @@ use turbo_tasks::{
     event::{Event, EventListener},
     registry,
     test_helpers::{current_task_for_testing, with_turbo_tasks_for_testing},
-    CellId, RawVc, TaskId, TraitTypeId, TurboTasksApi, TurboTasksCallApi,
+    CellId, InvalidationReason, RawVc, TaskId, TraitTypeId, TurboTasksApi, TurboTasksCallApi,
 };
 
 enum Task {
@@ -104,6 +104,14 @@ impl TurboTasksApi for VcStorage {
         unreachable!()
     }
 
+    fn invalidate_with_reason(&self, _task: TaskId, _reason: InvalidationReason) {
+        unreachable!()
+    }
+
+    fn invalidation_reason(&self, _task: TaskId) -> Option<Arc<InvalidationReason>> {
+        None
+    }
+
     fn notify_scheduled_tasks(&self) {
         // ignore
     }
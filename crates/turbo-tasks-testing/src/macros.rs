@@ -24,3 +24,25 @@ macro_rules! run {
         .await.unwrap();
     }};
 }
+
+/// Like [run!], but starts tasks in a pseudo-random order derived from the
+/// `seed` and returns the recorded order. Use it with different seeds to find
+/// results that depend on the order of execution.
+#[macro_export]
+macro_rules! run_with_seed {
+    ($seed:expr, $($stmt:tt)+) => {{
+        use turbo_tasks::{SchedulingMode, TurboTasks};
+        use turbo_tasks_memory::MemoryBackend;
+        *REGISTER;
+        let tt = TurboTasks::new_with_scheduling_mode(
+            MemoryBackend::default(),
+            SchedulingMode::Seeded($seed),
+        );
+        tt.run_once(async {
+            $($stmt)+
+            Ok(())
+        })
+        .await.unwrap();
+        tt.schedule_recording().unwrap()
+    }};
+}
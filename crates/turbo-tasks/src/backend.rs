@@ -14,9 +14,9 @@ use serde::{Deserialize, Serialize};
 
 pub use crate::id::BackendJobId;
 use crate::{
-    event::EventListener, manager::TurboTasksBackendApi, raw_vc::CellId, registry,
-    task_input::SharedReference, FunctionId, RawVc, ReadRef, TaskId, TaskIdProvider, TaskInput,
-    TraitTypeId,
+    event::EventListener, invalidation::InvalidationReason, manager::TurboTasksBackendApi,
+    raw_vc::CellId, registry, task_input::SharedReference, ConcurrencyLimit, FunctionId, RawVc,
+    ReadRef, TaskId, TaskIdProvider, TaskInput, TraitTypeId,
 };
 
 /// Different Task types
@@ -40,12 +40,11 @@ pub enum TransientTaskType {
     /// Always active. Automatically scheduled.
     Root(TransientTaskRoot),
 
-    // TODO implement these strongly consistency
     /// A single root task execution. It won't track dependencies.
     /// Task will definitely include all invalidations that happened before the
     /// start of the task. It may or may not include invalidations that
     /// happened after that. It may see these invalidations partially
-    /// applied.
+    /// applied, unless it reads strongly consistent.
     /// Active until done. Automatically scheduled.
     Once(Pin<Box<dyn Future<Output = Result<RawVc>> + Send + 'static>>),
 }
@@ -76,6 +75,17 @@ pub enum PersistentTaskType {
 }
 
 impl PersistentTaskType {
+    /// Returns the concurrency limit of the executed function. Resolve tasks
+    /// are not limited, since they only call the function.
+    pub fn concurrency_limit(&self) -> Option<&'static ConcurrencyLimit> {
+        match self {
+            PersistentTaskType::Native(fn_id, _) => {
+                registry::get_function(*fn_id).concurrency_limit.as_ref()
+            }
+            PersistentTaskType::ResolveNative(..) | PersistentTaskType::ResolveTrait(..) => None,
+        }
+    }
+
     pub fn shrink_to_fit(&mut self) {
         match self {
             Self::Native(_, inputs) => inputs.shrink_to_fit(),
@@ -174,8 +184,44 @@ pub trait Backend: Sync + Send {
 
     fn invalidate_tasks(&self, tasks: Vec<TaskId>, turbo_tasks: &dyn TurboTasksBackendApi);
 
+    /// Like [Backend::invalidate_task], but records why the task has been
+    /// invalidated. Backends that don't keep track of reasons ignore it.
+    #[allow(unused_variables)]
+    fn invalidate_task_with_reason(
+        &self,
+        task: TaskId,
+        reason: Arc<InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.invalidate_task(task, turbo_tasks)
+    }
+
+    #[allow(unused_variables)]
+    fn invalidate_tasks_with_reason(
+        &self,
+        tasks: Vec<TaskId>,
+        reason: Arc<InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.invalidate_tasks(tasks, turbo_tasks)
+    }
+
+    /// Returns why the task has been invalidated, as long as it hasn't been
+    /// executed again.
+    #[allow(unused_variables)]
+    fn get_invalidation_reason(&self, task: TaskId) -> Option<Arc<InvalidationReason>> {
+        None
+    }
+
     fn get_task_description(&self, task: TaskId) -> String;
 
+    /// Returns the concurrency limit of the function executed by the task.
+    /// The scheduler waits for a free slot before starting an execution.
+    #[allow(unused_variables)]
+    fn get_task_concurrency_limit(&self, task: TaskId) -> Option<&'static ConcurrencyLimit> {
+        None
+    }
+
     type ExecutionScopeFuture<T: Future<Output = Result<()>> + Send + 'static>: Future<Output = Result<()>>
         + Send
         + 'static;
@@ -205,6 +251,7 @@ pub trait Backend: Sync + Send {
         duration: Duration,
         instant: Instant,
         stateful: bool,
+        volatile: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool;
 
@@ -0,0 +1,209 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::TaskId;
+
+const CATEGORY: &str = "turbo-tasks";
+
+/// Records a timeline of task executions as Chrome Trace Event JSON, which
+/// can be loaded into Perfetto or `chrome://tracing`.
+///
+/// Every task gets its own async track. It shows when the task was waiting to
+/// be executed after being scheduled, when it was executing, which cells it
+/// read and why it was invalidated.
+pub struct ChromeTraceRecorder {
+    start: Instant,
+    pid: u32,
+    state: Mutex<RecorderState>,
+}
+
+struct RecorderState {
+    writer: BufWriter<File>,
+    has_events: bool,
+    finished: bool,
+    error: Option<io::Error>,
+}
+
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: Cow<'a, str>,
+    cat: &'static str,
+    ph: &'static str,
+    /// Timestamp in microseconds
+    ts: f64,
+    pid: u32,
+    tid: u32,
+    id: usize,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    args: Value,
+}
+
+impl ChromeTraceRecorder {
+    /// Creates a recorder which writes to the file at `path`. Timestamps are
+    /// relative to `start`.
+    pub fn new(path: &Path, start: Instant) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(b"[\n")?;
+        Ok(Self {
+            start,
+            pid: std::process::id(),
+            state: Mutex::new(RecorderState {
+                writer,
+                has_events: false,
+                finished: false,
+                error: None,
+            }),
+        })
+    }
+
+    fn timestamp(&self, instant: Instant) -> f64 {
+        instant.saturating_duration_since(self.start).as_nanos() as f64 / 1000.0
+    }
+
+    fn write(&self, event: TraceEvent) {
+        let mut state = self.state.lock().unwrap();
+        let RecorderState {
+            writer,
+            has_events,
+            finished,
+            error,
+        } = &mut *state;
+        if *finished || error.is_some() {
+            return;
+        }
+        let mut write = || -> io::Result<()> {
+            if *has_events {
+                writer.write_all(b",\n")?;
+            }
+            serde_json::to_writer(&mut *writer, &event)?;
+            *has_events = true;
+            Ok(())
+        };
+        if let Err(err) = write() {
+            *error = Some(err);
+        }
+    }
+
+    fn task_event(
+        &self,
+        task: TaskId,
+        name: Cow<'_, str>,
+        ph: &'static str,
+        instant: Instant,
+        args: Value,
+    ) {
+        self.write(TraceEvent {
+            name,
+            cat: CATEGORY,
+            ph,
+            ts: self.timestamp(instant),
+            pid: self.pid,
+            tid: 0,
+            id: *task,
+            args,
+        });
+    }
+
+    /// Records the time a task was waiting for execution after it has been
+    /// scheduled.
+    pub fn task_scheduled(&self, task: TaskId, scheduled: Instant, start: Instant) {
+        self.task_event(
+            task,
+            Cow::Borrowed("scheduled"),
+            "b",
+            scheduled,
+            Value::Null,
+        );
+        self.task_event(task, Cow::Borrowed("scheduled"), "e", start, Value::Null);
+    }
+
+    /// Records a single execution of a task. `duration` is the time the task
+    /// was actually running, excluding the time it was waiting for other
+    /// tasks.
+    pub fn task_executed(
+        &self,
+        task: TaskId,
+        description: &str,
+        start: Instant,
+        end: Instant,
+        duration: Duration,
+        scheduling_delay: Duration,
+    ) {
+        self.task_event(
+            task,
+            Cow::Borrowed(description),
+            "b",
+            start,
+            json!({
+                "task": *task,
+                "duration_us": duration.as_micros() as u64,
+                "scheduling_delay_us": scheduling_delay.as_micros() as u64,
+            }),
+        );
+        self.task_event(task, Cow::Borrowed(description), "e", end, Value::Null);
+    }
+
+    /// Records that a task has been invalidated. `cause` describes the task
+    /// that updated a cell the invalidated task depends on, or the external
+    /// source of the invalidation.
+    pub fn task_invalidated(&self, task: TaskId, description: &str, cause: &str) {
+        self.task_event(
+            task,
+            Cow::Borrowed("invalidated"),
+            "n",
+            Instant::now(),
+            json!({
+                "task": description,
+                "cause": cause,
+            }),
+        );
+    }
+
+    /// Records that a task has read a cell of another task.
+    pub fn cell_read(&self, reader: TaskId, task: &str, value_type: &str) {
+        self.task_event(
+            reader,
+            Cow::Borrowed("read cell"),
+            "n",
+            Instant::now(),
+            json!({
+                "task": task,
+                "type": value_type,
+            }),
+        );
+    }
+
+    /// Flushes all recorded events to the file. The file is a valid trace
+    /// even when it's not finished.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(err) = state.error.take() {
+            return Err(err);
+        }
+        state.writer.flush()
+    }
+
+    /// Writes the end of the trace and flushes it. Events recorded afterwards
+    /// are ignored.
+    pub fn finish(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.finished {
+            return Ok(());
+        }
+        state.finished = true;
+        if let Some(err) = state.error.take() {
+            return Err(err);
+        }
+        state.writer.write_all(b"\n]\n")?;
+        state.writer.flush()
+    }
+}
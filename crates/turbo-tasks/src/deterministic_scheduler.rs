@@ -0,0 +1,178 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    mem::{replace, take},
+    pin::Pin,
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::TaskId;
+
+/// Controls in which order scheduled tasks are started.
+#[derive(Clone, Debug, Default)]
+pub enum SchedulingMode {
+    /// Tasks are spawned on the tokio runtime as soon as they are scheduled.
+    #[default]
+    Parallel,
+    /// Scheduled tasks are queued and started one by one in a pseudo-random
+    /// order derived from the seed. Running with the same seed on a current
+    /// thread tokio runtime results in the same execution order, different
+    /// seeds can be used to explore different orders.
+    Seeded(u64),
+    /// Scheduled tasks are started in the order of a previous recording. When
+    /// the execution diverges from the recording, the remaining tasks are
+    /// started in a pseudo-random order derived from the recording's seed.
+    Replay(ScheduleRecording),
+}
+
+/// The order in which tasks have been started by a deterministic scheduler.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRecording {
+    pub seed: u64,
+    /// Descriptions of the started tasks in order of their start
+    pub tasks: Vec<String>,
+}
+
+type TaskFuture = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+struct PendingTask {
+    id: TaskId,
+    description: Option<String>,
+    future: TaskFuture,
+}
+
+struct SchedulerState {
+    rng: SplitMix64,
+    replay: VecDeque<String>,
+    pending: Vec<PendingTask>,
+    /// true, while a driver is starting pending tasks
+    driving: bool,
+    recording: ScheduleRecording,
+}
+
+/// Queues scheduled tasks and decides which one is started next.
+pub(crate) struct DeterministicScheduler {
+    state: Mutex<SchedulerState>,
+}
+
+impl DeterministicScheduler {
+    pub fn new(mode: SchedulingMode) -> Option<Self> {
+        let (seed, replay) = match mode {
+            SchedulingMode::Parallel => return None,
+            SchedulingMode::Seeded(seed) => (seed, VecDeque::with_capacity(0)),
+            SchedulingMode::Replay(recording) => (recording.seed, recording.tasks.into()),
+        };
+        Some(Self {
+            state: Mutex::new(SchedulerState {
+                rng: SplitMix64(seed),
+                replay,
+                pending: Vec::new(),
+                driving: false,
+                recording: ScheduleRecording {
+                    seed,
+                    tasks: Vec::new(),
+                },
+            }),
+        })
+    }
+
+    /// Queues a task for execution. Returns true when the caller need to
+    /// start a driver which calls [DeterministicScheduler::next] until it
+    /// returns `None`.
+    pub fn enqueue(&self, id: TaskId, future: TaskFuture) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.pending.push(PendingTask {
+            id,
+            description: None,
+            future,
+        });
+        !replace(&mut state.driving, true)
+    }
+
+    /// Picks the next task to start. Returns `None` when no tasks are pending,
+    /// the driver has to stop then.
+    pub fn next(&self, describe: impl Fn(TaskId) -> String) -> Option<TaskFuture> {
+        loop {
+            let missing = {
+                let mut state = self.state.lock().unwrap();
+                if state.pending.is_empty() {
+                    state.driving = false;
+                    return None;
+                }
+                let missing = state
+                    .pending
+                    .iter()
+                    .filter(|task| task.description.is_none())
+                    .map(|task| task.id)
+                    .collect::<Vec<_>>();
+                if missing.is_empty() {
+                    return Some(state.pick());
+                }
+                missing
+            };
+            // Descriptions are computed without holding the lock, as the backend
+            // might schedule tasks while holding locks itself.
+            let descriptions = missing
+                .into_iter()
+                .map(|id| (id, describe(id)))
+                .collect::<Vec<_>>();
+            let mut state = self.state.lock().unwrap();
+            for (id, description) in descriptions {
+                for task in state.pending.iter_mut() {
+                    if task.id == id && task.description.is_none() {
+                        task.description = Some(description.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn recording(&self) -> ScheduleRecording {
+        self.state.lock().unwrap().recording.clone()
+    }
+}
+
+impl SchedulerState {
+    fn pick(&mut self) -> TaskFuture {
+        let replayed = self.replay.front().and_then(|expected| {
+            self.pending
+                .iter()
+                .position(|task| task.description.as_ref() == Some(expected))
+        });
+        let index = match replayed {
+            Some(index) => {
+                self.replay.pop_front();
+                index
+            }
+            None => {
+                // The execution diverged from the recording
+                take(&mut self.replay);
+                (self.rng.next() % self.pending.len() as u64) as usize
+            }
+        };
+        let PendingTask {
+            description,
+            future,
+            ..
+        } = self.pending.remove(index);
+        self.recording.tasks.push(description.unwrap());
+        future
+    }
+}
+
+/// A small and fast pseudo-random number generator, good enough to shuffle
+/// task orders.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
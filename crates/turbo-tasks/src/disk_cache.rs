@@ -0,0 +1,266 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::{DeterministicHasher, Xxh3Hash64Hasher};
+
+use crate::{
+    backend::CellContent,
+    id::{with_task_id_mapping, IdMapping},
+    manager::{find_cell_by_type, read_task_cell_untracked, read_task_output_untracked},
+    registry::{get_value_type, get_value_type_global_name},
+    turbo_tasks, RawVc, SharedReference, SharedValue, TaskId, TaskInput, TryJoinIterExt,
+    ValueTypeId,
+};
+
+/// Changes whenever the format of cache keys or entries changes.
+const CACHE_VERSION: u32 = 2;
+
+static DISK_CACHE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Enables the disk cache for functions declared with
+/// `#[turbo_tasks::function(cache = "disk")]`, storing their results in the
+/// given directory. Without it these functions are only cached in memory like
+/// all other functions. Only the first call has an effect.
+///
+/// Results are keyed by the name and crate version of the function and the
+/// values of its inputs. Vcs in the inputs are keyed by the values of their
+/// cells, so a cached function must only depend on these values and not read
+/// anything else, e. g. files behind a referenced path. Values are hashed with
+/// [DeterministicHash](turbo_tasks_hash::DeterministicHash), so the types of
+/// these cells need to derive it. Calls with other inputs are not cached.
+///
+/// This rules out parsing and transforming modules: their inputs reference
+/// the source asset and its file content through Vcs, and their results hold
+/// process local ASTs, source maps and hygiene marks, which can't be
+/// serialized.
+pub fn enable_disk_cache(dir: impl Into<PathBuf>) {
+    let _ = DISK_CACHE_DIR.set(dir.into());
+}
+
+/// A cached result of a function call.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// The function name and crate version, to detect hash collisions
+    function: String,
+    output: SharedReference,
+}
+
+/// Task ids are not stable across restarts, so outputs referencing tasks
+/// can't be cached. This mapping detects them during serialization.
+#[derive(Default)]
+struct DetectTaskIds(Cell<bool>);
+
+impl IdMapping<TaskId> for DetectTaskIds {
+    fn forward(&self, _id: TaskId) -> usize {
+        self.0.set(true);
+        0
+    }
+
+    fn backward(&self, _id: usize) -> TaskId {
+        unreachable!("DetectTaskIds is only used for serialization")
+    }
+}
+
+/// Serializes a value when it's serializable and doesn't reference any tasks.
+fn to_stable_json<T: Serialize>(value: &T) -> Option<String> {
+    let detect = DetectTaskIds::default();
+    let json = with_task_id_mapping(&detect, || serde_json::to_string(value)).ok()?;
+    (!detect.0.get()).then_some(json)
+}
+
+fn resolve_to_values(
+    inputs: Vec<TaskInput>,
+) -> Pin<Box<dyn Future<Output = Result<Vec<TaskInput>>> + Send>> {
+    Box::pin(
+        inputs
+            .into_iter()
+            .map(|input| async move {
+                match input {
+                    TaskInput::List(list) => Ok(TaskInput::List(resolve_to_values(list).await?)),
+                    input => input.resolve_to_value().await,
+                }
+            })
+            .try_join(),
+    )
+}
+
+/// Tags written before each input, so different kinds of inputs don't hash the
+/// same.
+#[repr(u8)]
+enum Tag {
+    List,
+    String,
+    Bool,
+    Usize,
+    I32,
+    U32,
+    U64,
+    Nothing,
+    Value,
+}
+
+/// Hashes a value by its type and its [DeterministicHash] implementation.
+/// Returns false when the type doesn't implement it.
+fn hash_value(
+    hasher: &mut Xxh3Hash64Hasher,
+    ty: ValueTypeId,
+    value: &(dyn Any + Send + Sync),
+) -> bool {
+    hasher.write_u8(Tag::Value as u8);
+    hasher.write_value(get_value_type_global_name(ty));
+    get_value_type(ty).any_deterministic_hash(value, hasher)
+}
+
+/// Hashes a resolved input. Returns false when it or any value in it can't be
+/// hashed deterministically.
+fn hash_input(hasher: &mut Xxh3Hash64Hasher, input: &TaskInput) -> bool {
+    match input {
+        TaskInput::List(list) => {
+            hasher.write_u8(Tag::List as u8);
+            hasher.write_usize(list.len());
+            list.iter().all(|input| hash_input(hasher, input))
+        }
+        TaskInput::String(value) => {
+            hasher.write_u8(Tag::String as u8);
+            hasher.write_ref(value);
+            true
+        }
+        TaskInput::Bool(value) => {
+            hasher.write_u8(Tag::Bool as u8);
+            hasher.write_ref(value);
+            true
+        }
+        TaskInput::Usize(value) => {
+            hasher.write_u8(Tag::Usize as u8);
+            hasher.write_ref(value);
+            true
+        }
+        TaskInput::I32(value) => {
+            hasher.write_u8(Tag::I32 as u8);
+            hasher.write_ref(value);
+            true
+        }
+        TaskInput::U32(value) => {
+            hasher.write_u8(Tag::U32 as u8);
+            hasher.write_ref(value);
+            true
+        }
+        TaskInput::U64(value) => {
+            hasher.write_u8(Tag::U64 as u8);
+            hasher.write_ref(value);
+            true
+        }
+        TaskInput::Nothing => {
+            hasher.write_u8(Tag::Nothing as u8);
+            true
+        }
+        TaskInput::SharedValue(SharedValue(Some(ty), value)) => {
+            hash_value(hasher, *ty, &*value.clone().magic_any_arc())
+        }
+        TaskInput::SharedReference(SharedReference(Some(ty), value)) => {
+            hash_value(hasher, *ty, &**value)
+        }
+        // Task ids are not stable across restarts and transient or untyped
+        // values can't be hashed
+        TaskInput::TaskOutput(_)
+        | TaskInput::TaskCell(..)
+        | TaskInput::TransientSharedValue(_)
+        | TaskInput::SharedValue(SharedValue(None, _))
+        | TaskInput::SharedReference(SharedReference(None, _)) => false,
+    }
+}
+
+/// Computes the cache key of a function call from the values of its inputs.
+/// Vcs are keyed by the values of their cells. Returns `None` when any of
+/// these values doesn't implement [DeterministicHash], e. g. because it
+/// references other Vcs.
+async fn cache_key(function: &str, inputs: Vec<TaskInput>) -> Result<Option<u64>> {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_value(CACHE_VERSION);
+    hasher.write_ref(&function);
+    // Reading the inputs tracks them as dependencies, which is fine since the
+    // output depends on them anyway
+    let inputs = resolve_to_values(inputs).await?;
+    if !inputs.iter().all(|input| hash_input(&mut hasher, input)) {
+        return Ok(None);
+    }
+    Ok(Some(hasher.finish()))
+}
+
+async fn read_entry(path: &Path, function: &str) -> Option<SharedReference> {
+    let bytes = tokio::fs::read(path).await.ok()?;
+    let entry: CacheEntry = serde_json::from_slice(&bytes).ok()?;
+    (entry.function == function).then_some(entry.output)
+}
+
+async fn write_entry(path: &Path, entry: &CacheEntry) -> Result<()> {
+    let Some(json) = to_stable_json(entry) else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    // Write to a temporary file first, so concurrent processes never read a
+    // partially written entry
+    let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    tokio::fs::write(&temp_path, json).await?;
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+/// Executes a function with the disk cache. The cache is skipped when it's
+/// not enabled, when inputs are not serializable or when the output is not
+/// serializable or references other tasks. Collectibles emitted by the
+/// function are not cached.
+pub(crate) async fn with_disk_cache(
+    name: &str,
+    salt: &str,
+    inputs: Vec<TaskInput>,
+    execute: impl Future<Output = Result<RawVc>>,
+) -> Result<RawVc> {
+    let Some(dir) = DISK_CACHE_DIR.get() else {
+        return execute.await;
+    };
+    let function = format!("{salt}::{name}");
+    let Some(key) = cache_key(&function, inputs).await? else {
+        return execute.await;
+    };
+    let path = dir.join(format!("{key:016x}.json"));
+
+    if let Some(output) = read_entry(&path, &function).await {
+        if let SharedReference(Some(ty), _) = output {
+            let cell = find_cell_by_type(ty);
+            cell.update_shared_reference(output);
+            return Ok(cell.into());
+        }
+    }
+
+    let result = execute.await?;
+    // INVALIDATION: The output is read untracked, since the task would
+    // otherwise depend on its own cells. It only depends on the inputs.
+    let tt = turbo_tasks();
+    let mut current = result;
+    let content = loop {
+        match current {
+            RawVc::TaskOutput(task) => {
+                current = read_task_output_untracked(&*tt, task, false).await?;
+            }
+            RawVc::TaskCell(task, index) => {
+                break read_task_cell_untracked(&*tt, task, index).await?;
+            }
+        }
+    };
+    if let CellContent(Some(output)) = content {
+        // The disk cache is best effort, failing to write it is not an error
+        let _ = write_entry(&path, &CacheEntry { function, output }).await;
+    }
+    Ok(result)
+}
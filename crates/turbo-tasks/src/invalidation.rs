@@ -0,0 +1,62 @@
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{CellId, TaskId};
+
+/// The immediate cause that made a task dirty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidationCause {
+    /// An [Invalidator](crate::Invalidator) has been called, e. g. by a file
+    /// watcher. The string describes the external change, like the path of
+    /// the changed file.
+    External(Cow<'static, str>),
+    /// The task has read a cell of `task` which has been updated.
+    CellChanged { task: TaskId, cell: CellId },
+    /// The task has read the output or the collectibles of `task` which have
+    /// changed.
+    OutputChanged { task: TaskId },
+}
+
+impl InvalidationCause {
+    /// The task that caused the invalidation, if it wasn't caused externally.
+    pub fn task(&self) -> Option<TaskId> {
+        match self {
+            InvalidationCause::External(_) => None,
+            InvalidationCause::CellChanged { task, .. }
+            | InvalidationCause::OutputChanged { task } => Some(*task),
+        }
+    }
+}
+
+/// Why a task has been invalidated the last time.
+///
+/// Reasons are chained: when the causing task has been re-executed because of
+/// an invalidation itself, `parent` is the reason for that, e. g. a file
+/// change that invalidated the task reading the file, which updated a cell
+/// that invalidated this task.
+#[derive(Clone, Debug)]
+pub struct InvalidationReason {
+    pub cause: InvalidationCause,
+    pub parent: Option<Arc<InvalidationReason>>,
+}
+
+impl InvalidationReason {
+    pub fn new(cause: InvalidationCause, parent: Option<Arc<InvalidationReason>>) -> Self {
+        Self { cause, parent }
+    }
+
+    pub fn external(description: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(InvalidationCause::External(description.into()), None)
+    }
+
+    /// Iterates the chain of causes, starting with the immediate cause and
+    /// ending with the original one.
+    pub fn chain(&self) -> impl Iterator<Item = &InvalidationCause> {
+        std::iter::successors(Some(self), |reason| reason.parent.as_deref())
+            .map(|reason| &reason.cause)
+    }
+
+    /// The original cause of the invalidation, usually an external change.
+    pub fn root_cause(&self) -> &InvalidationCause {
+        self.chain().last().unwrap()
+    }
+}
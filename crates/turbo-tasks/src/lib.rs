@@ -34,14 +34,19 @@
 #![feature(new_uninit)]
 
 pub mod backend;
+mod chrome_trace;
 mod collectibles;
 mod completion;
 pub mod debug;
+mod deterministic_scheduler;
+mod disk_cache;
 mod display;
 pub mod event;
 mod id;
 mod id_factory;
+mod invalidation;
 mod join_iter_ext;
+mod limited_future;
 mod magic_any;
 mod manager;
 mod native_function;
@@ -65,18 +70,21 @@ mod value_type;
 pub use anyhow::{Error, Result};
 pub use collectibles::CollectiblesSource;
 pub use completion::{Completion, CompletionVc, CompletionsVc};
+pub use deterministic_scheduler::{ScheduleRecording, SchedulingMode};
+pub use disk_cache::enable_disk_cache;
 pub use display::{ValueToString, ValueToStringVc};
 pub use id::{
     with_task_id_mapping, without_task_id_mapping, FunctionId, IdMapping, TaskId, TraitTypeId,
     ValueTypeId,
 };
+pub use invalidation::{InvalidationCause, InvalidationReason};
 pub use join_iter_ext::{JoinIterExt, TryJoinIterExt};
 pub use manager::{
-    dynamic_call, emit, get_invalidator, mark_stateful, run_once, spawn_blocking, spawn_thread,
-    trait_call, turbo_tasks, Invalidator, StatsType, TaskIdProvider, TurboTasks, TurboTasksApi,
-    TurboTasksBackendApi, TurboTasksCallApi,
+    dynamic_call, emit, get_invalidator, get_persisted_invalidator, mark_stateful, run_once,
+    spawn_blocking, spawn_thread, trait_call, turbo_tasks, Invalidator, StatsType, TaskIdProvider,
+    TurboTasks, TurboTasksApi, TurboTasksBackendApi, TurboTasksCallApi,
 };
-pub use native_function::{NativeFunction, NativeFunctionVc};
+pub use native_function::{ConcurrencyLimit, NativeFunction, NativeFunctionVc};
 pub use nothing::{Nothing, NothingVc};
 pub use raw_vc::{CellId, CollectiblesFuture, RawVc, ReadRawVcFuture, ResolveTypeError};
 pub use read_ref::ReadRef;
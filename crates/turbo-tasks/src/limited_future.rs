@@ -0,0 +1,101 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use tokio::{sync::SemaphorePermit, task::futures::TaskLocalFuture, task_local};
+
+use crate::ConcurrencyLimit;
+
+task_local! {
+    /// Number of reads of other tasks the current limited execution is
+    /// waiting for
+    static BLOCKED_READS: Arc<AtomicUsize>;
+}
+
+type AcquireFuture = Pin<Box<dyn Future<Output = SemaphorePermit<'static>> + Send>>;
+
+pin_project! {
+    /// Runs a task execution within a [ConcurrencyLimit]. The slot is released
+    /// while the execution waits for other tasks and acquired again before it
+    /// continues. That way a limited function can (indirectly) await calls to
+    /// itself without waiting for a slot that is held by the caller.
+    pub struct LimitedFuture<F: Future> {
+        limit: &'static ConcurrencyLimit,
+        permit: Option<SemaphorePermit<'static>>,
+        acquire: Option<AcquireFuture>,
+        blocked_reads: Arc<AtomicUsize>,
+        #[pin]
+        future: TaskLocalFuture<Arc<AtomicUsize>, F>,
+    }
+}
+
+impl<F: Future> LimitedFuture<F> {
+    /// Creates a future that holds the already acquired `permit` until the
+    /// execution waits for other tasks.
+    pub fn new(
+        limit: &'static ConcurrencyLimit,
+        permit: SemaphorePermit<'static>,
+        future: F,
+    ) -> Self {
+        let blocked_reads = Arc::new(AtomicUsize::new(0));
+        Self {
+            limit,
+            permit: Some(permit),
+            acquire: None,
+            future: BLOCKED_READS.scope(blocked_reads.clone(), future),
+            blocked_reads,
+        }
+    }
+}
+
+impl<F: Future> Future for LimitedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if this.permit.is_none() {
+            let limit = *this.limit;
+            let acquire = this
+                .acquire
+                .get_or_insert_with(|| Box::pin(limit.acquire()));
+            match acquire.as_mut().poll(cx) {
+                Poll::Ready(permit) => {
+                    *this.permit = Some(permit);
+                    *this.acquire = None;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        let result = this.future.poll(cx);
+        if result.is_ready() || this.blocked_reads.load(Ordering::Acquire) > 0 {
+            *this.permit = None;
+        }
+        result
+    }
+}
+
+/// Marks the current execution as waiting for another task until the guard
+/// is dropped. Returns `None` outside of a limited execution.
+pub fn blocked_read() -> Option<BlockedRead> {
+    BLOCKED_READS
+        .try_with(|blocked_reads| {
+            blocked_reads.fetch_add(1, Ordering::AcqRel);
+            BlockedRead(blocked_reads.clone())
+        })
+        .ok()
+}
+
+pub struct BlockedRead(Arc<AtomicUsize>);
+
+impl Drop for BlockedRead {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
@@ -6,6 +6,7 @@ use std::{
     hash::Hash,
     mem::take,
     panic::AssertUnwindSafe,
+    path::Path,
     pin::Pin,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
@@ -15,18 +16,23 @@ use std::{
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use auto_hash_map::AutoSet;
+use dashmap::DashMap;
 use futures::FutureExt;
 use nohash_hasher::BuildNoHashHasher;
 use serde::{de::Visitor, Deserialize, Serialize};
-use tokio::{runtime::Handle, select, task_local};
+use tokio::{runtime::Handle, select, sync::oneshot, task_local};
 
 use crate::{
     backend::{Backend, CellContent, PersistentTaskType, TransientTaskType},
+    chrome_trace::ChromeTraceRecorder,
+    deterministic_scheduler::{DeterministicScheduler, ScheduleRecording, SchedulingMode},
     event::{Event, EventListener},
     id::{BackendJobId, FunctionId, TraitTypeId},
     id_factory::IdFactory,
+    invalidation::{InvalidationCause, InvalidationReason},
+    limited_future::{blocked_read, LimitedFuture},
     raw_vc::{CellId, RawVc},
     registry,
     task_input::{SharedReference, TaskInput},
@@ -58,6 +64,12 @@ pub trait TurboTasksCallApi: Sync + Send {
 
 pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
     fn invalidate(&self, task: TaskId);
+    fn invalidate_with_reason(&self, task: TaskId, reason: InvalidationReason);
+
+    /// Returns why the task has been invalidated, until it has been executed
+    /// again. Reasons are only tracked with full stats and if the backend
+    /// supports that.
+    fn invalidation_reason(&self, task: TaskId) -> Option<Arc<InvalidationReason>>;
 
     /// Eagerly notifies all tasks that were scheduled for notifications via
     /// `schedule_notify_tasks_set()`
@@ -169,6 +181,13 @@ pub trait TurboTasksBackendApi: TaskIdProvider + TurboTasksCallApi + Sync + Send
     fn set_stats_type(&self, stats_type: StatsType);
     /// Returns the duration from the start of the program to the given instant.
     fn program_duration_until(&self, instant: Instant) -> Duration;
+
+    /// Cancels the running execution of a task at its next await point. The
+    /// backend must not need the result of the execution anymore, e. g.
+    /// because the task has been invalidated during execution. The execution
+    /// will complete without reporting a result. Returns false and does
+    /// nothing when the task isn't executing.
+    fn cancel_task_execution(&self, task: TaskId) -> bool;
 }
 
 impl StatsType {
@@ -222,17 +241,32 @@ pub struct TurboTasks<B: Backend + 'static> {
     // locking overhead.
     enable_full_stats: AtomicBool,
     program_start: Instant,
+    trace_recording: AtomicBool,
+    trace_recorder: Mutex<Option<Arc<ChromeTraceRecorder>>>,
+    deterministic_scheduler: Option<DeterministicScheduler>,
+    /// Running task executions, which can be cancelled by sending to the
+    /// channel
+    running_executions: DashMap<TaskId, oneshot::Sender<()>, BuildNoHashHasher<TaskId>>,
 }
 
 #[derive(Default)]
 struct CurrentTaskState {
     /// Affected [Task]s, that are tracked during task execution
     /// These tasks will be invalidated when the execution finishes
-    /// or before reading a cell value
-    tasks_to_notify: Vec<TaskId>,
+    /// or before reading a cell value.
+    /// Each task is stored with the cell of the current task that has been
+    /// updated, or `None` when the output or collectibles changed
+    tasks_to_notify: Vec<(Option<CellId>, TaskId)>,
+
+    /// The cell that is currently updated by the current task
+    updating_cell: Option<CellId>,
 
     // true, if the current task has state in cells
     stateful: bool,
+
+    // true, if the current task depends on external state and can be
+    // invalidated by an Invalidator
+    volatile: bool,
 }
 
 // TODO implement our own thread pool and make these thread locals instead
@@ -253,7 +287,14 @@ impl<B: Backend> TurboTasks<B> {
     // that should be safe as long tasks can't outlife turbo task
     // so we probably want to make sure that all tasks are joined
     // when trying to drop turbo tasks
-    pub fn new(mut backend: B) -> Arc<Self> {
+    pub fn new(backend: B) -> Arc<Self> {
+        Self::new_with_scheduling_mode(backend, SchedulingMode::Parallel)
+    }
+
+    /// Creates a new instance which starts tasks according to the given
+    /// [SchedulingMode]. Deterministic modes need to run on a current thread
+    /// tokio runtime to get reproducible results.
+    pub fn new_with_scheduling_mode(mut backend: B, mode: SchedulingMode) -> Arc<Self> {
         let task_id_factory = IdFactory::new();
         backend.initialize(&task_id_factory);
         let this = Arc::new_cyclic(|this| Self {
@@ -273,6 +314,10 @@ impl<B: Backend> TurboTasks<B> {
             event_background: Event::new(|| "TurboTasks::event_background".to_string()),
             enable_full_stats: AtomicBool::new(false),
             program_start: Instant::now(),
+            trace_recording: AtomicBool::new(false),
+            trace_recorder: Mutex::new(None),
+            deterministic_scheduler: DeterministicScheduler::new(mode),
+            running_executions: DashMap::with_hasher(Default::default()),
         });
         this.backend.startup(&*this);
         this
@@ -297,9 +342,12 @@ impl<B: Backend> TurboTasks<B> {
         id
     }
 
-    // TODO make sure that all dependencies settle before reading them
     /// Creates a new root task, that is only executed once.
     /// Dependencies will not invalidate the task.
+    ///
+    /// Reads in the task might observe partially applied changes. Use
+    /// strongly consistent reads to wait until all dependencies of the read
+    /// value have settled.
     #[track_caller]
     pub fn spawn_once_task(
         &self,
@@ -392,6 +440,11 @@ impl<B: Backend> TurboTasks<B> {
         #[cfg(feature = "tokio_tracing")]
         let description = self.backend.get_task_description(task_id);
 
+        let mut scheduled_at = self
+            .trace_recording
+            .load(Ordering::Relaxed)
+            .then(Instant::now);
+
         let this = self.pin();
         let future = async move {
             #[allow(clippy::blocks_in_if_conditions)]
@@ -400,18 +453,59 @@ impl<B: Backend> TurboTasks<B> {
                     if this.stopped.load(Ordering::Acquire) {
                         return false;
                     }
+                    // Wait for a free slot when the function has a concurrency limit. The task
+                    // stays scheduled until then.
+                    let limit = this.backend.get_task_concurrency_limit(task_id);
+                    let permit = match limit {
+                        Some(limit) => Some((limit, limit.acquire().await)),
+                        None => None,
+                    };
+                    // Register before starting the execution, so cancellations are not missed
+                    let (cancel_sender, cancel_receiver) = oneshot::channel();
+                    this.running_executions.insert(task_id, cancel_sender);
                     if let Some(execution) = this.backend.try_start_task_execution(task_id, &*this)
                     {
+                        let future: Pin<Box<dyn Future<Output = Result<RawVc>> + Send>> =
+                            match permit {
+                                Some((limit, permit)) => {
+                                    Box::pin(LimitedFuture::new(limit, permit, execution.future))
+                                }
+                                None => execution.future,
+                            };
+                        let start = Instant::now();
+                        let cancellable = async move {
+                            select! {
+                                result = future => Some(result),
+                                Ok(()) = cancel_receiver => None,
+                            }
+                        };
                         // Setup thread locals
                         let (result, duration, instant) = CELL_COUNTERS
                             .scope(Default::default(), async {
-                                let (result, duration, instant) = TimedFuture::new(
-                                    AssertUnwindSafe(execution.future).catch_unwind(),
-                                )
-                                .await;
+                                let (result, duration, instant) =
+                                    TimedFuture::new(AssertUnwindSafe(cancellable).catch_unwind())
+                                        .await;
                                 (result, duration, instant)
                             })
                             .await;
+                        this.running_executions.remove(&task_id);
+                        if let Some(recorder) = this.trace_recorder() {
+                            let scheduling_delay = match scheduled_at.take() {
+                                Some(scheduled_at) => {
+                                    recorder.task_scheduled(task_id, scheduled_at, start);
+                                    start - scheduled_at
+                                }
+                                None => Duration::ZERO,
+                            };
+                            recorder.task_executed(
+                                task_id,
+                                &this.backend.get_task_description(task_id),
+                                start,
+                                instant,
+                                duration,
+                                scheduling_delay,
+                            );
+                        }
                         if cfg!(feature = "log_function_stats") && duration.as_millis() > 1000 {
                             println!(
                                 "{} took {}",
@@ -426,15 +520,28 @@ impl<B: Backend> TurboTasks<B> {
                                 Err(_) => None,
                             },
                         });
-                        this.backend.task_execution_result(task_id, result, &*this);
-                        let stateful = this.finish_current_task_state();
-                        let reexecute = this
-                            .backend
-                            .task_execution_completed(task_id, duration, instant, stateful, &*this);
+                        match result {
+                            // A cancelled execution has no result, the task will be executed
+                            // again when needed
+                            Ok(None) => {}
+                            Ok(Some(result)) => {
+                                this.backend
+                                    .task_execution_result(task_id, Ok(result), &*this)
+                            }
+                            Err(message) => {
+                                this.backend
+                                    .task_execution_result(task_id, Err(message), &*this)
+                            }
+                        }
+                        let (stateful, volatile) = this.finish_current_task_state();
+                        let reexecute = this.backend.task_execution_completed(
+                            task_id, duration, instant, stateful, volatile, &*this,
+                        );
                         if !reexecute {
                             return false;
                         }
                     } else {
+                        this.running_executions.remove(&task_id);
                         return false;
                     }
                     true
@@ -450,6 +557,13 @@ impl<B: Backend> TurboTasks<B> {
             CURRENT_TASK_ID.scope(task_id, self.backend.execution_scope(task_id, future)),
         );
 
+        if let Some(scheduler) = &self.deterministic_scheduler {
+            if scheduler.enqueue(task_id, Box::pin(future)) {
+                self.spawn_deterministic_driver();
+            }
+            return;
+        }
+
         #[cfg(feature = "tokio_tracing")]
         tokio::task::Builder::new()
             .name(&description)
@@ -459,6 +573,34 @@ impl<B: Backend> TurboTasks<B> {
         tokio::task::spawn(future);
     }
 
+    /// Starts the pending tasks of the deterministic scheduler one by one,
+    /// until no more tasks are pending.
+    fn spawn_deterministic_driver(&self) {
+        let this = self.pin();
+        tokio::task::spawn(async move {
+            let scheduler = this.deterministic_scheduler.as_ref().unwrap();
+            loop {
+                // Give the started tasks the chance to run until they wait for
+                // something, which might schedule more tasks.
+                tokio::task::yield_now().await;
+                let Some(future) =
+                    scheduler.next(|task| this.backend.get_task_description(task)) else {
+                    break;
+                };
+                tokio::task::spawn(future);
+            }
+        });
+    }
+
+    /// Returns the order in which tasks have been started so far, when a
+    /// deterministic [SchedulingMode] is used. It can be passed to
+    /// [SchedulingMode::Replay] to reproduce a run.
+    pub fn schedule_recording(&self) -> Option<ScheduleRecording> {
+        self.deterministic_scheduler
+            .as_ref()
+            .map(|scheduler| scheduler.recording())
+    }
+
     fn begin_primary_job(&self) {
         if self
             .currently_scheduled_tasks
@@ -483,6 +625,9 @@ impl<B: Backend> TurboTasks<B> {
             == 1
         {
             self.backend.idle_start(self);
+            if let Some(recorder) = self.trace_recorder() {
+                let _ = recorder.flush();
+            }
             // That's not super race-condition-safe, but it's only for
             // statistical reasons
             let total = self.scheduled_tasks.load(Ordering::Acquire);
@@ -684,23 +829,109 @@ impl<B: Backend> TurboTasks<B> {
         }));
     }
 
-    fn finish_current_task_state(&self) -> bool {
+    /// Returns the (stateful, volatile) flags of the current task.
+    fn finish_current_task_state(&self) -> (bool, bool) {
         CURRENT_TASK_STATE.with(|cell| {
             let CurrentTaskState {
                 tasks_to_notify,
                 stateful,
+                volatile,
+                ..
             } = &mut *cell.borrow_mut();
             let tasks = take(tasks_to_notify);
             if !tasks.is_empty() {
-                self.backend.invalidate_tasks(tasks, self);
+                self.notify_tasks(tasks);
             }
-            *stateful
+            (*stateful, *volatile)
         })
     }
 
+    /// Invalidates tasks that depend on changed cells or the output of the
+    /// current task. With full stats, the current task is recorded as the
+    /// cause of the invalidation.
+    fn notify_tasks(&self, tasks: Vec<(Option<CellId>, TaskId)>) {
+        let current = match CURRENT_TASK_ID.try_with(|id| *id) {
+            Ok(current) if self.stats_type().is_full() => current,
+            _ => {
+                self.backend
+                    .invalidate_tasks(tasks.into_iter().map(|(_, task)| task).collect(), self);
+                return;
+            }
+        };
+        let parent = self.backend.get_invalidation_reason(current);
+        let mut tasks_by_cell: HashMap<Option<CellId>, Vec<TaskId>> = HashMap::new();
+        for (cell, task) in tasks {
+            tasks_by_cell.entry(cell).or_default().push(task);
+        }
+        for (cell, tasks) in tasks_by_cell {
+            let cause = match cell {
+                Some(cell) => InvalidationCause::CellChanged {
+                    task: current,
+                    cell,
+                },
+                None => InvalidationCause::OutputChanged { task: current },
+            };
+            self.backend.invalidate_tasks_with_reason(
+                tasks,
+                Arc::new(InvalidationReason::new(cause, parent.clone())),
+                self,
+            );
+        }
+    }
+
     pub fn backend(&self) -> &B {
         &self.backend
     }
+
+    /// Starts recording a timeline of task executions, scheduling delays,
+    /// invalidations and cell reads to the file at `path`. The file uses the
+    /// Chrome Trace Event format and can be loaded into Perfetto.
+    ///
+    /// Recording is flushed whenever turbo-tasks becomes idle, so the trace can
+    /// be inspected while recording continues.
+    pub fn start_trace_recording(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let recorder = ChromeTraceRecorder::new(path, self.program_start)
+            .with_context(|| format!("creating trace file {}", path.display()))?;
+        if let Some(previous) = self
+            .trace_recorder
+            .lock()
+            .unwrap()
+            .replace(Arc::new(recorder))
+        {
+            previous.finish()?;
+        }
+        self.trace_recording.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Stops recording and finishes the trace file.
+    pub fn stop_trace_recording(&self) -> Result<()> {
+        self.trace_recording.store(false, Ordering::Release);
+        if let Some(recorder) = self.trace_recorder.lock().unwrap().take() {
+            recorder.finish()?;
+        }
+        Ok(())
+    }
+
+    fn trace_recorder(&self) -> Option<Arc<ChromeTraceRecorder>> {
+        if !self.trace_recording.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.trace_recorder.lock().unwrap().clone()
+    }
+
+    fn trace_notified_tasks(&self, tasks: impl Iterator<Item = TaskId>) {
+        if let Some(recorder) = self.trace_recorder() {
+            let cause = match CURRENT_TASK_ID.try_with(|id| *id) {
+                Ok(task) => self.backend.get_task_description(task),
+                Err(_) => "background job".to_string(),
+            };
+            for task in tasks {
+                recorder.task_invalidated(task, &self.backend.get_task_description(task), &cause);
+            }
+        }
+    }
 }
 
 impl<B: Backend> TurboTasksCallApi for TurboTasks<B> {
@@ -747,9 +978,36 @@ impl<B: Backend> TurboTasksCallApi for TurboTasks<B> {
 
 impl<B: Backend> TurboTasksApi for TurboTasks<B> {
     fn invalidate(&self, task: TaskId) {
+        if let Some(recorder) = self.trace_recorder() {
+            recorder.task_invalidated(
+                task,
+                &self.backend.get_task_description(task),
+                "invalidator",
+            );
+        }
         self.backend.invalidate_task(task, self);
     }
 
+    fn invalidate_with_reason(&self, task: TaskId, reason: InvalidationReason) {
+        if let Some(recorder) = self.trace_recorder() {
+            let cause = match &reason.cause {
+                InvalidationCause::External(description) => description.as_ref(),
+                _ => "invalidator",
+            };
+            recorder.task_invalidated(task, &self.backend.get_task_description(task), cause);
+        }
+        if self.stats_type().is_full() {
+            self.backend
+                .invalidate_task_with_reason(task, Arc::new(reason), self);
+        } else {
+            self.backend.invalidate_task(task, self);
+        }
+    }
+
+    fn invalidation_reason(&self, task: TaskId) -> Option<Arc<InvalidationReason>> {
+        self.backend.get_invalidation_reason(task)
+    }
+
     fn notify_scheduled_tasks(&self) {
         let _ = CURRENT_TASK_STATE.try_with(|cell| {
             let CurrentTaskState {
@@ -759,7 +1017,7 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
             if tasks.is_empty() {
                 return;
             }
-            self.backend.invalidate_tasks(tasks, self);
+            self.notify_tasks(tasks);
         });
     }
 
@@ -790,8 +1048,18 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
         task: TaskId,
         index: CellId,
     ) -> Result<Result<CellContent, EventListener>> {
-        self.backend
-            .try_read_task_cell(task, index, current_task("reading Vcs"), self)
+        let reader = current_task("reading Vcs");
+        let result = self.backend.try_read_task_cell(task, index, reader, self);
+        if let Some(recorder) = self.trace_recorder() {
+            if let Ok(Ok(_)) = &result {
+                recorder.cell_read(
+                    reader,
+                    &self.backend.get_task_description(task),
+                    &registry::get_value_type(index.type_id).name,
+                );
+            }
+        }
+        result
     }
 
     fn try_read_task_cell_untracked(
@@ -859,12 +1127,14 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
     }
 
     fn update_current_task_cell(&self, index: CellId, content: CellContent) {
+        let _ = CURRENT_TASK_STATE.try_with(|cell| cell.borrow_mut().updating_cell = Some(index));
         self.backend.update_task_cell(
             current_task("cellting turbo_tasks values"),
             index,
             content,
             self,
         );
+        let _ = CURRENT_TASK_STATE.try_with(|cell| cell.borrow_mut().updating_cell = None);
     }
 }
 
@@ -924,11 +1194,15 @@ impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
     /// Enqueues tasks for notification of changed dependencies. This will
     /// eventually call `dependent_cell_updated()` on all tasks.
     fn schedule_notify_tasks(&self, tasks: &[TaskId]) {
+        self.trace_notified_tasks(tasks.iter().copied());
         let result = CURRENT_TASK_STATE.try_with(|cell| {
             let CurrentTaskState {
-                tasks_to_notify, ..
+                tasks_to_notify,
+                updating_cell,
+                ..
             } = &mut *cell.borrow_mut();
-            tasks_to_notify.extend(tasks.iter());
+            let cell = *updating_cell;
+            tasks_to_notify.extend(tasks.iter().map(|&task| (cell, task)));
         });
         if result.is_err() {
             self.backend.invalidate_tasks(tasks.to_vec(), self);
@@ -938,11 +1212,15 @@ impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
     /// Enqueues tasks for notification of changed dependencies. This will
     /// eventually call `dependent_cell_updated()` on all tasks.
     fn schedule_notify_tasks_set(&self, tasks: &AutoSet<TaskId>) {
+        self.trace_notified_tasks(tasks.iter().copied());
         let result = CURRENT_TASK_STATE.try_with(|cell| {
             let CurrentTaskState {
-                tasks_to_notify, ..
+                tasks_to_notify,
+                updating_cell,
+                ..
             } = &mut *cell.borrow_mut();
-            tasks_to_notify.extend(tasks.iter());
+            let cell = *updating_cell;
+            tasks_to_notify.extend(tasks.iter().map(|&task| (cell, task)));
         });
         if result.is_err() {
             self.backend
@@ -972,6 +1250,13 @@ impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
     fn program_duration_until(&self, instant: Instant) -> Duration {
         instant - self.program_start
     }
+
+    fn cancel_task_execution(&self, task: TaskId) -> bool {
+        match self.running_executions.remove(&task) {
+            Some((_, cancel_sender)) => cancel_sender.send(()).is_ok(),
+            None => false,
+        }
+    }
 }
 
 impl<B: Backend> TaskIdProvider for TurboTasks<B> {
@@ -1021,11 +1306,26 @@ impl Invalidator {
             turbo_tasks,
             handle,
         } = self;
-        let _ = handle.enter();
+        let _guard = handle.enter();
         if let Some(turbo_tasks) = turbo_tasks.upgrade() {
             turbo_tasks.invalidate(task);
         }
     }
+
+    /// Invalidates the task and records `reason` as the external cause, e. g.
+    /// the path of a changed file. The reason is shown when debugging why a
+    /// task has been re-executed.
+    pub fn invalidate_with_reason(self, reason: impl Into<Cow<'static, str>>) {
+        let Invalidator {
+            task,
+            turbo_tasks,
+            handle,
+        } = self;
+        let _guard = handle.enter();
+        if let Some(turbo_tasks) = turbo_tasks.upgrade() {
+            turbo_tasks.invalidate_with_reason(task, InvalidationReason::external(reason));
+        }
+    }
 }
 
 impl TraceRawVcs for Invalidator {
@@ -1136,8 +1436,16 @@ pub fn current_task_for_testing() -> TaskId {
 
 /// Get an [Invalidator] that can be used to invalidate the current [Task]
 /// based on external events.
+///
+/// This also marks the current task as volatile. Invalidators don't survive a
+/// restart, so backends that restore tasks from a persisted graph need to
+/// re-execute volatile tasks to validate them against the external state.
 pub fn get_invalidator() -> Invalidator {
     let handle = Handle::current();
+    let _ = CURRENT_TASK_STATE.try_with(|cell| {
+        let CurrentTaskState { volatile, .. } = &mut *cell.borrow_mut();
+        *volatile = true;
+    });
     Invalidator {
         task: current_task("turbo_tasks::get_invalidator()"),
         turbo_tasks: weak_turbo_tasks(),
@@ -1145,6 +1453,20 @@ pub fn get_invalidator() -> Invalidator {
     }
 }
 
+/// Get an [Invalidator] that can be used to invalidate the current [Task]
+/// based on external events, without marking the current task as volatile.
+///
+/// The caller is responsible for persisting the [Invalidator] (e. g. in the
+/// cell of a stateful task) and for invalidating it after a restart when the
+/// external state has changed while the process wasn't running.
+pub fn get_persisted_invalidator() -> Invalidator {
+    Invalidator {
+        task: current_task("turbo_tasks::get_persisted_invalidator()"),
+        turbo_tasks: weak_turbo_tasks(),
+        handle: Handle::current(),
+    }
+}
+
 /// Marks the current task as stateful. This prevents the tasks from being
 /// dropped without persisting the state.
 pub fn mark_stateful() {
@@ -1179,6 +1501,12 @@ pub fn spawn_thread(func: impl FnOnce() + Send + 'static) {
     });
 }
 
+/// Waits for another task. A limited execution releases its slot meanwhile.
+async fn wait_for_task(listener: EventListener) {
+    let _blocked = blocked_read();
+    listener.await
+}
+
 pub(crate) async fn read_task_output(
     this: &dyn TurboTasksApi,
     id: TaskId,
@@ -1187,7 +1515,7 @@ pub(crate) async fn read_task_output(
     loop {
         match this.try_read_task_output(id, strongly_consistent)? {
             Ok(result) => return Ok(result),
-            Err(listener) => listener.await,
+            Err(listener) => wait_for_task(listener).await,
         }
     }
 }
@@ -1202,7 +1530,7 @@ pub(crate) async fn read_task_output_untracked(
     loop {
         match this.try_read_task_output_untracked(id, strongly_consistent)? {
             Ok(result) => return Ok(result),
-            Err(listener) => listener.await,
+            Err(listener) => wait_for_task(listener).await,
         }
     }
 }
@@ -1215,7 +1543,7 @@ pub(crate) async fn read_task_cell(
     loop {
         match this.try_read_task_cell(id, index)? {
             Ok(result) => return Ok(result),
-            Err(listener) => listener.await,
+            Err(listener) => wait_for_task(listener).await,
         }
     }
 }
@@ -1230,7 +1558,7 @@ pub(crate) async fn read_task_cell_untracked(
     loop {
         match this.try_read_task_cell_untracked(id, index)? {
             Ok(result) => return Ok(result),
-            Err(listener) => listener.await,
+            Err(listener) => wait_for_task(listener).await,
         }
     }
 }
@@ -1243,7 +1571,7 @@ pub(crate) async fn read_task_collectibles(
     loop {
         match this.try_read_task_collectibles(id, trait_id)? {
             Ok(result) => return Ok(result),
-            Err(listener) => listener.await,
+            Err(listener) => wait_for_task(listener).await,
         }
     }
 }
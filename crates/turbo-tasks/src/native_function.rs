@@ -7,10 +7,11 @@ use std::{
 };
 
 use anyhow::Result;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
 use crate::{
-    self as turbo_tasks, registry::register_function, task_input::TaskInput, util::SharedError,
-    RawVc,
+    self as turbo_tasks, disk_cache::with_disk_cache, registry::register_function,
+    task_input::TaskInput, util::SharedError, RawVc,
 };
 
 type NativeTaskFuture = Pin<Box<dyn Future<Output = Result<RawVc>> + Send>>;
@@ -32,6 +33,58 @@ pub struct NativeFunction {
     /// A counter that tracks total executions of that function
     #[turbo_tasks(debug_ignore, trace_ignore)]
     pub executed_count: AtomicUsize,
+    /// Results are cached on disk, keyed by the values of the inputs and
+    /// this salt. See [crate::enable_disk_cache].
+    pub disk_cache: Option<&'static str>,
+    /// Limits the number of concurrent executions of the function. See
+    /// [ConcurrencyLimit].
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub concurrency_limit: Option<ConcurrencyLimit>,
+}
+
+/// Limits the number of concurrent executions of a function declared with
+/// `#[turbo_tasks::function(concurrency = N)]`. The scheduler waits for a free
+/// slot before starting an execution, so waiting tasks stay scheduled and
+/// don't occupy a tokio worker.
+///
+/// An execution releases its slot while it waits for other tasks and waits
+/// for a free slot again before it continues. So a limited function can
+/// (indirectly) await calls to itself.
+pub struct ConcurrencyLimit {
+    semaphore: Semaphore,
+    waiting: AtomicUsize,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            semaphore: Semaphore::new(limit),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits for a free slot. The slot is released when the permit is
+    /// dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+        // Also counts down when the future is dropped while waiting
+        let _waiting = WaitingGuard(&self.waiting);
+        // The semaphore is never closed
+        self.semaphore.acquire().await.unwrap()
+    }
+
+    /// The number of executions that are waiting for a free slot.
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+}
+
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl Debug for NativeFunction {
@@ -51,22 +104,45 @@ impl NativeFunction {
             name,
             bind_fn: Box::new(bind_fn),
             executed_count: AtomicUsize::new(0),
+            disk_cache: None,
+            concurrency_limit: None,
         }
     }
 
+    /// This is internally used by `#[turbo_tasks::function(cache = "disk")]`
+    pub fn with_disk_cache(mut self, salt: &'static str) -> Self {
+        self.disk_cache = Some(salt);
+        self
+    }
+
+    /// This is internally used by `#[turbo_tasks::function(concurrency = N)]`
+    pub fn with_concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency_limit = Some(ConcurrencyLimit::new(limit));
+        self
+    }
+
     /// Creates a functor for execution from a fixed set of inputs.
     pub fn bind(&'static self, inputs: &Vec<TaskInput>) -> NativeTaskFn {
         match (self.bind_fn)(inputs) {
-            Ok(native_fn) => Box::new(move || {
-                let r = native_fn();
-                if cfg!(feature = "log_function_stats") {
-                    let count = self.executed_count.fetch_add(1, Ordering::Relaxed);
-                    if count > 0 && count % 100000 == 0 {
-                        println!("{} was executed {}k times", self.name, count / 1000);
+            Ok(native_fn) => {
+                let disk_cache = self.disk_cache.map(|salt| (salt, inputs.clone()));
+                Box::new(move || {
+                    let r = native_fn();
+                    if cfg!(feature = "log_function_stats") {
+                        let count = self.executed_count.fetch_add(1, Ordering::Relaxed);
+                        if count > 0 && count % 100000 == 0 {
+                            println!("{} was executed {}k times", self.name, count / 1000);
+                        }
                     }
-                }
-                r
-            }),
+                    match &disk_cache {
+                        Some((salt, inputs)) => {
+                            Box::pin(with_disk_cache(&self.name, salt, inputs.clone(), r))
+                                as NativeTaskFuture
+                        }
+                        None => r,
+                    }
+                })
+            }
             Err(err) => {
                 let err = SharedError::new(err);
                 Box::new(move || {
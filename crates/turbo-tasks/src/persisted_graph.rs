@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     backend::{CellContent, PersistentTaskType},
-    CellId, RawVc, TaskId,
+    CellId, RawVc, TaskId, TraitTypeId,
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -24,6 +24,11 @@ pub struct TaskData {
     pub dependencies: Vec<RawVc>,
     pub cells: Vec<(CellId, TaskCell)>,
     pub output: RawVc,
+    pub emitted_collectibles: Vec<(TraitTypeId, RawVc)>,
+    pub unemitted_collectibles: Vec<(TraitTypeId, RawVc)>,
+    /// The task holds state that can change without re-executing the task
+    /// (see [crate::mark_stateful]), so it need to be persisted again.
+    pub stateful: bool,
 }
 pub struct ReadTaskState {
     pub clean: bool,
@@ -32,6 +37,9 @@ pub struct ReadTaskState {
 
 pub struct PersistTaskState {
     pub externally_active: bool,
+    /// The task depends on external state (it used an Invalidator)
+    /// and need to be re-executed after a restart.
+    pub volatile: bool,
 }
 
 /*
@@ -182,6 +190,11 @@ pub trait PersistedGraph: Sync + Send {
     /// This is usually called at the beginning to schedule these tasks.
     fn get_dirty_active_tasks(&self, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>>;
 
+    /// Get all tasks that were persisted as volatile.
+    /// This is usually called at the beginning to invalidate these tasks, as
+    /// the external state might have changed while the process was offline.
+    fn get_volatile_tasks(&self, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>>;
+
     /// Get tasks that have active update pending that need to be continued
     /// returns (tasks_to_activate, tasks_to_deactivate)
     fn get_pending_active_update(
@@ -321,6 +334,10 @@ impl PersistedGraph for () {
         Ok(Vec::new())
     }
 
+    fn get_volatile_tasks(&self, _api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        Ok(Vec::new())
+    }
+
     fn get_pending_active_update(
         &self,
         _api: &dyn PersistedGraphApi,
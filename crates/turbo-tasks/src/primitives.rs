@@ -2,10 +2,12 @@ use std::ops::Deref;
 
 use anyhow::Result;
 use auto_hash_map::AutoSet;
+use turbo_tasks_hash::DeterministicHash;
 
 use crate::{self as turbo_tasks, RawVc, ValueToString, ValueToStringVc};
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct String(std::string::String);
 
 #[turbo_tasks::value_impl]
@@ -17,18 +19,23 @@ impl StringVc {
 }
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct OptionU16(Option<u16>);
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct U32(u32);
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct U64(u64);
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct OptionString(Option<std::string::String>);
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct Strings(Vec<std::string::String>);
 
 #[turbo_tasks::value_impl]
@@ -40,12 +47,15 @@ impl StringsVc {
 }
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct Bytes(Vec<u8>);
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct Bool(bool);
 
 #[turbo_tasks::value(transparent)]
+#[derive(DeterministicHash)]
 pub struct Usize(usize);
 
 #[turbo_tasks::value(transparent)]
@@ -17,6 +17,7 @@ use thiserror::Error;
 use crate::{
     backend::CellContent,
     event::EventListener,
+    limited_future::{blocked_read, BlockedRead},
     manager::{
         find_cell_by_type, read_task_cell, read_task_cell_untracked, read_task_output,
         read_task_output_untracked, CurrentCellRef, TurboTasksApi,
@@ -151,6 +152,11 @@ impl RawVc {
                         read_task_output_untracked(turbo_tasks, task, strongly_consistent).await?
                 }
                 RawVc::TaskCell(task, index) => {
+                    if strongly_consistent {
+                        // The cell might belong to a task that is not part of the graph that has
+                        // been settled so far
+                        read_task_output_untracked(turbo_tasks, task, true).await?;
+                    }
                     return read_task_cell_untracked(turbo_tasks, task, index).await;
                 }
             }
@@ -383,8 +389,14 @@ impl Display for RawVc {
 pub struct ReadRawVcFuture<T: Any + Send + Sync, U: Any + Send + Sync = T> {
     turbo_tasks: Arc<dyn TurboTasksApi>,
     strongly_consistent: bool,
+    /// The last task that has been read strongly consistent. All its
+    /// dependencies have settled.
+    settled_task: Option<TaskId>,
     current: RawVc,
     listener: Option<EventListener>,
+    /// Marks the execution as waiting for another task while the listener is
+    /// pending
+    blocked: Option<BlockedRead>,
     phantom_data: PhantomData<Pin<Box<(T, U)>>>,
 }
 
@@ -395,8 +407,10 @@ impl<T: Any + Send + Sync> ReadRawVcFuture<T, T> {
         ReadRawVcFuture {
             turbo_tasks: tt,
             strongly_consistent: false,
+            settled_task: None,
             current: vc,
             listener: None,
+            blocked: None,
             phantom_data: PhantomData,
         }
     }
@@ -407,8 +421,10 @@ impl<T: Any + Send + Sync> ReadRawVcFuture<T, T> {
         ReadRawVcFuture {
             turbo_tasks: tt,
             strongly_consistent: true,
+            settled_task: None,
             current: vc,
             listener: None,
+            blocked: None,
             phantom_data: PhantomData,
         }
     }
@@ -424,8 +440,10 @@ impl<T: Any + Send + Sync, U: Any + Send + Sync> ReadRawVcFuture<T, U> {
         ReadRawVcFuture {
             turbo_tasks: tt,
             strongly_consistent: false,
+            settled_task: None,
             current: vc,
             listener: None,
+            blocked: None,
             phantom_data: PhantomData,
         }
     }
@@ -439,8 +457,10 @@ impl<T: Any + Send + Sync, U: Any + Send + Sync> ReadRawVcFuture<T, U> {
         ReadRawVcFuture {
             turbo_tasks: tt,
             strongly_consistent: true,
+            settled_task: None,
             current: vc,
             listener: None,
+            blocked: None,
             phantom_data: PhantomData,
         }
     }
@@ -458,9 +478,13 @@ impl<T: Any + Send + Sync, U: Any + Send + Sync> Future for ReadRawVcFuture<T, U
                 // SAFETY: listener is from previous pinned this
                 let listener = unsafe { Pin::new_unchecked(listener) };
                 if listener.poll(cx).is_pending() {
+                    if this.blocked.is_none() {
+                        this.blocked = blocked_read();
+                    }
                     return Poll::Pending;
                 }
                 this.listener = None;
+                this.blocked = None;
             }
             let mut listener = match this.current {
                 RawVc::TaskOutput(task) => match this
@@ -468,7 +492,9 @@ impl<T: Any + Send + Sync, U: Any + Send + Sync> Future for ReadRawVcFuture<T, U
                     .try_read_task_output(task, this.strongly_consistent)
                 {
                     Ok(Ok(vc)) => {
-                        this.strongly_consistent = false;
+                        if this.strongly_consistent {
+                            this.settled_task = Some(task);
+                        }
                         this.current = vc;
                         continue 'outer;
                     }
@@ -476,6 +502,22 @@ impl<T: Any + Send + Sync, U: Any + Send + Sync> Future for ReadRawVcFuture<T, U
                     Err(err) => return Poll::Ready(Err(err)),
                 },
                 RawVc::TaskCell(task, index) => {
+                    if this.strongly_consistent && this.settled_task != Some(task) {
+                        // The cell might belong to a task that is not part of the graph that
+                        // has been settled so far. The output is only read to wait for
+                        // the task, so it's not tracked as dependency.
+                        match this.turbo_tasks.try_read_task_output_untracked(task, true) {
+                            Ok(Ok(_)) => {
+                                this.settled_task = Some(task);
+                                continue 'outer;
+                            }
+                            Ok(Err(listener)) => {
+                                this.listener = Some(listener);
+                                continue 'outer;
+                            }
+                            Err(err) => return Poll::Ready(Err(err)),
+                        }
+                    }
                     match this.turbo_tasks.try_read_task_cell(task, index) {
                         Ok(Ok(content)) => {
                             // SAFETY: Constructor ensures that T and U are binary identical
@@ -491,6 +533,7 @@ impl<T: Any + Send + Sync, U: Any + Send + Sync> Future for ReadRawVcFuture<T, U
                 Poll::Ready(_) => continue,
                 Poll::Pending => {
                     this.listener = Some(listener);
+                    this.blocked = blocked_read();
                     return Poll::Pending;
                 }
             };
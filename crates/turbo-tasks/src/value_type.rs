@@ -8,6 +8,7 @@ use std::{
 
 use auto_hash_map::{AutoMap, AutoSet};
 use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::{DeterministicHash, Xxh3Hash64Hasher};
 
 use crate::{
     id::{FunctionId, TraitTypeId},
@@ -59,6 +60,7 @@ pub trait TypedForInput: Typed {}
 
 type MagicSerializationFn = fn(&dyn MagicAny) -> &dyn erased_serde::Serialize;
 type AnySerializationFn = fn(&(dyn Any + Sync + Send)) -> &dyn erased_serde::Serialize;
+type DeterministicHashFn = fn(&(dyn Any + Sync + Send), &mut Xxh3Hash64Hasher);
 
 // TODO this type need some refactoring when multiple languages are added to
 // turbo-task In this case a trait_method might be of a different function type.
@@ -80,6 +82,9 @@ pub struct ValueType {
     /// Functors for serialization
     magic_serialization: Option<(MagicSerializationFn, MagicAnyDeserializeSeed)>,
     any_serialization: Option<(AnySerializationFn, AnyDeserializeSeed)>,
+
+    /// Functor for hashing values in a way that is stable across process runs
+    deterministic_hash: Option<DeterministicHashFn>,
 }
 
 impl Hash for ValueType {
@@ -130,6 +135,20 @@ pub fn any_as_serialize<T: Any + Serialize + Send + Sync + 'static>(
     );
 }
 
+pub fn any_deterministic_hash<T: Any + DeterministicHash + Send + Sync + 'static>(
+    this: &(dyn Any + Send + Sync),
+    hasher: &mut Xxh3Hash64Hasher,
+) {
+    if let Some(r) = this.downcast_ref::<T>() {
+        hasher.write_ref(r);
+        return;
+    }
+    panic!(
+        "any_deterministic_hash::<{}> called with invalid type",
+        type_name::<T>()
+    );
+}
+
 impl ValueType {
     /// This is internally used by `#[turbo_tasks::value]`
     pub fn new<T>() -> Self {
@@ -139,6 +158,7 @@ impl ValueType {
             trait_methods: AutoMap::new(),
             magic_serialization: None,
             any_serialization: None,
+            deterministic_hash: None,
         }
     }
 
@@ -155,6 +175,7 @@ impl ValueType {
                 MagicAnyDeserializeSeed::new::<T>(),
             )),
             any_serialization: Some((any_as_serialize::<T>, AnyDeserializeSeed::new::<T>())),
+            deterministic_hash: None,
         }
     }
 
@@ -168,6 +189,7 @@ impl ValueType {
             trait_methods: AutoMap::new(),
             magic_serialization: None,
             any_serialization: Some((any_as_serialize::<T>, AnyDeserializeSeed::new::<T>())),
+            deterministic_hash: None,
         }
     }
 
@@ -202,6 +224,30 @@ impl ValueType {
         self.any_serialization.map(|s| s.1)
     }
 
+    /// This is internally used by `#[turbo_tasks::value]` for types deriving
+    /// [DeterministicHash]
+    pub fn register_deterministic_hash<T: Any + DeterministicHash + Send + Sync + 'static>(
+        &mut self,
+    ) {
+        self.deterministic_hash = Some(any_deterministic_hash::<T>);
+    }
+
+    /// Hashes a value of this type in a way that is stable across process
+    /// runs. Returns false when the type doesn't implement
+    /// [DeterministicHash].
+    pub fn any_deterministic_hash(
+        &self,
+        value: &(dyn Any + Sync + Send),
+        hasher: &mut Xxh3Hash64Hasher,
+    ) -> bool {
+        if let Some(hash) = self.deterministic_hash {
+            hash(value, hasher);
+            true
+        } else {
+            false
+        }
+    }
+
     /// This is internally used by `#[turbo_tasks::value_impl]`
     pub fn register_trait_method(
         &mut self,
@@ -2,7 +2,7 @@ use std::{
     borrow::Cow,
     cmp::{min, Ordering},
     collections::{hash_map::Entry, HashMap, HashSet},
-    fmt::Write as _,
+    fmt::{Display, Write as _},
     path::PathBuf,
     str::FromStr,
     sync::{Arc, Mutex},
@@ -412,6 +412,19 @@ impl ConsoleUi {
             seen: Arc::new(Mutex::new(SeenIssues::new())),
         }
     }
+
+    /// Logs a message that doesn't come from a task, e. g. a failed
+    /// connection of the dev server, in the style of issues. Messages above
+    /// the log level are skipped.
+    pub fn log(&self, severity: IssueSeverity, category: &str, message: impl Display) {
+        if severity > self.options.log_level {
+            return;
+        }
+        println!(
+            "{} - [{category}] {message}",
+            severity.style(severity_to_style(severity))
+        );
+    }
 }
 
 #[turbo_tasks::value(transparent)]
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use indexmap::IndexSet;
-use turbo_tasks::CompletionVc;
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString};
 use turbo_tasks_fs::{
     File, FileContent, FileContentVc, FileJsonContent, FileJsonContentVc, FileLinesContent,
     FileLinesContentVc, FileSystemPathVc, LinkContent, LinkType,
@@ -38,6 +38,13 @@ pub trait Asset {
     /// a name/identifier of the [Asset].
     fn path(&self) -> FileSystemPathVc;
 
+    /// A unique identifier of the [Asset]. It's the path by default, but it
+    /// can also include e. g. the query of the request the [Asset] was
+    /// resolved from, so it can differ between [Asset]s with the same path.
+    fn ident(&self) -> StringVc {
+        self.path().to_string()
+    }
+
     /// The content of the [Asset].
     fn content(&self) -> AssetContentVc;
 
@@ -1,5 +1,6 @@
 pub mod dev;
 pub mod optimize;
+pub mod production;
 
 use std::{collections::VecDeque, fmt::Debug};
 
@@ -45,6 +46,22 @@ impl ModuleId {
 #[turbo_tasks::value(transparent, shared)]
 pub struct ModuleIds(Vec<ModuleIdVc>);
 
+/// The ids of the modules in chunks, keyed by the path of the chunk relative
+/// to the output root.
+#[turbo_tasks::value(transparent)]
+pub struct ChunkModules(Vec<(String, ModuleIdsVc)>);
+
+/// The runtime code that is included in chunks which are evaluated.
+#[derive(Default)]
+#[turbo_tasks::value(shared)]
+pub enum RuntimeType {
+    /// The runtime supports hot module replacement.
+    #[default]
+    Development,
+    /// A smaller runtime without support for hot module replacement.
+    Production,
+}
+
 /// A context for the chunking that influences the way chunks are created
 #[turbo_tasks::value_trait]
 pub trait ChunkingContext {
@@ -67,6 +84,34 @@ pub trait ChunkingContext {
     }
 
     fn with_layer(&self, layer: &str) -> ChunkingContextVc;
+
+    fn runtime_type(&self) -> RuntimeTypeVc {
+        RuntimeType::Development.cell()
+    }
+
+    /// Whether the path of a chunk should contain a hash of its content, so
+    /// that it changes whenever the content changes.
+    fn is_content_hashing_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    fn is_minification_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// The entries of the module graph that all chunks created with this
+    /// context are part of. Chunk items of that module graph get short
+    /// numeric ids. This is empty when the module graph isn't known upfront,
+    /// e.g. in development.
+    fn module_graph_entries(&self) -> AssetsVc {
+        AssetsVc::empty()
+    }
+
+    /// The entries of the module graph that is used to determine which exports
+    /// of modules are used. Tree shaking is disabled when this is empty.
+    fn tree_shaking_entries(&self) -> AssetsVc {
+        AssetsVc::empty()
+    }
 }
 
 /// An [Asset] that can be converted into a [Chunk].
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    Value, ValueToString,
+};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+use super::{ChunkingContext, ChunkingContextVc, RuntimeType, RuntimeTypeVc};
+use crate::{
+    asset::{AssetVc, AssetsVc},
+    environment::EnvironmentVc,
+};
+
+pub struct ProductionChunkingContextBuilder {
+    context: ProductionChunkingContext,
+}
+
+impl ProductionChunkingContextBuilder {
+    pub fn layer(mut self, layer: &str) -> Self {
+        self.context.layer = (!layer.is_empty()).then(|| layer.to_string());
+        self
+    }
+
+    pub fn css_chunk_root_path(mut self, path: FileSystemPathVc) -> Self {
+        self.context.css_chunk_root_path = Some(path);
+        self
+    }
+
+    /// Sets the entries of the module graph. All chunks created with this
+    /// context need to be part of that module graph. Chunk items of the module
+    /// graph get short numeric ids.
+    pub fn entries(mut self, entries: AssetsVc) -> Self {
+        self.context.entries = Some(entries);
+        self
+    }
+
+    /// Enables tree shaking of unused exports for the module graph of the
+    /// [entries](Self::entries).
+    pub fn tree_shaking(mut self) -> Self {
+        self.context.enable_tree_shaking = true;
+        self
+    }
+
+    /// Disables minification of the generated chunks. Useful to inspect the
+    /// output of a production build.
+    pub fn no_minify(mut self) -> Self {
+        self.context.enable_minification = false;
+        self
+    }
+
+    pub fn build(self) -> ChunkingContextVc {
+        ProductionChunkingContextVc::new(Value::new(self.context)).into()
+    }
+}
+
+/// A chunking context for production builds.
+/// Chunk file names are short hashes which also encode the content of the
+/// chunk, so they can be cached forever. Module ids are short numbers when the
+/// entries of the module graph are known. Chunks are minified and use a
+/// runtime without hot module replacement.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub struct ProductionChunkingContext {
+    /// This path is used to compute the url to request chunks or assets from
+    output_root_path: FileSystemPathVc,
+    /// Chunks are placed at this path
+    chunk_root_path: FileSystemPathVc,
+    /// Css Chunks are placed at this path
+    css_chunk_root_path: Option<FileSystemPathVc>,
+    /// Static assets are placed at this path
+    asset_root_path: FileSystemPathVc,
+    /// Layer name within this context
+    layer: Option<String>,
+    /// Minify the generated chunks
+    enable_minification: bool,
+    /// Entries of the module graph of all chunks
+    entries: Option<AssetsVc>,
+    /// Remove unused exports and unused side effect free modules
+    enable_tree_shaking: bool,
+    /// The environment chunks will be evaluated in.
+    environment: EnvironmentVc,
+}
+
+impl ProductionChunkingContextVc {
+    pub fn builder(
+        output_root_path: FileSystemPathVc,
+        chunk_root_path: FileSystemPathVc,
+        asset_root_path: FileSystemPathVc,
+        environment: EnvironmentVc,
+    ) -> ProductionChunkingContextBuilder {
+        ProductionChunkingContextBuilder {
+            context: ProductionChunkingContext {
+                output_root_path,
+                chunk_root_path,
+                css_chunk_root_path: None,
+                asset_root_path,
+                layer: None,
+                enable_minification: true,
+                entries: None,
+                enable_tree_shaking: false,
+                environment,
+            },
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ProductionChunkingContextVc {
+    #[turbo_tasks::function]
+    fn new(this: Value<ProductionChunkingContext>) -> Self {
+        this.into_value().cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkingContext for ProductionChunkingContext {
+    #[turbo_tasks::function]
+    fn output_root(&self) -> FileSystemPathVc {
+        self.output_root_path
+    }
+
+    #[turbo_tasks::function]
+    fn environment(&self) -> EnvironmentVc {
+        self.environment
+    }
+
+    #[turbo_tasks::function]
+    async fn chunk_path(
+        &self,
+        path_vc: FileSystemPathVc,
+        extension: &str,
+    ) -> Result<FileSystemPathVc> {
+        // The chunk path already contains all information that makes the chunk
+        // unique (including the content hash), so we only need to shorten it.
+        let path = path_vc.to_string().await?;
+        let hash = encode_hex(hash_xxh3_hash64(path.as_bytes()));
+        let name = format!("{}{extension}", &hash[..12]);
+
+        let mut root_path = self.chunk_root_path;
+        #[allow(clippy::single_match, reason = "future extensions")]
+        match extension {
+            ".css" => {
+                if let Some(path) = self.css_chunk_root_path {
+                    root_path = path;
+                }
+            }
+            _ => {}
+        }
+        let root_path = if let Some(layer) = self.layer.as_deref() {
+            root_path.join(layer)
+        } else {
+            root_path
+        };
+        Ok(root_path.join(&name))
+    }
+
+    #[turbo_tasks::function]
+    async fn can_be_in_same_chunk(&self, asset_a: AssetVc, asset_b: AssetVc) -> Result<BoolVc> {
+        let parent_dir = asset_a.path().parent().await?;
+
+        let path = asset_b.path().await?;
+        if let Some(rel_path) = parent_dir.get_path_to(&path) {
+            if !rel_path.starts_with("node_modules/") && !rel_path.contains("/node_modules/") {
+                return Ok(BoolVc::cell(true));
+            }
+        }
+
+        Ok(BoolVc::cell(false))
+    }
+
+    #[turbo_tasks::function]
+    fn asset_path(&self, content_hash: &str, extension: &str) -> FileSystemPathVc {
+        self.asset_root_path
+            .join(&format!("{content_hash}.{extension}"))
+    }
+
+    #[turbo_tasks::function]
+    fn layer(&self) -> StringVc {
+        StringVc::cell(self.layer.clone().unwrap_or_default())
+    }
+
+    #[turbo_tasks::function]
+    async fn with_layer(
+        self_vc: ProductionChunkingContextVc,
+        layer: &str,
+    ) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.layer = (!layer.is_empty()).then(|| layer.to_string());
+        Ok(ProductionChunkingContextVc::new(Value::new(context)).into())
+    }
+
+    #[turbo_tasks::function]
+    fn runtime_type(&self) -> RuntimeTypeVc {
+        RuntimeType::Production.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn is_content_hashing_enabled(&self) -> BoolVc {
+        BoolVc::cell(true)
+    }
+
+    #[turbo_tasks::function]
+    fn is_minification_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_minification)
+    }
+
+    #[turbo_tasks::function]
+    fn module_graph_entries(&self) -> AssetsVc {
+        self.entries.unwrap_or_else(AssetsVc::empty)
+    }
+
+    #[turbo_tasks::function]
+    fn tree_shaking_entries(&self) -> AssetsVc {
+        match self.entries {
+            Some(entries) if self.enable_tree_shaking => entries,
+            _ => AssetsVc::empty(),
+        }
+    }
+}
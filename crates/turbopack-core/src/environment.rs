@@ -18,23 +18,39 @@ static DEFAULT_NODEJS_VERSION: &str = "16.0.0";
 
 #[derive(Default)]
 #[turbo_tasks::value(shared)]
-pub struct ServerAddr(#[turbo_tasks(trace_ignore)] Option<SocketAddr>);
+pub struct ServerAddr {
+    #[turbo_tasks(trace_ignore)]
+    addr: Option<SocketAddr>,
+    https: bool,
+}
 
 impl ServerAddr {
     pub fn new(addr: SocketAddr) -> Self {
-        Self(Some(addr))
+        Self {
+            addr: Some(addr),
+            https: false,
+        }
+    }
+
+    /// The address of a server that is served over HTTPS.
+    pub fn new_https(addr: SocketAddr) -> Self {
+        Self {
+            addr: Some(addr),
+            https: true,
+        }
     }
 
     pub fn to_string(&self) -> Result<String> {
-        let addr = &self.0.context("expected some server address")?;
+        let addr = &self.addr.context("expected some server address")?;
+        let scheme = if self.https { "https" } else { "http" };
         let uri = if addr.ip().is_loopback() || addr.ip().is_unspecified() {
-            match addr.port() {
-                80 => "http://localhost".to_string(),
-                443 => "https://localhost".to_string(),
-                _ => format!("http://localhost:{}", addr.port()),
+            match (addr.port(), self.https) {
+                (80, false) => "http://localhost".to_string(),
+                (443, _) => "https://localhost".to_string(),
+                (port, _) => format!("{scheme}://localhost:{port}"),
             }
         } else {
-            format!("http://{}", addr)
+            format!("{scheme}://{addr}")
         };
         Ok(uri)
     }
@@ -44,7 +60,7 @@ impl ServerAddr {
 impl ServerAddrVc {
     #[turbo_tasks::function]
     pub fn empty() -> Self {
-        ServerAddr(None).cell()
+        ServerAddr::default().cell()
     }
 }
 
@@ -114,8 +130,21 @@ pub enum EnvironmentIntention {
     Client,
     /// Intent to evaluate build time javascript code like config, plugins, etc.
     Build,
-    // TODO allow custom trait here
-    Custom(u8),
+    /// An intent that is not known to turbopack
+    Custom(CustomEnvironmentIntentionVc),
+}
+
+/// An [EnvironmentIntention] that is provided by the embedder of turbopack.
+#[turbo_tasks::value_trait]
+pub trait CustomEnvironmentIntention {
+    /// A human readable name of the intention.
+    fn name(&self) -> StringVc;
+
+    /// Global variables which are available without an import for this
+    /// intention, in addition to the globals of the runtime.
+    fn globals(&self) -> StringsVc {
+        StringsVc::empty()
+    }
 }
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -125,8 +154,60 @@ pub enum ExecutionEnvironment {
     NodeJsLambda(NodeJsEnvironmentVc),
     EdgeFunction(NodeJsEnvironmentVc),
     Browser(BrowserEnvironmentVc),
-    // TODO allow custom trait here
-    Custom(u8),
+    /// A runtime that is not built into turbopack, e.g. a worker platform or
+    /// Deno
+    Custom(CustomExecutionEnvironmentVc),
+}
+
+/// A runtime provided by the embedder of turbopack. It answers the capability
+/// questions which are hardcoded for the builtin [ExecutionEnvironment]s.
+#[turbo_tasks::value_trait]
+pub trait CustomExecutionEnvironment {
+    /// The platform native code is compiled for. Runtimes that can't load
+    /// native code keep the default.
+    fn compile_target(&self) -> CompileTargetVc {
+        CompileTargetVc::unknown()
+    }
+
+    /// The versions of the runtime, used to pick the language features that
+    /// need to be transpiled.
+    fn runtime_versions(&self) -> RuntimeVersionsVc;
+
+    /// Whether Node.js builtin modules are available and can be left external.
+    fn node_externals(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Additional extensions which are tried when resolving requests.
+    fn resolve_extensions(&self) -> StringsVc {
+        StringsVc::empty()
+    }
+
+    /// Whether requests can be resolved from `node_modules` directories.
+    fn resolve_node_modules(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// The conditions used to resolve `exports` and `imports` fields, e.g.
+    /// `worker` or `deno`.
+    fn resolve_conditions(&self) -> StringsVc;
+
+    /// Runtime specific global variables which are available without an
+    /// import.
+    fn globals(&self) -> StringsVc;
+
+    /// The working directory of the runtime, if there is one.
+    fn cwd(&self) -> OptionStringVc {
+        OptionStringVc::cell(None)
+    }
+
+    /// Whether the runtime renders on a server or on a client.
+    fn rendering(&self) -> RenderingVc {
+        Rendering::None.cell()
+    }
+
+    /// How chunks are loaded at runtime.
+    fn chunk_loading(&self) -> ChunkLoadingVc;
 }
 
 #[turbo_tasks::value_impl]
@@ -137,9 +218,11 @@ impl EnvironmentVc {
         Ok(match this.execution {
             ExecutionEnvironment::NodeJsBuildTime(node_env, ..)
             | ExecutionEnvironment::NodeJsLambda(node_env) => node_env.await?.compile_target,
-            ExecutionEnvironment::Browser(_) => CompileTargetVc::unknown(),
-            ExecutionEnvironment::EdgeFunction(_) => todo!(),
-            ExecutionEnvironment::Custom(_) => todo!(),
+            // Browsers and edge functions can't load native code
+            ExecutionEnvironment::Browser(_) | ExecutionEnvironment::EdgeFunction(_) => {
+                CompileTargetVc::unknown()
+            }
+            ExecutionEnvironment::Custom(custom_env) => custom_env.compile_target(),
         })
     }
 
@@ -148,15 +231,15 @@ impl EnvironmentVc {
         let this = self.await?;
         Ok(match this.execution {
             ExecutionEnvironment::NodeJsBuildTime(node_env, ..)
-            | ExecutionEnvironment::NodeJsLambda(node_env) => node_env.runtime_versions(),
+            | ExecutionEnvironment::NodeJsLambda(node_env)
+            | ExecutionEnvironment::EdgeFunction(node_env) => node_env.runtime_versions(),
             ExecutionEnvironment::Browser(browser_env) => {
                 RuntimeVersionsVc::cell(Versions::parse_versions(browserslist::resolve(
                     browser_env.await?.browserslist_query.split(','),
                     &browserslist::Opts::new(),
                 )?)?)
             }
-            ExecutionEnvironment::EdgeFunction(_) => todo!(),
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.runtime_versions(),
         })
     }
 
@@ -169,7 +252,7 @@ impl EnvironmentVc {
             }
             ExecutionEnvironment::Browser(_) => BoolVc::cell(false),
             ExecutionEnvironment::EdgeFunction(_) => BoolVc::cell(false),
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.node_externals(),
         })
     }
 
@@ -187,7 +270,7 @@ impl EnvironmentVc {
             ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
                 StringsVc::empty()
             }
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.resolve_extensions(),
         })
     }
 
@@ -201,7 +284,7 @@ impl EnvironmentVc {
             ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
                 BoolVc::cell(false)
             }
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.resolve_node_modules(),
         })
     }
 
@@ -215,7 +298,7 @@ impl EnvironmentVc {
             ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
                 StringsVc::empty()
             }
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.resolve_conditions(),
         })
     }
 
@@ -225,6 +308,7 @@ impl EnvironmentVc {
         Ok(match env.execution {
             ExecutionEnvironment::NodeJsBuildTime(env)
             | ExecutionEnvironment::NodeJsLambda(env) => env.await?.cwd,
+            ExecutionEnvironment::Custom(custom_env) => custom_env.cwd(),
             _ => OptionStringVc::cell(None),
         })
     }
@@ -239,7 +323,7 @@ impl EnvironmentVc {
                 Rendering::Server(env.await?.server_addr).cell()
             }
             ExecutionEnvironment::Browser(_) => Rendering::Client.cell(),
-            _ => Rendering::None.cell(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.rendering(),
         })
     }
 
@@ -251,9 +335,51 @@ impl EnvironmentVc {
             | ExecutionEnvironment::NodeJsLambda(_)
             | ExecutionEnvironment::EdgeFunction(_) => ChunkLoading::NodeJs.cell(),
             ExecutionEnvironment::Browser(_) => ChunkLoading::Dom.cell(),
-            _ => ChunkLoading::None.cell(),
+            ExecutionEnvironment::Custom(custom_env) => custom_env.chunk_loading(),
         })
     }
+
+    /// Global variables which are available without an import, provided by
+    /// the runtime and a custom intention.
+    #[turbo_tasks::function]
+    pub async fn globals(self) -> Result<StringsVc> {
+        let env = self.await?;
+        let mut globals = match env.execution {
+            ExecutionEnvironment::NodeJsBuildTime(_) | ExecutionEnvironment::NodeJsLambda(_) => [
+                "global",
+                "process",
+                "Buffer",
+                "require",
+                "module",
+                "exports",
+                "__dirname",
+                "__filename",
+            ]
+            .map(ToString::to_string)
+            .to_vec(),
+            ExecutionEnvironment::EdgeFunction(_) => {
+                vec!["self".to_string(), "EdgeRuntime".to_string()]
+            }
+            ExecutionEnvironment::Browser(browser_env) => {
+                let browser_env = browser_env.await?;
+                let mut globals = vec!["self".to_string()];
+                if browser_env.dom {
+                    globals.push("window".to_string());
+                    globals.push("document".to_string());
+                }
+                globals
+            }
+            ExecutionEnvironment::Custom(custom_env) => custom_env.globals().await?.clone_value(),
+        };
+        if let EnvironmentIntention::Custom(custom_intention) = env.intention {
+            for global in custom_intention.globals().await?.iter() {
+                if !globals.contains(global) {
+                    globals.push(global.clone());
+                }
+            }
+        }
+        Ok(StringsVc::cell(globals))
+    }
 }
 
 pub enum NodeEnvironmentType {
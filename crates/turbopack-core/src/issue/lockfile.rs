@@ -0,0 +1,33 @@
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{Issue, IssueVc};
+
+#[turbo_tasks::value(shared)]
+pub struct LockfileIssue {
+    pub path: FileSystemPathVc,
+    pub error_message: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for LockfileIssue {
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Error reading lockfile".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("parse".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.error_message.clone())
+    }
+}
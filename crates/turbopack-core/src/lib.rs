@@ -12,6 +12,7 @@ pub mod context;
 pub mod environment;
 pub mod introspect;
 pub mod issue;
+pub mod query_asset;
 pub mod reference;
 pub mod reference_type;
 pub mod resolve;
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use crate::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+    version::VersionedContentVc,
+};
+
+/// An [Asset] that was resolved from a request with a `?query`. It has the
+/// path and content of the original [Asset], but the query is part of its
+/// [Asset::ident], so modules created from it get their own ids.
+#[turbo_tasks::value]
+pub struct QueryAsset {
+    pub source: AssetVc,
+    /// The query without the leading `?`
+    pub query: String,
+}
+
+#[turbo_tasks::value_impl]
+impl QueryAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc, query: String) -> Self {
+        Self::cell(QueryAsset { source, query })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for QueryAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    async fn ident(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{}?{}",
+            self.source.ident().await?,
+            self.query
+        )))
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.source.references()
+    }
+
+    #[turbo_tasks::function]
+    fn versioned_content(&self) -> VersionedContentVc {
+        self.source.versioned_content()
+    }
+}
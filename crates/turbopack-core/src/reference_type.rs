@@ -1,10 +1,12 @@
 use std::fmt::Display;
 
+use turbo_tasks::primitives::StringVc;
+
 // These enums list well known types, which we use internally. Plugins might add
 // custom types too.
 
-// TODO when plugins are supported, replace u8 with a trait that defines the
-// behavior.
+// TODO replace u8 of the sub types with a trait that defines the behavior, like
+// [CustomReferenceType].
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
 #[derive(Debug, Clone, PartialOrd, Ord, Hash)]
@@ -63,10 +65,18 @@ pub enum ReferenceType {
     Url(UrlReferenceSubType),
     TypeScript(TypeScriptReferenceSubType),
     Entry(EntryReferenceSubType),
-    Custom(u8),
+    Custom(CustomReferenceTypeVc),
     Undefined,
 }
 
+/// A reference type added by a plugin, e.g. an import from a GraphQL
+/// document. Custom reference types only include themselves.
+#[turbo_tasks::value_trait]
+pub trait CustomReferenceType {
+    /// A human readable name of the reference type.
+    fn name(&self) -> StringVc;
+}
+
 impl Display for ReferenceType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // TODO print sub types
@@ -77,7 +87,7 @@ impl Display for ReferenceType {
             ReferenceType::Url(_) => "url",
             ReferenceType::TypeScript(_) => "typescript",
             ReferenceType::Entry(_) => "entry",
-            ReferenceType::Custom(_) => todo!(),
+            ReferenceType::Custom(_) => "custom",
             ReferenceType::Undefined => "undefined",
         };
         f.write_str(str)
@@ -114,9 +124,7 @@ impl ReferenceType {
                 matches!(other, ReferenceType::Entry(_))
                     && matches!(sub_type, EntryReferenceSubType::Undefined)
             }
-            ReferenceType::Custom(_) => {
-                todo!()
-            }
+            ReferenceType::Custom(_) => false,
             ReferenceType::Undefined => true,
         }
     }
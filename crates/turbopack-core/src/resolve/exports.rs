@@ -8,6 +8,7 @@ use serde_json::Value;
 use super::{
     alias_map::{AliasMap, AliasMapIter, AliasMapLookupIterator, AliasPattern, AliasTemplate},
     options::ConditionValue,
+    pattern::Pattern,
 };
 
 /// The result an "exports" field describes. Can represent multiple
@@ -318,6 +319,92 @@ impl ExportsField {
     pub fn lookup<'a>(&'a self, request: &'a str) -> AliasMapLookupIterator<'a, ExportsValue> {
         self.0.lookup(request)
     }
+
+    /// Looks up a request pattern in the "exports" field. Returns every entry
+    /// the pattern could match, together with the part of the pattern that
+    /// is captured by the wildcard of the entry (`None` for exact entries).
+    pub fn lookup_pattern(&self, request: &Pattern) -> Vec<(&ExportsValue, Option<Pattern>)> {
+        lookup_pattern(&self.0, request)
+    }
+}
+
+/// Content of an "imports" field in a package.json
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportsField(AliasMap<ExportsValue>);
+
+impl TryFrom<&Value> for ImportsField {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Value) -> Result<Self> {
+        // The "imports" field must be an object whose keys start with "#".
+        // https://nodejs.org/api/packages.html#imports
+        let Value::Object(object) = value else {
+            bail!("\"imports\" field must be an object");
+        };
+        let mut map = AliasMap::new();
+        for (key, value) in object.iter() {
+            if !key.starts_with('#') || key == "#" || key.starts_with("#/") {
+                bail!(
+                    "invalid key \"{}\" in the imports field. Keys must start with \"#\" followed \
+                     by a name",
+                    key
+                );
+            }
+
+            let mut value: ExportsValue = value.try_into()?;
+
+            let pattern = if is_folder_shorthand(key) {
+                expand_folder_shorthand(key, &mut value)?
+            } else {
+                AliasPattern::parse(key)
+            };
+
+            map.insert(pattern, value);
+        }
+        Ok(Self(map))
+    }
+}
+
+impl ImportsField {
+    /// Looks up a request string in the "imports" field. Returns an iterator of
+    /// matching requests. Usually only the first one is relevant, except
+    /// when conditions don't match or only partially match.
+    pub fn lookup<'a>(&'a self, request: &'a str) -> AliasMapLookupIterator<'a, ExportsValue> {
+        self.0.lookup(request)
+    }
+
+    /// Looks up a request pattern in the "imports" field. See
+    /// [ExportsField::lookup_pattern].
+    pub fn lookup_pattern(&self, request: &Pattern) -> Vec<(&ExportsValue, Option<Pattern>)> {
+        lookup_pattern(&self.0, request)
+    }
+}
+
+fn lookup_pattern<'a>(
+    map: &'a AliasMap<ExportsValue>,
+    request: &Pattern,
+) -> Vec<(&'a ExportsValue, Option<Pattern>)> {
+    let mut request = request.clone();
+    request.normalize();
+    let mut results = Vec::new();
+    for (pattern, value) in map {
+        match pattern {
+            AliasPattern::Exact(key) => {
+                if request.is_match(&key) {
+                    results.push((value, None));
+                }
+            }
+            AliasPattern::Wildcard { prefix, suffix } => {
+                if let Some(capture) = request
+                    .strip_prefix(&prefix)
+                    .and_then(|rest| rest.strip_suffix(&suffix))
+                {
+                    results.push((value, Some(capture)));
+                }
+            }
+        }
+    }
+    results
 }
 
 /// Content of an "alias" configuration
@@ -0,0 +1,579 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use turbo_tasks::{primitives::OptionStringVc, trace::TraceRawVcs};
+use turbo_tasks_fs::{FileContent, FileJsonContent, FileSystemPath, FileSystemPathVc};
+
+use super::{find_context_file, package_json, FindContextFileResult};
+use crate::issue::lockfile::{LockfileIssue, LockfileIssueVc};
+
+/// Fields of a package.json that list the dependencies of a package.
+const DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+/// The locked versions of all packages listed in a lockfile.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct LockedVersions {
+    /// The lockfile the versions were read from. Importer paths are relative
+    /// to its directory.
+    pub lockfile: FileSystemPathVc,
+    pub entries: LockfileEntries,
+}
+
+/// The parsed contents of a lockfile, independent of the package manager
+/// that wrote it.
+#[derive(TraceRawVcs, Hash, PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LockfileEntries {
+    /// The direct dependencies of each importer (the root package and every
+    /// workspace package), keyed by the importer's directory relative to the
+    /// lockfile. The root importer has the empty key.
+    pub importers: BTreeMap<String, BTreeMap<String, String>>,
+    /// The dependencies of each locked package, keyed by `name@version`.
+    pub packages: BTreeMap<String, BTreeMap<String, String>>,
+    /// Versions keyed by `name@range` descriptors. Used by lockfiles which
+    /// don't record importers (yarn), where the range is taken from the
+    /// package.json of the requesting package.
+    pub descriptors: BTreeMap<String, String>,
+}
+
+impl LockfileEntries {
+    /// Parses a lockfile based on its file name.
+    pub fn parse(file_name: &str, content: &str) -> Result<Self> {
+        match file_name {
+            "package-lock.json" | "npm-shrinkwrap.json" => Self::parse_npm(content),
+            "pnpm-lock.yaml" => Self::parse_pnpm(content),
+            "yarn.lock" => Self::parse_yarn(content),
+            _ => bail!("unsupported lockfile {file_name}"),
+        }
+    }
+
+    /// Parses a `package-lock.json`. Versions 2 and 3 are read from the
+    /// `packages` field, version 1 from the nested `dependencies` field.
+    pub fn parse_npm(content: &str) -> Result<Self> {
+        let lockfile: JsonValue = serde_json::from_str(content)?;
+        let mut locations = BTreeMap::new();
+        let mut dependencies = BTreeMap::new();
+        if let Some(packages) = lockfile["packages"].as_object() {
+            for (location, entry) in packages {
+                if entry["link"].as_bool() == Some(true) {
+                    continue;
+                }
+                if let Some(version) = entry["version"].as_str() {
+                    locations.insert(location.clone(), version.to_string());
+                }
+                let names = DEPENDENCY_FIELDS
+                    .iter()
+                    .filter_map(|field| entry[field].as_object())
+                    .flat_map(|deps| deps.keys().cloned())
+                    .collect::<Vec<_>>();
+                dependencies.insert(location.clone(), names);
+            }
+        } else if let Some(deps) = lockfile["dependencies"].as_object() {
+            fn flatten(
+                prefix: &str,
+                deps: &serde_json::Map<String, JsonValue>,
+                locations: &mut BTreeMap<String, String>,
+                dependencies: &mut BTreeMap<String, Vec<String>>,
+            ) {
+                for (name, entry) in deps {
+                    let location = if prefix.is_empty() {
+                        format!("node_modules/{name}")
+                    } else {
+                        format!("{prefix}/node_modules/{name}")
+                    };
+                    if let Some(version) = entry["version"].as_str() {
+                        locations.insert(location.clone(), version.to_string());
+                    }
+                    let names = entry["requires"]
+                        .as_object()
+                        .map(|requires| requires.keys().cloned().collect())
+                        .unwrap_or_default();
+                    dependencies.insert(location.clone(), names);
+                    if let Some(nested) = entry["dependencies"].as_object() {
+                        flatten(&location, nested, locations, dependencies);
+                    }
+                }
+            }
+            flatten("", deps, &mut locations, &mut dependencies);
+            // Version 1 lockfiles don't list the root dependencies, but every
+            // top-level entry is reachable from the root.
+            let root = locations
+                .keys()
+                .filter_map(|location| location.strip_prefix("node_modules/"))
+                .filter(|name| !name.contains("/node_modules/"))
+                .map(|name| name.to_string())
+                .collect();
+            dependencies.insert(String::new(), root);
+        } else {
+            bail!("package-lock.json contains neither `packages` nor `dependencies`");
+        }
+
+        let mut entries = Self::default();
+        for (location, names) in dependencies {
+            let resolved = names
+                .into_iter()
+                .filter_map(|name| {
+                    let version = npm_lookup(&locations, &location, &name)?;
+                    Some((name, version.to_string()))
+                })
+                .collect();
+            match location.rfind("node_modules/") {
+                Some(index) => {
+                    let name = &location[index + "node_modules/".len()..];
+                    if let Some(version) = locations.get(&location) {
+                        entries
+                            .packages
+                            .entry(format!("{name}@{version}"))
+                            .or_insert(resolved);
+                    }
+                }
+                None => {
+                    entries.importers.insert(location, resolved);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Parses a `pnpm-lock.yaml`, supporting both the `/name/version` (v5) and
+    /// `/name@version` (v6) package key formats.
+    pub fn parse_pnpm(content: &str) -> Result<Self> {
+        let lockfile: YamlValue = serde_yaml::from_str(content)?;
+        let mut entries = Self::default();
+        if let Some(importers) = lockfile["importers"].as_mapping() {
+            for (importer, entry) in importers {
+                let importer = importer
+                    .as_str()
+                    .ok_or_else(|| anyhow!("importer keys must be strings"))?;
+                let importer = if importer == "." { "" } else { importer };
+                entries
+                    .importers
+                    .insert(importer.to_string(), pnpm_dependencies(entry));
+            }
+        } else {
+            entries
+                .importers
+                .insert(String::new(), pnpm_dependencies(&lockfile));
+        }
+        if let Some(packages) = lockfile["packages"].as_mapping() {
+            for (key, entry) in packages {
+                let Some((name, version)) = key.as_str().and_then(pnpm_package_key) else {
+                    continue;
+                };
+                entries
+                    .packages
+                    .entry(format!("{name}@{version}"))
+                    .or_insert_with(|| pnpm_dependencies(entry));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Parses a `yarn.lock`, either in the classic (v1) format or in the YAML
+    /// format used by yarn berry.
+    pub fn parse_yarn(content: &str) -> Result<Self> {
+        let mut entries = Self::default();
+        if content.contains("\n__metadata:") {
+            let lockfile: YamlValue = serde_yaml::from_str(content)?;
+            let Some(lockfile) = lockfile.as_mapping() else {
+                bail!("yarn.lock must be a mapping");
+            };
+            for (key, entry) in lockfile {
+                if let (Some(key), Some(version)) = (key.as_str(), yaml_version(&entry["version"]))
+                {
+                    for descriptor in key.split(", ") {
+                        entries
+                            .descriptors
+                            .insert(descriptor.to_string(), version.clone());
+                    }
+                }
+            }
+            return Ok(entries);
+        }
+
+        let mut current: Vec<String> = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !line.starts_with(' ') {
+                let key = line
+                    .strip_suffix(':')
+                    .with_context(|| format!("unexpected yarn.lock entry at line {}", index + 1))?;
+                current = key
+                    .split(", ")
+                    .map(|descriptor| descriptor.trim_matches('"').to_string())
+                    .collect();
+            } else if let Some(version) = line.trim_start().strip_prefix("version ") {
+                let version = version.trim_matches('"');
+                for descriptor in current.drain(..) {
+                    entries.descriptors.insert(descriptor, version.to_string());
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Looks up the version of a direct dependency of the importer containing
+    /// `path`, which is relative to the lockfile's directory.
+    pub fn importer_dependency(&self, path: &str, name: &str) -> Option<&str> {
+        self.importers
+            .iter()
+            .filter(|(importer, _)| {
+                importer.is_empty()
+                    || path == importer.as_str()
+                    || path
+                        .strip_prefix(importer.as_str())
+                        .map_or(false, |rest| rest.starts_with('/'))
+            })
+            .max_by_key(|(importer, _)| importer.len())
+            .and_then(|(_, dependencies)| dependencies.get(name))
+            .map(|version| version.as_str())
+    }
+
+    /// Looks up the version of the dependency `name` of the locked package
+    /// `package@version`.
+    pub fn package_dependency(&self, package: &str, version: &str, name: &str) -> Option<&str> {
+        self.packages
+            .get(&format!("{package}@{version}"))
+            .and_then(|dependencies| dependencies.get(name))
+            .map(|version| version.as_str())
+    }
+
+    /// Looks up the version locked for a `name@range` descriptor.
+    pub fn descriptor(&self, name: &str, range: &str) -> Option<&str> {
+        self.descriptors
+            .get(&format!("{name}@{range}"))
+            .or_else(|| self.descriptors.get(&format!("{name}@npm:{range}")))
+            .map(|version| version.as_str())
+    }
+}
+
+/// Looks up `name` the way node would from the package installed at
+/// `location`, walking up the directories of the `node_modules` layout
+/// described by a `package-lock.json`.
+fn npm_lookup<'a>(
+    locations: &'a BTreeMap<String, String>,
+    location: &str,
+    name: &str,
+) -> Option<&'a str> {
+    let mut base = location;
+    loop {
+        let candidate = if base.is_empty() {
+            format!("node_modules/{name}")
+        } else {
+            format!("{base}/node_modules/{name}")
+        };
+        if let Some(version) = locations.get(&candidate) {
+            return Some(version);
+        }
+        if base.is_empty() {
+            return None;
+        }
+        base = base.rfind('/').map_or("", |index| &base[..index]);
+    }
+}
+
+/// Reads the dependencies of a pnpm importer or package entry. Dependencies
+/// which are not installed from the registry (`link:`, `file:`, aliases) are
+/// skipped.
+fn pnpm_dependencies(entry: &YamlValue) -> BTreeMap<String, String> {
+    let mut dependencies = BTreeMap::new();
+    for field in DEPENDENCY_FIELDS {
+        let Some(deps) = entry[field].as_mapping() else {
+            continue;
+        };
+        for (name, value) in deps {
+            let version = match value {
+                YamlValue::Mapping(_) => yaml_version(&value["version"]),
+                value => yaml_version(value),
+            };
+            if let (Some(name), Some(version)) = (name.as_str(), version) {
+                if version.contains(':') || version.starts_with('/') {
+                    continue;
+                }
+                dependencies.insert(name.to_string(), strip_peer_suffix(&version).to_string());
+            }
+        }
+    }
+    dependencies
+}
+
+/// Splits a pnpm package key (`/@scope/name/1.0.0_peer@2.0.0` or
+/// `/@scope/name@1.0.0(peer@2.0.0)`) into the package name and version.
+fn pnpm_package_key(key: &str) -> Option<(&str, &str)> {
+    let key = key.strip_prefix('/')?;
+    let name_start = if key.starts_with('@') {
+        key.find('/')? + 1
+    } else {
+        0
+    };
+    let separator = key[name_start..]
+        .find(|c| c == '@' || c == '/')
+        .map(|index| index + name_start)?;
+    Some((&key[..separator], strip_peer_suffix(&key[separator + 1..])))
+}
+
+/// Removes the peer dependency suffix pnpm appends to versions.
+fn strip_peer_suffix(version: &str) -> &str {
+    let end = version
+        .find(|c| c == '(' || c == '_')
+        .unwrap_or(version.len());
+    &version[..end]
+}
+
+fn yaml_version(value: &YamlValue) -> Option<String> {
+    match value {
+        YamlValue::String(version) => Some(version.clone()),
+        YamlValue::Number(version) => Some(version.to_string()),
+        _ => None,
+    }
+}
+
+/// Splits a path inside of the registry (`@scope/module/version/<path>`)
+/// into the package name and version.
+fn registry_package(path: &str) -> Option<(&str, &str)> {
+    let mut segments = path.splitn(4, '/');
+    let first = segments.next()?;
+    let name_len = if first.starts_with('@') {
+        first.len() + 1 + segments.next()?.len()
+    } else {
+        first.len()
+    };
+    let version = segments.next()?;
+    Some((&path[..name_len], version))
+}
+
+fn path_inside<'a>(root: &FileSystemPath, path: &'a FileSystemPath) -> Option<&'a str> {
+    if root == path {
+        Some("")
+    } else {
+        root.get_path_to(path)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl LockedVersionsVc {
+    /// Reads the locked versions from a `package-lock.json`, `pnpm-lock.yaml`
+    /// or `yarn.lock` file. Emits an issue and returns no versions when the
+    /// lockfile can't be parsed.
+    #[turbo_tasks::function]
+    pub async fn from_lockfile(lockfile: FileSystemPathVc) -> Result<Self> {
+        let entries = match &*lockfile.read().await? {
+            FileContent::Content(file) => {
+                let file_name = lockfile.await?.file_name().to_string();
+                file.content()
+                    .to_str()
+                    .and_then(|content| LockfileEntries::parse(&file_name, &content))
+            }
+            FileContent::NotFound => Err(anyhow!("lockfile not found")),
+        };
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(err) => {
+                let issue: LockfileIssueVc = LockfileIssue {
+                    path: lockfile,
+                    error_message: format!("{err:#}"),
+                }
+                .into();
+                issue.as_issue().emit();
+                LockfileEntries::default()
+            }
+        };
+        Ok(Self::cell(LockedVersions { lockfile, entries }))
+    }
+
+    /// Selects the version of `package_name` that is locked for the package
+    /// containing `context`. Packages inside of the `registry` use the
+    /// dependencies locked for them, everything else uses the dependencies
+    /// of the closest importer.
+    #[turbo_tasks::function]
+    pub async fn version_for(
+        self,
+        registry: FileSystemPathVc,
+        context: FileSystemPathVc,
+        package_name: String,
+    ) -> Result<OptionStringVc> {
+        let this = self.await?;
+        let context_value = context.await?;
+        let registry_value = registry.await?;
+        if let Some(path) = path_inside(&registry_value, &context_value) {
+            if let Some((name, version)) = registry_package(path) {
+                if let Some(version) = this
+                    .entries
+                    .package_dependency(name, version, &package_name)
+                {
+                    return Ok(OptionStringVc::cell(Some(version.to_string())));
+                }
+            }
+        } else {
+            let root = this.lockfile.parent().await?;
+            if let Some(path) = path_inside(&root, &context_value) {
+                if let Some(version) = this.entries.importer_dependency(path, &package_name) {
+                    return Ok(OptionStringVc::cell(Some(version.to_string())));
+                }
+            }
+        }
+        if !this.entries.descriptors.is_empty() {
+            if let FindContextFileResult::Found(package_json_path, _) =
+                &*find_context_file(context, package_json()).await?
+            {
+                if let FileJsonContent::Content(package_json) =
+                    &*package_json_path.read_json().await?
+                {
+                    for field in DEPENDENCY_FIELDS {
+                        if let Some(range) = package_json[field][&package_name].as_str() {
+                            if let Some(version) = this.entries.descriptor(&package_name, range) {
+                                return Ok(OptionStringVc::cell(Some(version.to_string())));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(OptionStringVc::cell(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pnpm_package_key, registry_package, LockfileEntries};
+
+    #[test]
+    fn npm() {
+        let entries = LockfileEntries::parse_npm(
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "dependencies": { "a": "^1.0.0" }, "workspaces": ["packages/*"] },
+                    "packages/web": { "dependencies": { "a": "^2.0.0", "b": "^1.0.0" } },
+                    "node_modules/web": { "resolved": "packages/web", "link": true },
+                    "node_modules/a": { "version": "1.2.0", "dependencies": { "b": "^1.0.0" } },
+                    "node_modules/b": { "version": "1.0.1" },
+                    "packages/web/node_modules/a": { "version": "2.0.0" }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(entries.importer_dependency("", "a"), Some("1.2.0"));
+        assert_eq!(entries.importer_dependency("src/lib", "a"), Some("1.2.0"));
+        assert_eq!(
+            entries.importer_dependency("packages/web", "a"),
+            Some("2.0.0")
+        );
+        assert_eq!(
+            entries.importer_dependency("packages/web/src", "b"),
+            Some("1.0.1")
+        );
+        assert_eq!(
+            entries.importer_dependency("packages/webapp", "a"),
+            Some("1.2.0")
+        );
+        assert_eq!(entries.package_dependency("a", "1.2.0", "b"), Some("1.0.1"));
+        assert_eq!(entries.importer_dependency("", "web"), None);
+    }
+
+    #[test]
+    fn pnpm() {
+        let entries = LockfileEntries::parse_pnpm(
+            r#"
+lockfileVersion: 5.4
+importers:
+  .:
+    specifiers:
+      react: ^18.2.0
+    dependencies:
+      react: 18.2.0
+  packages/ui:
+    specifiers:
+      '@scope/icons': ^1.0.0
+      shared: workspace:*
+    dependencies:
+      '@scope/icons': 1.0.0_react@18.2.0
+      shared: link:../shared
+packages:
+  /react/18.2.0:
+    dependencies:
+      loose-envify: 1.4.0
+  /@scope/icons/1.0.0_react@18.2.0:
+    peerDependencies:
+      react: 18.2.0
+"#,
+        )
+        .unwrap();
+        assert_eq!(entries.importer_dependency("", "react"), Some("18.2.0"));
+        assert_eq!(
+            entries.importer_dependency("packages/ui", "@scope/icons"),
+            Some("1.0.0")
+        );
+        assert_eq!(entries.importer_dependency("packages/ui", "shared"), None);
+        assert_eq!(
+            entries.package_dependency("react", "18.2.0", "loose-envify"),
+            Some("1.4.0")
+        );
+        assert_eq!(
+            entries.package_dependency("@scope/icons", "1.0.0", "react"),
+            Some("18.2.0")
+        );
+    }
+
+    #[test]
+    fn yarn() {
+        let entries = LockfileEntries::parse_yarn(
+            r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"@babel/code-frame@^7.0.0", "@babel/code-frame@^7.10.4":
+  version "7.12.13"
+  dependencies:
+    "@babel/highlight" "^7.12.13"
+
+lodash@^4.17.21:
+  version "4.17.21"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            entries.descriptor("@babel/code-frame", "^7.10.4"),
+            Some("7.12.13")
+        );
+        assert_eq!(entries.descriptor("lodash", "^4.17.21"), Some("4.17.21"));
+        assert_eq!(entries.descriptor("lodash", "^3.0.0"), None);
+
+        let entries = LockfileEntries::parse_yarn(
+            r#"
+__metadata:
+  version: 6
+
+"lodash@npm:^4.17.20, lodash@npm:^4.17.21":
+  version: 4.17.21
+"#,
+        )
+        .unwrap();
+        assert_eq!(entries.descriptor("lodash", "^4.17.20"), Some("4.17.21"));
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(pnpm_package_key("/react/18.2.0"), Some(("react", "18.2.0")));
+        assert_eq!(
+            pnpm_package_key("/@scope/icons@1.0.0(react@18.2.0)"),
+            Some(("@scope/icons", "1.0.0"))
+        );
+        assert_eq!(
+            registry_package("@scope/icons/1.0.0/dist/index.js"),
+            Some(("@scope/icons", "1.0.0"))
+        );
+        assert_eq!(registry_package("react/18.2.0"), Some(("react", "18.2.0")));
+        assert_eq!(registry_package("react"), None);
+    }
+}
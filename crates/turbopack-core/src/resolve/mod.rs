@@ -7,6 +7,9 @@ use std::{
 };
 
 use anyhow::{anyhow, Result};
+use mime::Mime;
+use mime_guess::get_mime_extensions_str;
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use turbo_tasks::{
@@ -16,11 +19,13 @@ use turbo_tasks::{
 };
 use turbo_tasks_fs::{
     util::{normalize_path, normalize_request},
-    FileJsonContent, FileJsonContentVc, FileSystemEntryType, FileSystemPathVc, RealPathResult,
+    File, FileContent, FileJsonContent, FileJsonContentVc, FileSystemEntryType, FileSystemPathVc,
+    RealPathResult,
 };
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
 use self::{
-    exports::ExportsField,
+    exports::{ExportsField, ImportsField},
     options::{
         resolve_modules_options, ImportMapResult, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveModulesOptionsVc, ResolveOptionsVc,
@@ -42,10 +47,12 @@ use crate::{
         pattern::{read_matches, Pattern, PatternMatch, PatternVc},
     },
     source_asset::SourceAssetVc,
+    virtual_asset::VirtualAssetVc,
 };
 
 mod alias_map;
 pub(crate) mod exports;
+pub mod lockfile;
 pub mod options;
 pub mod origin;
 pub mod parse;
@@ -387,6 +394,37 @@ async fn exports_field(
     }
 }
 
+#[turbo_tasks::value(shared)]
+enum ImportsFieldResult {
+    Some(#[turbo_tasks(debug_ignore, trace_ignore)] ImportsField),
+    None,
+}
+
+#[turbo_tasks::function]
+async fn imports_field(package_json_path: FileSystemPathVc) -> Result<ImportsFieldResultVc> {
+    if let FileJsonContent::Content(package_json) = &*package_json_path.read_json().await? {
+        let field_value = &package_json["imports"];
+        if let serde_json::Value::Null = field_value {
+            return Ok(ImportsFieldResult::None.into());
+        }
+        let imports_field: Result<ImportsField> = field_value.try_into();
+        match imports_field {
+            Ok(imports_field) => Ok(ImportsFieldResult::Some(imports_field).into()),
+            Err(err) => {
+                let issue: PackageJsonIssueVc = PackageJsonIssue {
+                    path: package_json_path,
+                    error_message: err.to_string(),
+                }
+                .into();
+                issue.as_issue().emit();
+                Ok(ImportsFieldResult::None.into())
+            }
+        }
+    } else {
+        Ok(ImportsFieldResult::None.into())
+    }
+}
+
 #[turbo_tasks::function]
 pub fn package_json() -> StringsVc {
     StringsVc::cell(vec!["package.json".to_string()])
@@ -437,6 +475,8 @@ pub async fn find_context_file(
 struct FindPackageResult {
     packages: Vec<FileSystemPathVc>,
     references: Vec<AssetReferenceVc>,
+    /// Lockfiles of registries which don't lock a version of the package.
+    missing_in_lockfiles: Vec<FileSystemPathVc>,
 }
 
 #[turbo_tasks::function]
@@ -447,6 +487,7 @@ async fn find_package(
 ) -> Result<FindPackageResultVc> {
     let mut packages = vec![];
     let mut references = vec![];
+    let mut missing_in_lockfiles = vec![];
     let options = options.await?;
     for resolve_modules in &options.modules {
         match resolve_modules {
@@ -479,12 +520,27 @@ async fn find_package(
                     packages.push(package_dir.resolve().await?);
                 }
             }
-            ResolveModules::Registry(_, _) => todo!(),
+            ResolveModules::Registry(registry, locked_versions) => {
+                let lockfile = locked_versions.await?.lockfile;
+                references.push(AffectingResolvingAssetReferenceVc::new(lockfile).into());
+                let version = locked_versions
+                    .version_for(*registry, context, package_name.clone())
+                    .await?;
+                if let Some(version) = &*version {
+                    let package_dir = registry.join(&format!("{package_name}/{version}"));
+                    if dir_exists(package_dir, &mut references).await?.is_some() {
+                        packages.push(package_dir.resolve().await?);
+                    }
+                } else {
+                    missing_in_lockfiles.push(lockfile);
+                }
+            }
         }
     }
     Ok(FindPackageResultVc::cell(FindPackageResult {
         packages,
         references,
+        missing_in_lockfiles,
     }))
 }
 
@@ -663,27 +719,25 @@ pub async fn resolve(
             module,
             path,
             query,
-        } => resolve_module_request(context, options, options_value, module, path, query).await?,
+        } => {
+            resolve_module_request(
+                context,
+                request,
+                options,
+                options_value,
+                module,
+                path,
+                query,
+            )
+            .await?
+        }
         Request::ServerRelative { path } => {
             let mut new_pat = path.clone();
             new_pat.push_front(".".to_string().into());
             let relative = RequestVc::relative(Value::new(new_pat), true);
 
-            let issue: ResolvingIssueVc = ResolvingIssue {
-                request_type: "server relative import: not implemented yet".to_string(),
-                request,
-                context,
-                resolve_options: options,
-                error_message: Some(
-                    "server relative imports are not implemented yet. Please try an import \
-                     relative to the file you are importing from."
-                        .to_string(),
-                ),
-            }
-            .into();
-            issue.as_issue().emit();
-
-            resolve(context.root(), relative, options)
+            let root = options_value.root.unwrap_or_else(|| context.root());
+            resolve(root, relative, options)
         }
         Request::Windows { path: _ } => {
             let issue: ResolvingIssueVc = ResolvingIssue {
@@ -699,17 +753,8 @@ pub async fn resolve(
             ResolveResult::unresolveable().into()
         }
         Request::Empty => ResolveResult::unresolveable().into(),
-        Request::PackageInternal { path: _ } => {
-            let issue: ResolvingIssueVc = ResolvingIssue {
-                request_type: "package internal import: not implemented yet".to_string(),
-                request,
-                context,
-                resolve_options: options,
-                error_message: Some("package internal imports are not implemented yet".to_string()),
-            }
-            .into();
-            issue.as_issue().emit();
-            ResolveResult::unresolveable().into()
+        Request::PackageInternal { path } => {
+            resolve_package_internal_with_imports_field(context, options, path).await?
         }
         Request::Uri {
             protocol,
@@ -719,6 +764,17 @@ pub async fn resolve(
             Vec::new(),
         )
         .into(),
+        Request::DataUri {
+            mime_type,
+            attributes,
+            base64,
+            data,
+        } => {
+            resolve_data_uri(
+                context, request, options, mime_type, attributes, *base64, data,
+            )
+            .await?
+        }
         Request::Unknown { path } => {
             let issue: ResolvingIssueVc = ResolvingIssue {
                 request_type: format!("unknown import: `{}`", path),
@@ -815,6 +871,7 @@ async fn resolve_into_folder(
 
 async fn resolve_module_request(
     context: FileSystemPathVc,
+    request: RequestVc,
     options: ResolveOptionsVc,
     options_value: &ResolveOptions,
     module: &str,
@@ -829,6 +886,20 @@ async fn resolve_module_request(
     .await?;
 
     if result.packages.is_empty() {
+        for lockfile in result.missing_in_lockfiles.iter() {
+            let issue: ResolvingIssueVc = ResolvingIssue {
+                request_type: "module request: not in lockfile".to_string(),
+                request,
+                context,
+                resolve_options: options,
+                error_message: Some(format!(
+                    "package \"{module}\" is not locked in {}",
+                    lockfile.to_string().await?
+                )),
+            }
+            .into();
+            issue.as_issue().emit();
+        }
         return Ok(ResolveResult::Unresolveable(result.references.clone()).into());
     }
 
@@ -886,7 +957,17 @@ async fn resolve_module_request(
                                     unspecified_conditions,
                                 )?);
                             } else {
-                                todo!("pattern into an exports field is not implemented yet");
+                                let mut request = Pattern::Constant(".".to_string());
+                                request.push(path.clone());
+                                results.push(handle_exports_field_pattern(
+                                    *package_path,
+                                    package_json_path,
+                                    options,
+                                    exports_field,
+                                    &request,
+                                    conditions,
+                                    unspecified_conditions,
+                                ));
                             }
                             // other options do not apply anymore when an exports
                             // field exist
@@ -908,6 +989,71 @@ async fn resolve_module_request(
     ))
 }
 
+/// Turns a `data:` URI into a [VirtualAsset]. The extension of its virtual
+/// path is derived from the MIME type, so the module type is picked the same
+/// way as for files on disk.
+async fn resolve_data_uri(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    options: ResolveOptionsVc,
+    mime_type: &str,
+    attributes: &str,
+    base64: bool,
+    data: &str,
+) -> Result<ResolveResultVc> {
+    let content = if base64 {
+        match base64::decode(data) {
+            Ok(content) => content,
+            Err(err) => {
+                let issue: ResolvingIssueVc = ResolvingIssue {
+                    request_type: "data uri".to_string(),
+                    request,
+                    context,
+                    resolve_options: options,
+                    error_message: Some(format!("invalid base64 data: {err}")),
+                }
+                .into();
+                issue.as_issue().emit();
+                return Ok(ResolveResult::unresolveable().into());
+            }
+        }
+    } else {
+        percent_decode_str(data).collect()
+    };
+
+    let mut file = File::from(content);
+    let content_type = if attributes.is_empty() {
+        mime_type.parse::<Mime>()
+    } else {
+        format!("{mime_type};{attributes}").parse::<Mime>()
+    };
+    if let Ok(content_type) = content_type {
+        file = file.with_content_type(content_type);
+    }
+
+    let extension = match mime_type {
+        "text/javascript" | "application/javascript" | "application/x-javascript" => "js",
+        "text/jsx" => "jsx",
+        "application/typescript" | "text/typescript" => "ts",
+        "application/json" => "json",
+        "text/css" => "css",
+        "application/wasm" => "wasm",
+        mime_type => get_mime_extensions_str(mime_type)
+            .and_then(|extensions| extensions.first().copied())
+            .unwrap_or("txt"),
+    };
+    let hash = encode_hex(hash_xxh3_hash64(
+        request.await?.request().unwrap_or_default(),
+    ));
+    let path = context.join(&format!("__data_uri__/{hash}.{extension}"));
+
+    Ok(ResolveResult::Single(
+        VirtualAssetVc::new(path, FileContent::Content(file).into()).into(),
+        Vec::new(),
+    )
+    .into())
+}
+
 async fn resolve_import_map_result(
     result: &ImportMapResult,
     context: FileSystemPathVc,
@@ -1078,12 +1224,129 @@ fn handle_exports_field(
     conditions: &BTreeMap<String, ConditionValue>,
     unspecified_conditions: &ConditionValue,
 ) -> Result<ResolveResultVc> {
-    let mut results = Vec::new();
-    let mut conditions_state = HashMap::new();
     let values = exports_field
         .lookup(path)
         .map(AliasMatch::try_into_self)
         .collect::<Result<Vec<Cow<'_, ExportsValue>>>>()?;
+    let results = field_results(&values, conditions, unspecified_conditions);
+    let mut resolved_results = Vec::new();
+    for path in results {
+        let request = Pattern::Constant(path.to_string());
+        // exports must stay inside of the package
+        if is_inside_package(&request) {
+            let request = RequestVc::parse(Value::new(request));
+            resolved_results.push(resolve(package_path, request, options));
+        }
+    }
+    // other options do not apply anymore when an exports field exist
+    Ok(merge_results_with_references(
+        resolved_results,
+        vec![AffectingResolvingAssetReferenceVc::new(package_json).into()],
+    ))
+}
+
+/// Like [handle_exports_field], but for a dynamic request `path`. Every
+/// export the pattern could match is resolved, leading to alternative results.
+fn handle_exports_field_pattern(
+    package_path: FileSystemPathVc,
+    package_json: FileSystemPathVc,
+    options: ResolveOptionsVc,
+    exports_field: &ExportsField,
+    path: &Pattern,
+    conditions: &BTreeMap<String, ConditionValue>,
+    unspecified_conditions: &ConditionValue,
+) -> ResolveResultVc {
+    let resolved_results = field_pattern_results(
+        exports_field.lookup_pattern(path),
+        conditions,
+        unspecified_conditions,
+    )
+    .into_iter()
+    // exports must stay inside of the package
+    .filter(is_inside_package)
+    .map(|request| resolve(package_path, RequestVc::parse(Value::new(request)), options))
+    .collect();
+    merge_results_with_references(
+        resolved_results,
+        vec![AffectingResolvingAssetReferenceVc::new(package_json).into()],
+    )
+}
+
+/// Resolves a package internal request (`#internal`) using the "imports"
+/// field of the closest package.json.
+async fn resolve_package_internal_with_imports_field(
+    context: FileSystemPathVc,
+    options: ResolveOptionsVc,
+    path: &Pattern,
+) -> Result<ResolveResultVc> {
+    let (conditions, unspecified_conditions) = options
+        .await?
+        .into_package
+        .iter()
+        .find_map(|resolve_into_package| match resolve_into_package {
+            ResolveIntoPackage::ExportsField {
+                conditions,
+                unspecified_conditions,
+                ..
+            } => Some((conditions.clone(), unspecified_conditions.clone())),
+            _ => None,
+        })
+        .unwrap_or((BTreeMap::new(), ConditionValue::Unset));
+
+    let (package_json_path, mut references) = match &*find_context_file(context, package_json())
+        .await?
+    {
+        FindContextFileResult::Found(package_json_path, refs) => (*package_json_path, refs.clone()),
+        FindContextFileResult::NotFound(refs) => {
+            return Ok(ResolveResult::Unresolveable(refs.clone()).into());
+        }
+    };
+    references.push(AffectingResolvingAssetReferenceVc::new(package_json_path).into());
+    let ImportsFieldResult::Some(imports_field) = &*imports_field(package_json_path).await? else {
+        return Ok(ResolveResult::Unresolveable(references).into());
+    };
+    let package_path = package_json_path.parent();
+
+    let requests = if let Some(path) = path.clone().into_string() {
+        let values = imports_field
+            .lookup(&path)
+            .map(AliasMatch::try_into_self)
+            .collect::<Result<Vec<Cow<'_, ExportsValue>>>>()?;
+        field_results(&values, &conditions, &unspecified_conditions)
+            .into_iter()
+            .map(|result| Pattern::Constant(result.to_string()))
+            .collect()
+    } else {
+        field_pattern_results(
+            imports_field.lookup_pattern(path),
+            &conditions,
+            &unspecified_conditions,
+        )
+    };
+
+    let mut resolved_results = Vec::new();
+    for request in requests {
+        // imports can either point to a file inside of the package or to
+        // another package
+        if is_inside_package(&request)
+            || matches!(Request::parse(request.clone()), Request::Module { .. })
+        {
+            let request = RequestVc::parse(Value::new(request));
+            resolved_results.push(resolve(package_path, request, options));
+        }
+    }
+    Ok(merge_results_with_references(resolved_results, references))
+}
+
+/// Applies the `conditions` to the values an exports or imports field lookup
+/// returned. Only the first value that leads to results is used.
+fn field_results<'a>(
+    values: &'a [Cow<'_, ExportsValue>],
+    conditions: &BTreeMap<String, ConditionValue>,
+    unspecified_conditions: &ConditionValue,
+) -> Vec<&'a str> {
+    let mut results = Vec::new();
+    let mut conditions_state = HashMap::new();
     for value in values.iter() {
         if value.add_results(
             conditions,
@@ -1094,22 +1357,80 @@ fn handle_exports_field(
             break;
         }
     }
-    {
-        let mut duplicates_set = HashSet::new();
-        results.retain(|item| duplicates_set.insert(*item));
+    let mut duplicates_set = HashSet::new();
+    results.retain(|item| duplicates_set.insert(*item));
+    results
+}
+
+/// Applies the `conditions` to all entries of an exports or imports field a
+/// dynamic request could match, and substitutes the captured part of the
+/// request into the wildcards of the results.
+fn field_pattern_results(
+    matches: Vec<(&ExportsValue, Option<Pattern>)>,
+    conditions: &BTreeMap<String, ConditionValue>,
+    unspecified_conditions: &ConditionValue,
+) -> Vec<Pattern> {
+    let mut requests = Vec::new();
+    for (value, capture) in matches {
+        let mut conditions_state = HashMap::new();
+        let mut results = Vec::new();
+        value.add_results(
+            conditions,
+            unspecified_conditions,
+            &mut conditions_state,
+            &mut results,
+        );
+        for result in results {
+            let mut request = match &capture {
+                Some(capture) => {
+                    let mut parts = result.split('*');
+                    let mut request = Pattern::Constant(parts.next().unwrap().to_string());
+                    for part in parts {
+                        request.push(capture.clone());
+                        request.push(Pattern::Constant(part.to_string()));
+                    }
+                    request
+                }
+                None => Pattern::Constant(result.to_string()),
+            };
+            // Every alternative of the capture is checked on its own
+            request.normalize();
+            let alternatives = match request {
+                Pattern::Alternatives(alternatives) => alternatives,
+                request => vec![request],
+            };
+            for request in alternatives {
+                if !requests.contains(&request) {
+                    requests.push(request);
+                }
+            }
+        }
     }
-    let mut resolved_results = Vec::new();
-    for path in results {
-        if let Some(path) = normalize_path(path) {
-            let request = RequestVc::parse(Value::new(format!("./{}", path).into()));
-            resolved_results.push(resolve(package_path, request, options));
+    requests
+}
+
+/// Checks if a target of an exports or imports field is a relative request
+/// that doesn't escape the package. The target must be normalized and the
+/// captured part of the request already substituted, so `..` segments in the
+/// capture are taken into account. Dynamic parts are treated as empty: they
+/// only match names of existing files, which can't form `..` segments.
+fn is_inside_package(request: &Pattern) -> bool {
+    let parts = match request {
+        Pattern::Concatenation(parts) => parts.as_slice(),
+        request => std::slice::from_ref(request),
+    };
+    if !matches!(parts.first(), Some(Pattern::Constant(first)) if first.starts_with("./")) {
+        return false;
+    }
+    let mut path = String::new();
+    for part in parts {
+        match part {
+            Pattern::Constant(constant) => path.push_str(constant),
+            Pattern::Dynamic => {}
+            Pattern::Alternatives(_) | Pattern::Concatenation(_) => return false,
         }
     }
-    // other options do not apply anymore when an exports field exist
-    Ok(merge_results_with_references(
-        resolved_results,
-        vec![AffectingResolvingAssetReferenceVc::new(package_json).into()],
-    ))
+    normalize_path(&path).is_some()
 }
 
 #[turbo_tasks::value]
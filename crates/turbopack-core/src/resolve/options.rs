@@ -8,16 +8,13 @@ use turbo_tasks::{
 };
 use turbo_tasks_fs::{glob::GlobVc, FileSystemPathVc};
 
+pub use super::lockfile::{LockedVersions, LockedVersionsVc};
 use super::{
     alias_map::{AliasMap, AliasTemplate},
     AliasPattern, ResolveResult, ResolveResultVc, SpecialType,
 };
 use crate::resolve::parse::RequestVc;
 
-#[turbo_tasks::value(shared)]
-#[derive(Hash, Debug)]
-pub struct LockedVersions {}
-
 /// A location where to resolve modules.
 #[derive(
     TraceRawVcs, Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize, ValueDebugFormat,
@@ -374,6 +371,9 @@ pub struct ResolveOptions {
     /// An import map to use when a request is otherwise unresolveable.
     pub fallback_import_map: Option<ImportMapVc>,
     pub resolved_map: Option<ResolvedMapVc>,
+    /// The directory server relative requests (`/path`) are resolved in.
+    /// Defaults to the root of the filesystem of the requesting file.
+    pub root: Option<FileSystemPathVc>,
     pub placeholder_for_future_extensions: (),
 }
 
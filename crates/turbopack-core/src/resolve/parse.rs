@@ -36,6 +36,12 @@ pub enum Request {
         protocol: String,
         remainder: String,
     },
+    DataUri {
+        mime_type: String,
+        attributes: String,
+        base64: bool,
+        data: String,
+    },
     Unknown {
         path: Pattern,
     },
@@ -75,6 +81,24 @@ impl Request {
                 protocol,
                 remainder,
             } => format!("{protocol}{remainder}"),
+            Request::DataUri {
+                mime_type,
+                attributes,
+                base64,
+                data,
+            } => {
+                let mut uri = format!("data:{mime_type}");
+                if !attributes.is_empty() {
+                    uri.push(';');
+                    uri.push_str(attributes);
+                }
+                if *base64 {
+                    uri.push_str(";base64");
+                }
+                uri.push(',');
+                uri.push_str(data);
+                uri
+            }
             Request::Unknown {
                 path: Pattern::Constant(path),
             } => path.to_string(),
@@ -109,9 +133,29 @@ impl Request {
                     if WINDOWS_PATH.is_match(r) {
                         return Request::Windows { path: request };
                     }
+                    if let Some(uri) = r.strip_prefix("data:") {
+                        if let Some((header, data)) = uri.split_once(',') {
+                            let mut params = header.split(';');
+                            let mime_type = params.next().unwrap_or_default();
+                            let mut attributes = params.collect::<Vec<_>>();
+                            let base64 = attributes.last() == Some(&"base64");
+                            if base64 {
+                                attributes.pop();
+                            }
+                            return Request::DataUri {
+                                mime_type: if mime_type.is_empty() {
+                                    "text/plain".to_string()
+                                } else {
+                                    mime_type.to_string()
+                                },
+                                attributes: attributes.join(";"),
+                                base64,
+                                data: data.to_string(),
+                            };
+                        }
+                    }
                     if let Some(caps) = URI_PATH.captures(r) {
                         if let (Some(protocol), Some(remainder)) = (caps.get(1), caps.get(2)) {
-                            // TODO data uri
                             return Request::Uri {
                                 protocol: protocol.as_str().to_string(),
                                 remainder: remainder.as_str().to_string(),
@@ -164,7 +208,7 @@ impl Request {
                         Request::PackageInternal { path } => {
                             path.extend(iter);
                         }
-                        Request::Uri { .. } => {
+                        Request::Uri { .. } | Request::DataUri { .. } => {
                             result = Request::Dynamic;
                         }
                         Request::Unknown { path } => {
@@ -235,6 +279,7 @@ impl RequestVc {
             | Request::Windows { .. }
             | Request::Relative { .. }
             | Request::Uri { .. }
+            | Request::DataUri { .. }
             | Request::Dynamic => self,
             Request::Module {
                 module,
@@ -312,6 +357,7 @@ impl ValueToString for Request {
                 protocol,
                 remainder,
             } => format!("uri \"{protocol}\" \"{remainder}\""),
+            Request::DataUri { mime_type, .. } => format!("data uri \"{mime_type}\""),
             Request::Unknown { path } => format!("unknown {path}"),
             Request::Dynamic => "dynamic".to_string(),
             Request::Alternatives { requests } => requests
@@ -287,6 +287,131 @@ impl Pattern {
         }
     }
 
+    /// Returns the pattern matching what remains of the strings matched by
+    /// this pattern after removing `prefix`, or `None` when no string matched
+    /// by this pattern starts with `prefix`. Dynamic parts are assumed to be
+    /// able to absorb any part of the prefix.
+    ///
+    /// The pattern must be normalized.
+    pub fn strip_prefix(&self, prefix: &str) -> Option<Pattern> {
+        if prefix.is_empty() {
+            return Some(self.clone());
+        }
+        match self {
+            Pattern::Constant(c) => c
+                .strip_prefix(prefix)
+                .map(|rest| Pattern::Constant(rest.to_string())),
+            Pattern::Dynamic => Some(Pattern::Dynamic),
+            Pattern::Alternatives(list) => {
+                let list = list
+                    .iter()
+                    .filter_map(|alt| alt.strip_prefix(prefix))
+                    .collect::<Vec<_>>();
+                (!list.is_empty()).then(|| Pattern::alternatives(list))
+            }
+            Pattern::Concatenation(list) => {
+                let mut prefix = prefix;
+                for (index, part) in list.iter().enumerate() {
+                    match part {
+                        Pattern::Constant(c) => {
+                            if let Some(rest) = prefix.strip_prefix(c.as_str()) {
+                                prefix = rest;
+                                if prefix.is_empty() {
+                                    return Some(Pattern::concatenation(
+                                        list[index + 1..].iter().cloned(),
+                                    ));
+                                }
+                            } else if let Some(rest) = c.strip_prefix(prefix) {
+                                return Some(Pattern::concatenation(
+                                    [Pattern::Constant(rest.to_string())]
+                                        .into_iter()
+                                        .chain(list[index + 1..].iter().cloned()),
+                                ));
+                            } else {
+                                return None;
+                            }
+                        }
+                        Pattern::Dynamic => {
+                            return Some(Pattern::concatenation(list[index..].iter().cloned()));
+                        }
+                        Pattern::Alternatives(_) | Pattern::Concatenation(_) => {
+                            panic!("for strip_prefix() the Pattern must be normalized")
+                        }
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Returns the pattern matching what remains of the strings matched by
+    /// this pattern after removing `suffix`, or `None` when no string matched
+    /// by this pattern ends with `suffix`. Dynamic parts are assumed to be
+    /// able to absorb any part of the suffix.
+    ///
+    /// The pattern must be normalized.
+    pub fn strip_suffix(&self, suffix: &str) -> Option<Pattern> {
+        if suffix.is_empty() {
+            return Some(self.clone());
+        }
+        match self {
+            Pattern::Constant(c) => c
+                .strip_suffix(suffix)
+                .map(|rest| Pattern::Constant(rest.to_string())),
+            Pattern::Dynamic => Some(Pattern::Dynamic),
+            Pattern::Alternatives(list) => {
+                let list = list
+                    .iter()
+                    .filter_map(|alt| alt.strip_suffix(suffix))
+                    .collect::<Vec<_>>();
+                (!list.is_empty()).then(|| Pattern::alternatives(list))
+            }
+            Pattern::Concatenation(list) => {
+                let mut suffix = suffix;
+                for (index, part) in list.iter().enumerate().rev() {
+                    match part {
+                        Pattern::Constant(c) => {
+                            if let Some(rest) = suffix.strip_suffix(c.as_str()) {
+                                suffix = rest;
+                                if suffix.is_empty() {
+                                    return Some(Pattern::concatenation(
+                                        list[..index].iter().cloned(),
+                                    ));
+                                }
+                            } else if let Some(rest) = c.strip_suffix(suffix) {
+                                return Some(Pattern::concatenation(
+                                    list[..index]
+                                        .iter()
+                                        .cloned()
+                                        .chain([Pattern::Constant(rest.to_string())]),
+                                ));
+                            } else {
+                                return None;
+                            }
+                        }
+                        Pattern::Dynamic => {
+                            return Some(Pattern::concatenation(list[..=index].iter().cloned()));
+                        }
+                        Pattern::Alternatives(_) | Pattern::Concatenation(_) => {
+                            panic!("for strip_suffix() the Pattern must be normalized")
+                        }
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Creates a normalized concatenation of the given patterns.
+    pub fn concatenation(parts: impl IntoIterator<Item = Pattern>) -> Self {
+        let mut pattern = Pattern::Concatenation(parts.into_iter().collect());
+        pattern.normalize();
+        match pattern {
+            Pattern::Concatenation(list) if list.is_empty() => Pattern::Constant(String::new()),
+            pattern => pattern,
+        }
+    }
+
     pub fn filter_could_match(&self, value: &str) -> Option<Pattern> {
         if let Pattern::Alternatives(list) = self {
             let new_list = list
@@ -999,6 +1124,48 @@ mod tests {
         assert!(pat.could_match("dir/inner/file.d.ts.map"));
     }
 
+    #[test]
+    fn strip_prefix_and_suffix() {
+        let pat = Pattern::Concatenation(vec![
+            Pattern::Constant("./locales/".to_string()),
+            Pattern::Dynamic,
+            Pattern::Constant(".json".to_string()),
+        ]);
+        assert_eq!(
+            pat.strip_prefix("./locales/"),
+            Some(Pattern::Concatenation(vec![
+                Pattern::Dynamic,
+                Pattern::Constant(".json".to_string()),
+            ]))
+        );
+        assert_eq!(
+            pat.strip_prefix("./"),
+            Some(Pattern::Concatenation(vec![
+                Pattern::Constant("locales/".to_string()),
+                Pattern::Dynamic,
+                Pattern::Constant(".json".to_string()),
+            ]))
+        );
+        assert_eq!(
+            pat.strip_prefix("./locales/en/"),
+            Some(Pattern::Concatenation(vec![
+                Pattern::Dynamic,
+                Pattern::Constant(".json".to_string()),
+            ]))
+        );
+        assert_eq!(pat.strip_prefix("./lib/"), None);
+        assert_eq!(
+            pat.strip_prefix("./locales/")
+                .and_then(|pat| pat.strip_suffix(".json")),
+            Some(Pattern::Dynamic)
+        );
+        assert_eq!(pat.strip_suffix(".js"), None);
+        assert_eq!(
+            Pattern::Constant("./a.js".to_string()).strip_prefix("./a.js"),
+            Some(Pattern::Constant("".to_string()))
+        );
+    }
+
     #[rstest]
     #[case::dynamic(Pattern::Dynamic, "feijf", None)]
     #[case::dynamic_concat(
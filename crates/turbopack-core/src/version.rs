@@ -8,7 +8,10 @@ use turbo_tasks::{
 use turbo_tasks_fs::{FileContent, FileContentReadRef, LinkType};
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
-use crate::asset::{AssetContent, AssetContentReadRef, AssetContentVc};
+use crate::{
+    asset::{AssetContent, AssetContentReadRef, AssetContentVc},
+    chunk::{ChunkModulesVc, ModuleIdsVc},
+};
 
 /// The content of an [Asset] alongside its version.
 #[turbo_tasks::value_trait]
@@ -115,6 +118,20 @@ pub trait Version {
     /// to convert an id back to its original `Version`, so the original object
     /// needs to be stored somewhere.
     fn id(&self) -> StringVc;
+
+    /// The ids of the modules contained in this version of the object, e. g.
+    /// the modules of a chunk. They allow clients to dispose of the modules
+    /// when the object is removed.
+    fn module_ids(&self) -> ModuleIdsVc {
+        ModuleIdsVc::cell(Vec::new())
+    }
+
+    /// The chunks of the chunk group this version of the object was loaded
+    /// in, as they are in the current chunk graph. When the object is
+    /// removed, they tell clients where its modules have moved to.
+    fn chunk_group_modules(&self) -> ChunkModulesVc {
+        ChunkModulesVc::cell(Vec::new())
+    }
 }
 
 #[turbo_tasks::value]
@@ -148,6 +165,10 @@ pub enum Update {
     /// specific set of instructions.
     Partial(PartialUpdate),
 
+    /// The object was removed. Clients can dispose of it instead of
+    /// restarting.
+    Removed(RemovedUpdate),
+
     /// No update required.
     None,
 }
@@ -169,6 +190,18 @@ pub struct PartialUpdate {
     pub instruction: JsonValueVc,
 }
 
+/// An update that removes a versioned object.
+#[derive(PartialEq, Eq, Debug, Clone, TraceRawVcs, ValueDebugFormat, Serialize, Deserialize)]
+pub struct RemovedUpdate {
+    /// The version this update will bring the object to.
+    pub to: VersionVc,
+    /// The ids of the modules which were contained in the removed object.
+    pub modules: ModuleIdsVc,
+    /// The chunks of the chunk group the removed object was loaded in, with
+    /// the modules they contain now.
+    pub chunk_group: ChunkModulesVc,
+}
+
 /// [`Version`] implementation that hashes a file at a given path and returns
 /// the hex encoded hash as a version identifier.
 #[turbo_tasks::value]
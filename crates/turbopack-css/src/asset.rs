@@ -82,6 +82,11 @@ impl Asset for CssModuleAsset {
         self.source.path()
     }
 
+    #[turbo_tasks::function]
+    fn ident(&self) -> StringVc {
+        self.source.ident()
+    }
+
     #[turbo_tasks::function]
     fn content(&self) -> AssetContentVc {
         self.source.content()
@@ -145,7 +150,7 @@ impl ValueToString for ModuleChunkItem {
     async fn to_string(&self) -> Result<StringVc> {
         Ok(StringVc::cell(format!(
             "{} (css)",
-            self.module.await?.source.path().to_string().await?
+            self.module.await?.source.ident().await?
         )))
     }
 }
@@ -115,25 +115,15 @@ impl CssChunkContentVc {
     async fn code(self) -> Result<CodeVc> {
         let this = self.await?;
         let chunk_name = this.chunk_path.to_string();
-
-        let mut body = CodeBuilder::default();
-        let mut external_imports = IndexSet::new();
-        for entry in this.main_entries.await?.iter() {
-            let entry_placeable = CssChunkPlaceableVc::cast_from(entry);
-            let entry_item = entry_placeable.as_chunk_item(this.context);
-
-            for external_import in expand_imports(&mut body, entry_item).await? {
-                external_imports.insert(external_import.await?.to_owned());
-            }
-        }
+        let body = css_chunk_body(this.main_entries, this.context).await?;
 
         let mut code = CodeBuilder::default();
         writeln!(code, "/* chunk {} */", chunk_name.await?)?;
-        for external_import in external_imports {
-            writeln!(code, "@import {};", stringify_str(&external_import))?;
+        for external_import in &body.external_imports {
+            writeln!(code, "@import {};", stringify_str(external_import))?;
         }
 
-        code.push_code(&body.build());
+        code.push_code(&*body.code.await?);
 
         if code.has_source_map() {
             let chunk_path = this.chunk_path.await?;
@@ -155,6 +145,37 @@ impl CssChunkContentVc {
     }
 }
 
+/// The concatenated code of all chunk items of a chunk, independent of the
+/// path of the chunk.
+#[turbo_tasks::value(serialization = "none")]
+struct CssChunkBody {
+    code: CodeVc,
+    external_imports: Vec<String>,
+}
+
+#[turbo_tasks::function]
+async fn css_chunk_body(
+    main_entries: CssChunkPlaceablesVc,
+    context: ChunkingContextVc,
+) -> Result<CssChunkBodyVc> {
+    let mut body = CodeBuilder::default();
+    let mut external_imports = IndexSet::new();
+    for entry in main_entries.await?.iter() {
+        let entry_placeable = CssChunkPlaceableVc::cast_from(entry);
+        let entry_item = entry_placeable.as_chunk_item(context);
+
+        for external_import in expand_imports(&mut body, entry_item).await? {
+            external_imports.insert(external_import.await?.clone_value());
+        }
+    }
+
+    Ok(CssChunkBody {
+        code: body.build().cell(),
+        external_imports: external_imports.into_iter().collect(),
+    }
+    .cell())
+}
+
 #[turbo_tasks::value_impl]
 impl GenerateSourceMap for CssChunkContent {
     #[turbo_tasks::function]
@@ -280,9 +301,27 @@ impl Asset for CssChunk {
         let main_entry = main_entries
             .next()
             .ok_or_else(|| anyhow!("Chunk must have at least one entry"))?;
+        // Entries resolved from requests with a query share the path of the file,
+        // so the ident has to be part of the hashed info.
+        let ident = main_entry.ident().await?;
+        if *ident != *main_entry.path().to_string().await? {
+            hasher.write_value(ident);
+            needs_hash = true;
+        }
         for entry in main_entries {
-            let path = entry.path().to_string().await?;
-            hasher.write_value(path);
+            let ident = entry.ident().await?;
+            hasher.write_value(ident);
+            needs_hash = true;
+        }
+
+        // With content hashing the code of the chunk contributes to the hash, so the
+        // path changes whenever the content of the chunk changes.
+        if *this.context.is_content_hashing_enabled().await? {
+            let body = css_chunk_body(this.main_entries, this.context).await?;
+            for external_import in &body.external_imports {
+                hasher.write_ref(external_import);
+            }
+            hasher.write_ref(body.code.await?.source_code());
             needs_hash = true;
         }
 
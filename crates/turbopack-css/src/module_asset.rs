@@ -56,6 +56,11 @@ impl Asset for ModuleCssModuleAsset {
         self.inner.path()
     }
 
+    #[turbo_tasks::function]
+    fn ident(&self) -> StringVc {
+        self.inner.ident()
+    }
+
     #[turbo_tasks::function]
     fn content(&self) -> AssetContentVc {
         self.inner.content()
@@ -118,7 +123,7 @@ impl ValueToString for ModuleChunkItem {
     async fn to_string(&self) -> Result<StringVc> {
         Ok(StringVc::cell(format!(
             "{} (css module)",
-            self.module.await?.source.path().to_string().await?
+            self.module.await?.source.ident().await?
         )))
     }
 }
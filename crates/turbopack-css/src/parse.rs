@@ -109,6 +109,8 @@ impl SourceMapGenConfig for InlineSourcesContentConfig {
     }
 }
 
+// Not cached on disk: the result holds the stylesheet and source map of this
+// process, which can't be serialized, and the source is read through an asset.
 #[turbo_tasks::function]
 pub async fn parse(
     source: AssetVc,
@@ -242,15 +242,18 @@ impl CodeGenerateable for ImportAssetReference {
     ) -> Result<CodeGenerationVc> {
         let this = &*self_vc.await?;
         let mut imports = vec![];
-        if let Request::Uri {
-            protocol,
-            remainder,
-        } = &*this.request.await?
-        {
-            imports.push(CssImport::External(StringVc::cell(format!(
+        match &*this.request.await? {
+            Request::Uri {
+                protocol,
+                remainder,
+            } => imports.push(CssImport::External(StringVc::cell(format!(
                 "{}{}",
                 protocol, remainder
-            ))))
+            )))),
+            request @ Request::DataUri { .. } => imports.push(CssImport::External(StringVc::cell(
+                request.request().unwrap_or_default(),
+            ))),
+            _ => {}
         }
 
         Ok(CodeGeneration {
@@ -14,7 +14,12 @@ use turbopack_core::{
     asset::AssetVc,
     reference::{AssetReferenceVc, AssetReferencesVc},
     reference_type::{CssReferenceSubType, ReferenceType},
-    resolve::{handle_resolve_error, origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+    resolve::{
+        handle_resolve_error,
+        origin::ResolveOriginVc,
+        parse::{Request, RequestVc},
+        ResolveResult, ResolveResultVc, SpecialType,
+    },
 };
 use turbopack_swc_utils::emitter::IssueEmitter;
 
@@ -152,6 +157,9 @@ pub async fn css_resolve(
     request: RequestVc,
     ty: Value<CssReferenceSubType>,
 ) -> Result<ResolveResultVc> {
+    if let Some(result) = data_uri_external(request).await? {
+        return Ok(result);
+    }
     let ty = Value::new(ReferenceType::Css(ty.into_value()));
     let options = origin.resolve_options(ty.clone());
     let result = origin.resolve_asset(request, options, ty.clone());
@@ -162,3 +170,19 @@ pub async fn css_resolve(
 // TODO enable serialization
 #[turbo_tasks::value(transparent, serialization = "none")]
 pub struct AstPath(#[turbo_tasks(trace_ignore)] Vec<AstParentKind>);
+
+/// `data:` URIs stay in the stylesheet like other URLs instead of being
+/// resolved to an asset, as they are already inlined.
+pub(crate) async fn data_uri_external(request: RequestVc) -> Result<Option<ResolveResultVc>> {
+    let request = request.await?;
+    if !matches!(&*request, Request::DataUri { .. }) {
+        return Ok(None);
+    }
+    Ok(Some(
+        ResolveResult::Special(
+            SpecialType::OriginalReferenceTypeExternal(request.request().unwrap_or_default()),
+            Vec::new(),
+        )
+        .into(),
+    ))
+}
@@ -17,7 +17,7 @@ use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
     embed::CssEmbeddableVc,
-    references::AstPathVc,
+    references::{data_uri_external, AstPathVc},
 };
 
 #[turbo_tasks::value(into = "new")]
@@ -63,12 +63,15 @@ impl UrlAssetReferenceVc {
 #[turbo_tasks::value_impl]
 impl AssetReference for UrlAssetReference {
     #[turbo_tasks::function]
-    fn resolve_reference(&self) -> ResolveResultVc {
-        url_resolve(
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        if let Some(result) = data_uri_external(self.request).await? {
+            return Ok(result);
+        }
+        Ok(url_resolve(
             self.origin,
             self.request,
             Value::new(UrlReferenceSubType::CssUrl),
-        )
+        ))
     }
 }
 
@@ -5,11 +5,13 @@
 pub mod html;
 pub mod introspect;
 pub mod source;
+pub mod tls;
 pub mod update;
 
 use std::{
     borrow::Cow,
     collections::{btree_map::Entry, BTreeMap},
+    fmt::{self, Debug},
     future::Future,
     net::{SocketAddr, TcpListener},
     pin::Pin,
@@ -24,14 +26,16 @@ use anyhow::{bail, Context, Result};
 use futures::{StreamExt, TryStreamExt};
 use hyper::{
     header::HeaderName,
-    server::{conn::AddrIncoming, Builder},
+    server::conn::AddrIncoming,
     service::{make_service_fn, service_fn},
     Request, Response, Server,
 };
 use mime_guess::mime;
 use source::{Body, Bytes};
+use tokio_rustls::TlsAcceptor;
 use turbo_tasks::{
-    run_once, trace::TraceRawVcs, util::FormatDuration, RawVc, TransientValue, TurboTasksApi, Value,
+    run_once, trace::TraceRawVcs, turbo_tasks, util::FormatDuration, RawVc, TransientValue,
+    TurboTasksApi, Value,
 };
 use turbo_tasks_fs::{FileContent, FileContentReadRef};
 use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc};
@@ -39,9 +43,13 @@ use turbopack_core::asset::AssetContent;
 
 use self::{
     source::{
-        query::Query, ContentSourceContent, ContentSourceDataVary, ContentSourceResult,
-        ContentSourceResultVc, ContentSourceVc, ProxyResultReadRef,
+        proxy::{is_proxy, proxy_request, ProxyRequestReadRef},
+        query::Query,
+        stream::{BodyError, StreamedResponseReadRef},
+        ContentSourceContent, ContentSourceDataVary, ContentSourceResult, ContentSourceResultVc,
+        ContentSourceVc, ProxyResultReadRef,
     },
+    tls::{TlsCertificate, TlsIncoming},
     update::{protocol::ResourceIdentifier, UpdateServer},
 };
 use crate::source::{ContentSourceData, HeaderValue};
@@ -64,12 +72,23 @@ where
     }
 }
 
-#[derive(TraceRawVcs, Debug)]
+#[derive(TraceRawVcs)]
 pub struct DevServerBuilder {
     #[turbo_tasks(trace_ignore)]
     pub addr: SocketAddr,
     #[turbo_tasks(trace_ignore)]
-    server: Builder<AddrIncoming>,
+    incoming: AddrIncoming,
+    #[turbo_tasks(trace_ignore)]
+    tls: Option<TlsAcceptor>,
+}
+
+impl Debug for DevServerBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DevServerBuilder")
+            .field("addr", &self.addr)
+            .field("tls", &self.tls.is_some())
+            .finish()
+    }
 }
 
 #[derive(TraceRawVcs)]
@@ -97,10 +116,33 @@ async fn handle_issues<T: Into<RawVc>>(
     Ok(())
 }
 
+/// Reports the issues of an error that ended a streamed body early. The
+/// response has already been sent at that point, so issues can't change it.
+async fn report_body_error(
+    tt: Arc<dyn TurboTasksApi>,
+    error: &anyhow::Error,
+    path: &str,
+    console_ui: ConsoleUiVc,
+) {
+    println!("[500] error while streaming /{path}: {error}");
+    if let Some(issues) = error
+        .downcast_ref::<BodyError>()
+        .and_then(BodyError::issues)
+    {
+        let path = path.to_string();
+        let _ = run_once(tt, async move {
+            handle_issues(issues, &path, "stream response body", console_ui).await
+        })
+        .await;
+    }
+}
+
 #[turbo_tasks::value(serialization = "none")]
 enum GetFromSourceResult {
     Static(FileContentReadRef),
     HttpProxy(ProxyResultReadRef),
+    Stream(StreamedResponseReadRef),
+    Proxy(ProxyRequestReadRef),
     NeedData {
         source: ContentSourceVc,
         path: String,
@@ -115,6 +157,7 @@ async fn get_from_source(
     path: &str,
     data: Value<ContentSourceData>,
     vary: Value<ContentSourceDataVary>,
+    upgrade: bool,
 ) -> Result<GetFromSourceResultVc> {
     let result = source.get(path, data.clone()).await?;
     Ok(match &*result {
@@ -125,6 +168,11 @@ async fn get_from_source(
             vary: data.vary.clone(),
         },
         ContentSourceResult::Result { get_content, .. } => {
+            if upgrade && !is_proxy(*get_content).await? {
+                // Other content can't handle upgrade requests, so it isn't
+                // computed at all
+                return Ok(GetFromSourceResult::NotFound.cell());
+            }
             let content_vary = get_content.vary().await?;
             if *vary != *content_vary {
                 GetFromSourceResult::NeedData {
@@ -146,6 +194,15 @@ async fn get_from_source(
                     ContentSourceContent::HttpProxy(proxy) => {
                         GetFromSourceResult::HttpProxy(proxy.await?)
                     }
+                    ContentSourceContent::Stream(response) => {
+                        GetFromSourceResult::Stream(response.await?)
+                    }
+                    ContentSourceContent::Proxy(proxy) => GetFromSourceResult::Proxy(proxy.await?),
+                    ContentSourceContent::NeedData(data) => GetFromSourceResult::NeedData {
+                        source: data.source.resolve().await?,
+                        path: data.path.clone(),
+                        vary: data.vary.clone(),
+                    },
                 }
             }
         }
@@ -160,6 +217,7 @@ async fn process_request_with_content_source(
     mut request: Request<hyper::Body>,
     console_ui: ConsoleUiVc,
 ) -> Result<Response<hyper::Body>> {
+    let upgrade = hyper_tungstenite::is_upgrade_request(&request);
     let mut data = ContentSourceData::default();
     let mut data_vary = ContentSourceDataVary::default();
     loop {
@@ -168,6 +226,7 @@ async fn process_request_with_content_source(
             &asset_path,
             Value::new(data),
             Value::new(data_vary),
+            upgrade,
         );
         handle_issues(
             content_source_result,
@@ -221,6 +280,35 @@ async fn process_request_with_content_source(
 
                 return Ok(response.body(hyper::Body::wrap_stream(proxy_result.body.read()))?);
             }
+            GetFromSourceResult::Stream(streamed_response) => {
+                let mut response = Response::builder().status(streamed_response.status);
+                let headers = response.headers_mut().expect("headers must be defined");
+
+                for [name, value] in streamed_response.headers.array_chunks() {
+                    headers.append(
+                        HeaderName::from_bytes(name.as_bytes())?,
+                        hyper::header::HeaderValue::from_str(value)?,
+                    );
+                }
+
+                // Chunks are sent to the client as soon as they are produced
+                let tt = turbo_tasks();
+                let path = path.to_string();
+                let body = streamed_response.body.read().then(move |chunk| {
+                    let tt = tt.clone();
+                    let path = path.clone();
+                    async move {
+                        if let Err(err) = &chunk {
+                            report_body_error(tt, err, &path, console_ui).await;
+                        }
+                        chunk
+                    }
+                });
+                return Ok(response.body(hyper::Body::wrap_stream(body))?);
+            }
+            GetFromSourceResult::Proxy(proxy) => {
+                return proxy_request(request, proxy, &*console_ui.await?).await;
+            }
             GetFromSourceResult::NeedData { source, path, vary } => {
                 resolved_source = *source;
                 asset_path = Cow::Owned(path.to_string());
@@ -247,120 +335,149 @@ impl DevServer {
             .local_addr()
             .context("not able to get bound address")?;
 
-        let server = Server::from_tcp(listener).context("Not able to start server")?;
-        Ok(DevServerBuilder { addr, server })
+        listener
+            .set_nonblocking(true)
+            .context("Not able to start server")?;
+        let incoming = tokio::net::TcpListener::from_std(listener)
+            .map_err(anyhow::Error::from)
+            .and_then(|listener| Ok(AddrIncoming::from_listener(listener)?))
+            .context("Not able to start server")?;
+        Ok(DevServerBuilder {
+            addr,
+            incoming,
+            tls: None,
+        })
     }
 }
 
 impl DevServerBuilder {
+    /// Serves HTTPS with the given certificate instead of plain HTTP. HTTP/2
+    /// is used for clients that support it.
+    pub fn tls(mut self, certificate: &TlsCertificate) -> Result<Self> {
+        self.tls = Some(certificate.acceptor()?);
+        Ok(self)
+    }
+
+    /// Returns true when the server will serve HTTPS.
+    pub fn is_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
     pub fn serve(
         self,
         turbo_tasks: Arc<dyn TurboTasksApi>,
         source_provider: impl SourceProvider + Clone + Send + Sync,
         console_ui: Arc<ConsoleUi>,
     ) -> DevServer {
-        let make_svc = make_service_fn(move |_| {
-            let tt = turbo_tasks.clone();
-            let source_provider = source_provider.clone();
+        let tt = turbo_tasks;
+        let tls_console_ui = console_ui.clone();
+        let handler = move |request: Request<hyper::Body>| {
             let console_ui = console_ui.clone();
-            async move {
-                let handler = move |request: Request<hyper::Body>| {
-                    let console_ui = console_ui.clone();
-                    let start = Instant::now();
-                    let tt = tt.clone();
-                    let source_provider = source_provider.clone();
-                    let future = async move {
-                        if hyper_tungstenite::is_upgrade_request(&request) {
-                            let uri = request.uri();
-                            let path = uri.path();
-
-                            if path == "/turbopack-hmr" {
-                                let (response, websocket) =
-                                    hyper_tungstenite::upgrade(request, None)?;
-                                let update_server = UpdateServer::new(source_provider);
-                                update_server.run(&*tt, websocket);
-                                return Ok(response);
-                            }
+            let start = Instant::now();
+            let tt = tt.clone();
+            let source_provider = source_provider.clone();
+            let future = async move {
+                if hyper_tungstenite::is_upgrade_request(&request) {
+                    let uri = request.uri();
+                    let path = uri.path();
 
-                            println!("[404] {} (WebSocket)", path);
-                            if path == "/_next/webpack-hmr" {
-                                // Special-case requests to webpack-hmr as these are made by Next.js
-                                // clients built without turbopack, which may be making requests in
-                                // development.
-                                println!("A non-turbopack next.js client is trying to connect.");
-                                println!(
-                                    "Make sure to reload/close any browser window which has been \
-                                     opened without --turbo."
-                                );
-                            }
+                    if path == "/turbopack-hmr" {
+                        let (response, websocket) = hyper_tungstenite::upgrade(request, None)?;
+                        let update_server = UpdateServer::new(source_provider);
+                        update_server.run(&*tt, websocket);
+                        return Ok(response);
+                    }
 
-                            return Ok(Response::builder()
-                                .status(404)
-                                .body(hyper::Body::empty())?);
-                        }
+                    if path == "/_next/webpack-hmr" {
+                        println!("[404] {} (WebSocket)", path);
+                        // Special-case requests to webpack-hmr as these are made by Next.js
+                        // clients built without turbopack, which may be making requests in
+                        // development.
+                        println!("A non-turbopack next.js client is trying to connect.");
+                        println!(
+                            "Make sure to reload/close any browser window which has been opened \
+                             without --turbo."
+                        );
+                        return Ok(Response::builder().status(404).body(hyper::Body::empty())?);
+                    }
+                    // Other upgrade requests can only be forwarded by proxies,
+                    // any other content returns a 404 without being computed
+                }
 
-                        run_once(tt, async move {
-                            let console_ui = (*console_ui).clone().cell();
-                            let uri = request.uri();
-                            let path = uri.path();
-                            // Remove leading slash.
-                            let path = &path[1..].to_string();
-                            let asset_path = urlencoding::decode(path)?;
-                            let source = source_provider.get_source();
-                            handle_issues(source, path, "get source", console_ui).await?;
-                            let resolved_source = source.resolve_strongly_consistent().await?;
-                            let response = process_request_with_content_source(
-                                path,
-                                resolved_source,
-                                asset_path,
-                                request,
-                                console_ui,
-                            )
-                            .await?;
-                            let status = response.status().as_u16();
-                            let is_error = response.status().is_client_error()
-                                || response.status().is_server_error();
-                            let elapsed = start.elapsed();
-                            if is_error
-                                || (cfg!(feature = "log_request_stats")
-                                    && elapsed > Duration::from_secs(1))
-                            {
-                                println!(
-                                    "[{status}] /{path} ({duration})",
-                                    duration = FormatDuration(elapsed)
-                                );
-                            }
-                            Ok(response)
-                        })
-                        .await
-                    };
-                    async move {
-                        match future.await {
-                            Ok(r) => Ok::<_, hyper::http::Error>(r),
-                            Err(e) => {
-                                println!(
-                                    "[500] error: {:?} ({})",
-                                    e,
-                                    FormatDuration(start.elapsed())
-                                );
-                                Ok(Response::builder()
-                                    .status(500)
-                                    .body(hyper::Body::from(format!("{:?}", e,)))?)
-                            }
-                        }
+                run_once(tt, async move {
+                    let console_ui = (*console_ui).clone().cell();
+                    let uri = request.uri();
+                    let path = uri.path();
+                    // Remove leading slash.
+                    let path = &path[1..].to_string();
+                    let asset_path = urlencoding::decode(path)?;
+                    let source = source_provider.get_source();
+                    handle_issues(source, path, "get source", console_ui).await?;
+                    let resolved_source = source.resolve_strongly_consistent().await?;
+                    let response = process_request_with_content_source(
+                        path,
+                        resolved_source,
+                        asset_path,
+                        request,
+                        console_ui,
+                    )
+                    .await?;
+                    let status = response.status().as_u16();
+                    let is_error =
+                        response.status().is_client_error() || response.status().is_server_error();
+                    let elapsed = start.elapsed();
+                    if is_error
+                        || (cfg!(feature = "log_request_stats") && elapsed > Duration::from_secs(1))
+                    {
+                        println!(
+                            "[{status}] /{path} ({duration})",
+                            duration = FormatDuration(elapsed)
+                        );
+                    }
+                    Ok(response)
+                })
+                .await
+            };
+            async move {
+                match future.await {
+                    Ok(r) => Ok::<_, hyper::http::Error>(r),
+                    Err(e) => {
+                        println!("[500] error: {:?} ({})", e, FormatDuration(start.elapsed()));
+                        Ok(Response::builder()
+                            .status(500)
+                            .body(hyper::Body::from(format!("{:?}", e,)))?)
                     }
-                };
-                anyhow::Ok(service_fn(handler))
+                }
+            }
+        };
+        // Each connection gets its own clone of the handler
+        let future: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> = match self.tls {
+            Some(acceptor) => {
+                let incoming = TlsIncoming::new(self.incoming, acceptor, tls_console_ui);
+                let server = Server::builder(incoming).serve(make_service_fn(move |_| {
+                    let handler = handler.clone();
+                    async move { anyhow::Ok(service_fn(handler)) }
+                }));
+                Box::pin(async move {
+                    server.await?;
+                    Ok(())
+                })
+            }
+            None => {
+                let server = Server::builder(self.incoming).serve(make_service_fn(move |_| {
+                    let handler = handler.clone();
+                    async move { anyhow::Ok(service_fn(handler)) }
+                }));
+                Box::pin(async move {
+                    server.await?;
+                    Ok(())
+                })
             }
-        });
-        let server = self.server.serve(make_svc);
+        };
 
         DevServer {
             addr: self.addr,
-            future: Box::pin(async move {
-                server.await?;
-                Ok(())
-            }),
+            future,
         }
     }
 }
@@ -2,11 +2,13 @@ pub mod asset_graph;
 pub mod combined;
 pub mod conditional;
 pub mod lazy_instatiated;
+pub mod proxy;
 pub mod query;
 pub mod router;
 pub mod source_maps;
 pub mod specificity;
 pub mod static_assets;
+pub mod stream;
 
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -20,7 +22,9 @@ use turbo_tasks::{trace::TraceRawVcs, Value};
 use turbo_tasks_fs::rope::Rope;
 use turbopack_core::version::VersionedContentVc;
 
-use self::{query::Query, specificity::SpecificityVc};
+use self::{
+    proxy::ProxyRequestVc, query::Query, specificity::SpecificityVc, stream::StreamedResponseVc,
+};
 
 /// The result of proxying a request to another HTTP server.
 #[turbo_tasks::value(shared)]
@@ -98,12 +102,20 @@ pub trait GetContentSourceContent {
 
 #[turbo_tasks::value(shared)]
 #[derive(Debug)]
-// TODO add Dynamic variant in future to allow streaming and server responses
 /// The content of a result that is returned by a content source.
 pub enum ContentSourceContent {
     NotFound,
     Static(VersionedContentVc),
     HttpProxy(ProxyResultVc),
+    /// A response that is sent while its body is produced.
+    Stream(StreamedResponseVc),
+    /// A request that is forwarded to another server as it is, including
+    /// upgrade requests, e. g. for WebSockets.
+    Proxy(ProxyRequestVc),
+    /// The content turned out to need more data, e. g. a cache buster for a
+    /// response that can only be sent once. The dev server gets the content
+    /// again from the held source with that data.
+    NeedData(NeededData),
 }
 
 #[turbo_tasks::value_impl]
@@ -0,0 +1,467 @@
+use anyhow::{Context, Result};
+use hyper::{
+    header::{HOST, UPGRADE},
+    http::request::Builder,
+    Client, HeaderMap, Request, Response, StatusCode,
+};
+use turbo_tasks::{primitives::StringVc, Value};
+use turbopack_cli_utils::issue::ConsoleUi;
+use turbopack_core::{introspect::Introspectable, issue::IssueSeverity};
+
+use super::{
+    ContentSource, ContentSourceContent, ContentSourceContentVc, ContentSourceData,
+    ContentSourceDataVary, ContentSourceDataVaryVc, ContentSourceResultVc, GetContentSourceContent,
+    GetContentSourceContentVc,
+};
+
+/// Headers which only apply to a single connection and must not be
+/// forwarded.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Forwards requests to another HTTP server, e. g. a backend API running
+/// locally. Method, headers and body are forwarded and the response is
+/// streamed back. Upgrade requests like WebSockets are forwarded too.
+///
+/// Requests are forwarded by the dev server outside of tasks, so neither
+/// request nor response bodies are cached.
+///
+/// Paths can be rewritten either by mounting the source in a
+/// [RouterContentSource](super::router::RouterContentSource), which strips the
+/// route from the path, or with `prefix` and `rewrite`.
+#[turbo_tasks::value(shared)]
+pub struct ProxyContentSource {
+    /// The server to forward requests to, e. g. `http://localhost:8080`. A
+    /// path in the URL is prepended to the forwarded path.
+    pub upstream: String,
+    /// Only paths within this prefix are forwarded, e. g. `api` forwards
+    /// `api` and `api/users`, but not `apiary`. Other paths are not found, so
+    /// the source can be combined with other sources.
+    pub prefix: String,
+    /// Replaces `prefix` in the forwarded path. When `None`, the path is
+    /// forwarded unchanged.
+    pub rewrite: Option<String>,
+}
+
+impl ProxyContentSourceVc {
+    pub fn new(upstream: String, prefix: String, rewrite: Option<String>) -> Self {
+        ProxyContentSource {
+            upstream,
+            prefix,
+            rewrite,
+        }
+        .cell()
+    }
+}
+
+impl ProxyContentSource {
+    fn rewrite_path(&self, path: &str) -> Option<String> {
+        let prefix = self.prefix.trim_matches('/');
+        let rest = path.strip_prefix(prefix)?;
+        let rest = match rest.strip_prefix('/') {
+            Some(rest) => rest,
+            // Only whole segments match
+            None if prefix.is_empty() || rest.is_empty() => rest,
+            None => return None,
+        };
+        Some(match &self.rewrite {
+            Some(rewrite) => match (rewrite.trim_matches('/'), rest) {
+                (rewrite, "") => rewrite.to_string(),
+                ("", rest) => rest.to_string(),
+                (rewrite, rest) => format!("{rewrite}/{rest}"),
+            },
+            None => path.to_string(),
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for ProxyContentSource {
+    #[turbo_tasks::function]
+    fn get(&self, path: &str, _data: Value<ContentSourceData>) -> ContentSourceResultVc {
+        let Some(path) = self.rewrite_path(path) else {
+            return ContentSourceResultVc::not_found();
+        };
+        ContentSourceResultVc::exact(
+            ProxyGetContentResult {
+                upstream: self.upstream.clone(),
+                path,
+            }
+            .cell()
+            .into(),
+        )
+    }
+}
+
+#[turbo_tasks::value]
+struct ProxyGetContentResult {
+    upstream: String,
+    /// The rewritten path, without a leading slash
+    path: String,
+}
+
+#[turbo_tasks::value_impl]
+impl GetContentSourceContent for ProxyGetContentResult {
+    #[turbo_tasks::function]
+    fn vary(&self) -> ContentSourceDataVaryVc {
+        // Only the query is needed, the rest of the request is forwarded by
+        // the dev server
+        ContentSourceDataVary {
+            url: true,
+            ..Default::default()
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    fn get(&self, data: Value<ContentSourceData>) -> Result<ContentSourceContentVc> {
+        let url = data.url.as_deref().context("Missing request data")?;
+        Ok(ContentSourceContent::Proxy(
+            ProxyRequest {
+                uri: upstream_uri(&self.upstream, &self.path, url),
+            }
+            .cell(),
+        )
+        .cell())
+    }
+}
+
+/// Returns true when the content is from a [ProxyContentSource]. Only
+/// proxied content can handle upgrade requests.
+pub(crate) async fn is_proxy(get_content: GetContentSourceContentVc) -> Result<bool> {
+    Ok(ProxyGetContentResultVc::resolve_from(get_content)
+        .await?
+        .is_some())
+}
+
+/// A request which the dev server forwards to another server.
+#[turbo_tasks::value(shared)]
+pub struct ProxyRequest {
+    /// The URI to forward the request to.
+    pub uri: String,
+}
+
+/// Forwards a request to the upstream and streams the response back. Upgrade
+/// requests connect the client with the upstream when the upstream switches
+/// protocols. Failures of upgraded connections are logged to the console UI.
+pub(crate) async fn proxy_request(
+    mut request: Request<hyper::Body>,
+    proxy: &ProxyRequest,
+    console_ui: &ConsoleUi,
+) -> Result<Response<hyper::Body>> {
+    let upgrade = request.headers().contains_key(UPGRADE);
+    let body = if upgrade {
+        hyper::Body::empty()
+    } else {
+        std::mem::take(request.body_mut())
+    };
+    let upstream_request =
+        forwarded_request(&proxy.uri, request.method(), request.headers(), upgrade)?.body(body)?;
+    let mut upstream_response = match Client::new().request(upstream_request).await {
+        Ok(response) => response,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .header("content-type", "text/plain; charset=utf-8")
+                .body(format!("Failed to proxy request to {}: {err}", proxy.uri).into())?);
+        }
+    };
+
+    let switching_protocols = upstream_response.status() == StatusCode::SWITCHING_PROTOCOLS;
+    let mut response = Response::builder().status(upstream_response.status());
+    if let Some(headers) = response.headers_mut() {
+        for (name, value) in upstream_response.headers() {
+            if switching_protocols || !HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+                headers.append(name, value.clone());
+            }
+        }
+    }
+    if !upgrade || !switching_protocols {
+        // The body is sent to the client while it's received
+        return Ok(response.body(upstream_response.into_body())?);
+    }
+
+    let client = hyper::upgrade::on(&mut request);
+    let upstream = hyper::upgrade::on(&mut upstream_response);
+    let uri = proxy.uri.clone();
+    let console_ui = console_ui.clone();
+    tokio::spawn(async move {
+        let result = async {
+            let (mut client, mut upstream) = futures::try_join!(client, upstream)?;
+            tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            console_ui.log(
+                IssueSeverity::Warning,
+                "proxy",
+                format_args!("upgraded connection to {uri} failed: {err}"),
+            );
+        }
+    });
+    Ok(response.body(hyper::Body::empty())?)
+}
+
+/// Joins the upstream URL with the rewritten path and the query of the
+/// original request.
+fn upstream_uri(upstream: &str, path: &str, url: &str) -> String {
+    let path = path
+        .split('/')
+        .map(urlencoding::encode)
+        .collect::<Vec<_>>()
+        .join("/");
+    let query = url
+        .split_once('?')
+        .map(|(_, query)| format!("?{query}"))
+        .unwrap_or_default();
+    format!("{}/{path}{query}", upstream.trim_end_matches('/'))
+}
+
+/// Creates a request to the upstream with the headers of the original
+/// request. The `host` header is replaced by the upstream host and passed as
+/// `x-forwarded-host` instead.
+fn forwarded_request(
+    uri: &str,
+    method: &hyper::Method,
+    headers: &HeaderMap,
+    upgrade: bool,
+) -> Result<Builder> {
+    let mut request = Request::builder().method(method).uri(uri);
+    let request_headers = request
+        .headers_mut()
+        .with_context(|| format!("Invalid request to {uri}"))?;
+    for (name, value) in headers {
+        let forward = match name.as_str() {
+            "host" => false,
+            "connection" | "upgrade" => upgrade,
+            name => !HOP_BY_HOP_HEADERS.contains(&name),
+        };
+        if forward {
+            request_headers.append(name, value.clone());
+        }
+    }
+    if let Some(host) = headers.get(HOST) {
+        request_headers.insert("x-forwarded-host", host.clone());
+    }
+    Ok(request)
+}
+
+#[turbo_tasks::function]
+fn introspectable_type() -> StringVc {
+    StringVc::cell("proxy content source".to_string())
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for ProxyContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        introspectable_type()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(self.upstream.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn details(&self) -> StringVc {
+        StringVc::cell(match &self.rewrite {
+            Some(rewrite) => format!("/{}* -> /{}*", self.prefix, rewrite),
+            None => format!("/{}*", self.prefix),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, net::SocketAddr};
+
+    use anyhow::Result;
+    use hyper::{
+        body::to_bytes,
+        header::{CONNECTION, UPGRADE},
+        service::{make_service_fn, service_fn},
+        Body, Client, Request, Response, Server, StatusCode,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use turbopack_cli_utils::issue::{ConsoleUi, LogOptions};
+    use turbopack_core::issue::IssueSeverity;
+
+    use super::{proxy_request, upstream_uri, ProxyContentSource, ProxyRequest};
+
+    fn source(prefix: &str, rewrite: Option<&str>) -> ProxyContentSource {
+        ProxyContentSource {
+            upstream: "http://localhost:8080".to_string(),
+            prefix: prefix.to_string(),
+            rewrite: rewrite.map(|rewrite| rewrite.to_string()),
+        }
+    }
+
+    #[test]
+    fn rewrite_path() {
+        let api = source("api", None);
+        assert_eq!(api.rewrite_path("api").as_deref(), Some("api"));
+        assert_eq!(api.rewrite_path("api/users").as_deref(), Some("api/users"));
+        assert_eq!(api.rewrite_path("apiary"), None);
+        assert_eq!(api.rewrite_path("index"), None);
+
+        let rewritten = source("/api/", Some("v1"));
+        assert_eq!(rewritten.rewrite_path("api").as_deref(), Some("v1"));
+        assert_eq!(
+            rewritten.rewrite_path("api/users").as_deref(),
+            Some("v1/users")
+        );
+        assert_eq!(rewritten.rewrite_path("apiary"), None);
+
+        let stripped = source("api", Some(""));
+        assert_eq!(stripped.rewrite_path("api/users").as_deref(), Some("users"));
+
+        let all = source("", Some("v1"));
+        assert_eq!(all.rewrite_path("users").as_deref(), Some("v1/users"));
+        assert_eq!(all.rewrite_path("").as_deref(), Some("v1"));
+    }
+
+    #[test]
+    fn uri() {
+        assert_eq!(
+            upstream_uri("http://localhost:8080/", "v1/a b", "/api/a%20b?id=1"),
+            "http://localhost:8080/v1/a%20b?id=1"
+        );
+        assert_eq!(
+            upstream_uri("http://localhost:8080/base", "users", "/users"),
+            "http://localhost:8080/base/users"
+        );
+    }
+
+    /// Serves requests on a free port.
+    fn serve<F, R>(handler: F) -> SocketAddr
+    where
+        F: Fn(Request<Body>) -> R + Clone + Send + Sync + 'static,
+        R: Future<Output = Result<Response<Body>>> + Send + 'static,
+    {
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service_fn(move |_| {
+            let handler = handler.clone();
+            async move { anyhow::Ok(service_fn(handler)) }
+        }));
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Responds with the method, URI, forwarded host and body of a request,
+    /// or echoes the upgraded connection.
+    async fn upstream(mut request: Request<Body>) -> Result<Response<Body>> {
+        if request.headers().contains_key(UPGRADE) {
+            let upgraded = hyper::upgrade::on(&mut request);
+            tokio::spawn(async move {
+                let mut upgraded = upgraded.await.unwrap();
+                let mut message = [0; 4];
+                upgraded.read_exact(&mut message).await.unwrap();
+                upgraded.write_all(&message).await.unwrap();
+            });
+            return Ok(Response::builder()
+                .status(StatusCode::SWITCHING_PROTOCOLS)
+                .header(CONNECTION, "upgrade")
+                .header(UPGRADE, "echo")
+                .body(Body::empty())?);
+        }
+        let forwarded_host = request
+            .headers()
+            .get("x-forwarded-host")
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = to_bytes(request.body_mut()).await?;
+        Ok(Response::new(
+            format!(
+                "{} {} {} {}",
+                request.method(),
+                request.uri(),
+                forwarded_host,
+                String::from_utf8_lossy(&body)
+            )
+            .into(),
+        ))
+    }
+
+    fn proxy(uri: String) -> SocketAddr {
+        let console_ui = ConsoleUi::new(LogOptions {
+            current_dir: std::env::current_dir().unwrap(),
+            show_all: false,
+            log_detail: false,
+            log_level: IssueSeverity::Error,
+        });
+        serve(move |request| {
+            let proxy = ProxyRequest { uri: uri.clone() };
+            let console_ui = console_ui.clone();
+            async move { proxy_request(request, &proxy, &console_ui).await }
+        })
+    }
+
+    #[tokio::test]
+    async fn forward() {
+        let upstream = serve(upstream);
+        let proxy = proxy(format!("http://{upstream}/v1/users?id=1"));
+
+        let request = Request::post(format!("http://{proxy}/api/users?id=1"))
+            .body(Body::from("body"))
+            .unwrap();
+        let response = Client::new().request(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, format!("POST /v1/users?id=1 {proxy} body"));
+    }
+
+    #[tokio::test]
+    async fn bad_gateway() {
+        // Nothing listens on the port after the listener is dropped
+        let unused = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let proxy = proxy(format!("http://{unused}/"));
+
+        let response = Client::new()
+            .get(format!("http://{proxy}/").parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn upgrade() {
+        let upstream = serve(upstream);
+        let proxy = proxy(format!("http://{upstream}/socket"));
+
+        let mut stream = TcpStream::connect(proxy).await.unwrap();
+        stream
+            .write_all(
+                b"GET /socket HTTP/1.1\r\nHost: localhost\r\nConnection: upgrade\r\nUpgrade: \
+                  echo\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.unwrap());
+        }
+        assert!(head.starts_with(b"HTTP/1.1 101"));
+
+        stream.write_all(b"ping").await.unwrap();
+        let mut message = [0; 4];
+        stream.read_exact(&mut message).await.unwrap();
+        assert_eq!(&message, b"ping");
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::{self, Display},
+    pin::Pin,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use futures::{stream, Stream, StreamExt};
+use hyper::body::Bytes;
+use parking_lot::Mutex;
+use turbo_tasks::RawVc;
+
+/// A response whose body is sent to the client while it's still produced,
+/// e. g. by a streaming server side renderer.
+#[turbo_tasks::value(shared, serialization = "none", eq = "manual", cell = "new")]
+pub struct StreamedResponse {
+    /// The HTTP status code to return.
+    pub status: u16,
+    /// Headers arranged as contiguous (name, value) pairs.
+    pub headers: Vec<String>,
+    /// The body to return.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub body: BodyStream,
+}
+
+type BodyChunks = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send>>;
+
+/// A body that is produced while it's read. The producer only runs as fast as
+/// the body is read and chunks are not kept after they have been read, so
+/// long or endless bodies don't accumulate in memory. A body is dropped
+/// together with the response when the client goes away.
+///
+/// Therefore a body can only be read once. Content that returns a body must
+/// not be cached, e. g. by varying on
+/// [super::ContentSourceDataVary::cache_buster].
+#[derive(Clone)]
+pub struct BodyStream {
+    chunks: Arc<Mutex<Option<BodyChunks>>>,
+}
+
+impl BodyStream {
+    /// Creates a body that is produced by the stream of chunks.
+    pub fn new(chunks: impl Stream<Item = Result<Bytes>> + Send + 'static) -> Self {
+        Self {
+            chunks: Arc::new(Mutex::new(Some(Box::pin(chunks)))),
+        }
+    }
+
+    /// Creates a body that is already complete.
+    pub fn from_bytes(bytes: impl Into<Bytes>) -> Self {
+        Self::new(stream::once(futures::future::ready(Ok(bytes.into()))))
+    }
+
+    /// Takes the chunks of the body. Yields an error if the body has already
+    /// been read.
+    pub fn read(&self) -> impl Stream<Item = Result<Bytes>> + Send + 'static {
+        match self.chunks.lock().take() {
+            Some(chunks) => chunks.left_stream(),
+            None => stream::once(futures::future::ready(Err(anyhow!(
+                "the body has already been read"
+            ))))
+            .right_stream(),
+        }
+    }
+}
+
+impl PartialEq for BodyStream {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.chunks, &other.chunks)
+    }
+}
+
+impl Eq for BodyStream {}
+
+/// The error that ended a body early. Since the response has already been
+/// sent at that point, issues describing the error are emitted by a separate
+/// task, which the dev server reports.
+#[derive(Debug)]
+pub struct BodyError {
+    message: String,
+    issues: Option<RawVc>,
+}
+
+impl BodyError {
+    pub fn new(message: String) -> Self {
+        Self {
+            message,
+            issues: None,
+        }
+    }
+
+    /// Attaches the output of a task that emitted issues about the error.
+    pub fn with_issues(mut self, issues: RawVc) -> Self {
+        self.issues = Some(issues);
+        self
+    }
+
+    pub fn issues(&self) -> Option<RawVc> {
+        self.issues
+    }
+}
+
+impl Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for BodyError {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use anyhow::{anyhow, Result};
+    use futures::{executor::block_on, stream, StreamExt};
+    use hyper::body::Bytes;
+
+    use super::BodyStream;
+
+    fn read_all(body: &BodyStream) -> Vec<Result<Bytes>> {
+        block_on(body.read().collect())
+    }
+
+    #[test]
+    fn chunk_order() {
+        let body = BodyStream::new(stream::iter(
+            ["a", "b", "c"].map(|chunk| Ok(Bytes::from(chunk))),
+        ));
+        let chunks = read_all(&body)
+            .into_iter()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn backpressure() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let body = BodyStream::new(stream::repeat_with(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Bytes::from("chunk"))
+        }));
+        // An endless body is only produced as far as it's read
+        let chunks = block_on(body.read().take(3).collect::<Vec<_>>());
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(produced.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn error() {
+        let body = BodyStream::new(stream::iter(vec![
+            Ok(Bytes::from("a")),
+            Err(anyhow!("failed")),
+        ]));
+        let chunks = read_all(&body);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_ref().unwrap(), "a");
+        assert_eq!(chunks[1].as_ref().unwrap_err().to_string(), "failed");
+    }
+
+    #[test]
+    fn read_once() {
+        let body = BodyStream::from_bytes("a");
+        assert_eq!(read_all(&body.clone()).len(), 1);
+        let chunks = read_all(&body);
+        assert!(chunks[0].is_err());
+    }
+}
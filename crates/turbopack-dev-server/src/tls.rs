@@ -0,0 +1,391 @@
+use std::{
+    fs::File,
+    io::{BufReader, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::Duration as StdDuration,
+};
+
+use anyhow::{bail, Context, Result};
+use hyper::server::{
+    accept::Accept,
+    conn::{AddrIncoming, AddrStream},
+};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose, SanType,
+};
+use time::{Duration, OffsetDateTime};
+use tokio::sync::mpsc;
+use tokio_rustls::{
+    rustls::{self, PrivateKey, ServerConfig},
+    server::TlsStream,
+    TlsAcceptor,
+};
+use turbo_tasks_hash::hash_xxh3_hash64;
+use turbopack_cli_utils::issue::ConsoleUi;
+use turbopack_core::issue::IssueSeverity;
+
+/// Validity of generated certificates. Browsers reject server certificates
+/// that are valid for more than 825 days.
+const VALIDITY_DAYS: i64 = 825;
+
+const CA_NAME: &str = "Turbopack Development CA";
+
+/// Clients that don't complete the TLS handshake within this time are
+/// disconnected.
+const HANDSHAKE_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+/// A certificate and private key in PEM format, which are used to serve the
+/// dev server over HTTPS.
+#[derive(Clone, Debug)]
+pub struct TlsCertificate {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl TlsCertificate {
+    pub fn new(cert: PathBuf, key: PathBuf) -> Self {
+        Self { cert, key }
+    }
+
+    /// Returns a certificate for `localhost` and the given hosts, which is
+    /// signed by a local certificate authority. Both are generated on first
+    /// use and cached in `dir`, so the certificate authority only needs to be
+    /// trusted once to avoid browser warnings.
+    pub fn self_signed(dir: &Path, hosts: &[String]) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating certificate directory {}", dir.display()))?;
+
+        let ca_cert = dir.join("ca.pem");
+        let ca_key = dir.join("ca-key.pem");
+        let ca = if ca_cert.exists() && ca_key.exists() {
+            // Only the name and the key of the certificate authority are
+            // needed to sign certificates
+            let key = std::fs::read_to_string(&ca_key)
+                .with_context(|| format!("reading private key {}", ca_key.display()))?;
+            let key = KeyPair::from_pem(&key)
+                .with_context(|| format!("invalid private key {}", ca_key.display()))?;
+            Certificate::from_params(ca_params(Some(key)))?
+        } else {
+            let ca = Certificate::from_params(ca_params(None))?;
+            std::fs::write(&ca_cert, ca.serialize_pem()?)?;
+            write_private_key(&ca_key, &ca.serialize_private_key_pem())?;
+            println!(
+                "Generated a local certificate authority for HTTPS. Add {} to the trusted \
+                 certificates of your system or browser to avoid security warnings.",
+                ca_cert.display()
+            );
+            ca
+        };
+
+        let mut alt_names = vec![
+            SanType::DnsName("localhost".to_string()),
+            SanType::IpAddress(Ipv4Addr::LOCALHOST.into()),
+            SanType::IpAddress(Ipv6Addr::LOCALHOST.into()),
+        ];
+        for host in hosts {
+            let alt_name = match host.parse::<IpAddr>() {
+                Ok(ip) if ip.is_unspecified() => continue,
+                Ok(ip) => SanType::IpAddress(ip),
+                Err(_) => SanType::DnsName(host.clone()),
+            };
+            if !alt_names.contains(&alt_name) {
+                alt_names.push(alt_name);
+            }
+        }
+
+        // Certificates are cached per set of hosts, so changing the hostname
+        // generates a new certificate
+        let name = format!(
+            "localhost-{:016x}",
+            hash_xxh3_hash64(format!("{alt_names:?}").as_bytes())
+        );
+        let certificate = Self::new(
+            dir.join(format!("{name}.pem")),
+            dir.join(format!("{name}-key.pem")),
+        );
+        if certificate.cert.exists() && certificate.key.exists() {
+            return Ok(certificate);
+        }
+
+        let mut params = CertificateParams::default();
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "localhost");
+        params.subject_alt_names = alt_names;
+        params.is_ca = IsCa::ExplicitNoCa;
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        set_validity(&mut params);
+        let cert = Certificate::from_params(params)?;
+        std::fs::write(&certificate.cert, cert.serialize_pem_with_signer(&ca)?)?;
+        write_private_key(&certificate.key, &cert.serialize_private_key_pem())?;
+        Ok(certificate)
+    }
+
+    /// Creates the TLS configuration. HTTP/2 is preferred, HTTP/1.1 is still
+    /// offered for clients that don't support it and for WebSockets.
+    pub(crate) fn acceptor(&self) -> Result<TlsAcceptor> {
+        let certs = rustls_pemfile::certs(&mut BufReader::new(
+            File::open(&self.cert)
+                .with_context(|| format!("opening certificate {}", self.cert.display()))?,
+        ))
+        .context("reading certificate")?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+        let key = read_private_key(&self.key)?;
+
+        let mut config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("invalid certificate or private key")?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+fn read_private_key(path: &Path) -> Result<PrivateKey> {
+    let file =
+        File::open(path).with_context(|| format!("opening private key {}", path.display()))?;
+    for item in rustls_pemfile::read_all(&mut BufReader::new(file))? {
+        match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => {}
+        }
+    }
+    bail!("no private key found in {}", path.display())
+}
+
+/// Parameters of the local certificate authority. A new key pair is
+/// generated unless an existing one is passed.
+fn ca_params(key_pair: Option<KeyPair>) -> CertificateParams {
+    let mut params = CertificateParams::default();
+    if let Some(alg) = key_pair
+        .as_ref()
+        .and_then(|key_pair| key_pair.compatible_algs().next())
+    {
+        params.alg = alg;
+    }
+    params.key_pair = key_pair;
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, CA_NAME);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    set_validity(&mut params);
+    params
+}
+
+fn set_validity(params: &mut CertificateParams) {
+    let now = OffsetDateTime::now_utc();
+    params.not_before = now;
+    params.not_after = now + Duration::days(VALIDITY_DAYS);
+}
+
+/// Writes a private key that only the current user can read.
+fn write_private_key(path: &Path, key: &str) -> Result<()> {
+    let mut file =
+        File::create(path).with_context(|| format!("creating private key {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+
+        // Restricted before the key is written
+        file.set_permissions(Permissions::from_mode(0o600))?;
+    }
+    file.write_all(key.as_bytes())?;
+    Ok(())
+}
+
+/// Accepts TLS connections. Handshakes run concurrently, so a slow client
+/// doesn't block other connections. Failed connections are logged to the
+/// console UI.
+pub(crate) struct TlsIncoming {
+    connections: mpsc::Receiver<TlsStream<AddrStream>>,
+}
+
+impl TlsIncoming {
+    pub(crate) fn new(
+        mut incoming: AddrIncoming,
+        acceptor: TlsAcceptor,
+        console_ui: Arc<ConsoleUi>,
+    ) -> Self {
+        let (sender, connections) = mpsc::channel(32);
+        tokio::spawn(async move {
+            loop {
+                let stream =
+                    match futures::future::poll_fn(|cx| Pin::new(&mut incoming).poll_accept(cx))
+                        .await
+                    {
+                        Some(Ok(stream)) => stream,
+                        Some(Err(err)) => {
+                            console_ui.log(
+                                IssueSeverity::Error,
+                                "TLS",
+                                format_args!("failed to accept connection: {err}"),
+                            );
+                            continue;
+                        }
+                        None => return,
+                    };
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+                let console_ui = console_ui.clone();
+                tokio::spawn(async move {
+                    let addr = stream.remote_addr();
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = sender.send(stream).await;
+                        }
+                        // Usually a client that doesn't trust the certificate
+                        Ok(Err(err)) => console_ui.log(
+                            IssueSeverity::Warning,
+                            "TLS",
+                            format_args!("handshake with {addr} failed: {err}"),
+                        ),
+                        Err(_) => console_ui.log(
+                            IssueSeverity::Warning,
+                            "TLS",
+                            format_args!("handshake with {addr} timed out"),
+                        ),
+                    }
+                });
+            }
+        });
+        Self { connections }
+    }
+}
+
+impl Accept for TlsIncoming {
+    type Conn = TlsStream<AddrStream>;
+    type Error = std::io::Error;
+
+    fn poll_accept(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        self.connections.poll_recv(cx).map(|stream| stream.map(Ok))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufReader, path::Path, pin::Pin, sync::Arc};
+
+    use futures::future::poll_fn;
+    use hyper::server::{accept::Accept, conn::AddrIncoming};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tokio_rustls::{
+        rustls::{self, ClientConfig, RootCertStore, ServerName},
+        TlsConnector,
+    };
+    use turbopack_cli_utils::issue::{ConsoleUi, LogOptions};
+    use turbopack_core::issue::IssueSeverity;
+
+    use super::{TlsCertificate, TlsIncoming};
+
+    fn connector(ca: &Path) -> TlsConnector {
+        let mut roots = RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(ca).unwrap())).unwrap() {
+            roots.add(&rustls::Certificate(cert)).unwrap();
+        }
+        let mut config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec()];
+        TlsConnector::from(Arc::new(config))
+    }
+
+    #[test]
+    fn self_signed() {
+        let dir = tempfile::tempdir().unwrap();
+        let hosts = ["0.0.0.0".to_string(), "dev.test".to_string()];
+        let certificate = TlsCertificate::self_signed(dir.path(), &hosts).unwrap();
+        certificate.acceptor().unwrap();
+
+        // The certificate is cached
+        let cert = std::fs::read(&certificate.cert).unwrap();
+        let cached = TlsCertificate::self_signed(dir.path(), &hosts).unwrap();
+        assert_eq!(cached.cert, certificate.cert);
+        assert_eq!(std::fs::read(&cached.cert).unwrap(), cert);
+
+        // Other hosts get another certificate from the same certificate authority
+        let ca = std::fs::read(dir.path().join("ca.pem")).unwrap();
+        let other = TlsCertificate::self_signed(dir.path(), &["other.test".to_string()]).unwrap();
+        assert_ne!(other.cert, certificate.cert);
+        assert_eq!(std::fs::read(dir.path().join("ca.pem")).unwrap(), ca);
+        other.acceptor().unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            for key in [dir.path().join("ca-key.pem"), certificate.key] {
+                let mode = std::fs::metadata(key).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
+    }
+
+    #[test]
+    fn missing_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let certificate =
+            TlsCertificate::new(dir.path().join("cert.pem"), dir.path().join("key.pem"));
+        assert!(certificate.acceptor().is_err());
+    }
+
+    #[tokio::test]
+    async fn accept() {
+        let dir = tempfile::tempdir().unwrap();
+        let certificate = TlsCertificate::self_signed(dir.path(), &[]).unwrap();
+        let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+        let addr = incoming.local_addr();
+        let console_ui = Arc::new(ConsoleUi::new(LogOptions {
+            current_dir: dir.path().to_path_buf(),
+            show_all: false,
+            log_detail: false,
+            log_level: IssueSeverity::Error,
+        }));
+        let mut incoming = TlsIncoming::new(incoming, certificate.acceptor().unwrap(), console_ui);
+
+        // A client that never completes the handshake doesn't block others
+        let _stalled = TcpStream::connect(addr).await.unwrap();
+
+        let connector = connector(&dir.path().join("ca.pem"));
+        let client = tokio::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let mut stream = connector
+                .connect(ServerName::try_from("localhost").unwrap(), stream)
+                .await
+                .unwrap();
+            assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+            stream.write_all(b"ping").await.unwrap();
+            let mut pong = [0; 4];
+            stream.read_exact(&mut pong).await.unwrap();
+            pong
+        });
+
+        let mut stream = poll_fn(|cx| Pin::new(&mut incoming).poll_accept(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut ping = [0; 4];
+        stream.read_exact(&mut ping).await.unwrap();
+        assert_eq!(&ping, b"ping");
+        stream.write_all(b"pong").await.unwrap();
+        assert_eq!(&client.await.unwrap(), b"pong");
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use turbopack_cli_utils::issue::{format_issue, LogOptions};
 use turbopack_core::{
+    chunk::ModuleIdReadRef,
     issue::{IssueSeverity, PlainIssue},
     source_pos::SourcePos,
 };
@@ -68,6 +69,32 @@ impl<'a> ClientUpdateInstruction<'a> {
         )
     }
 
+    pub fn removed(
+        resource: &'a ResourceIdentifier,
+        modules: &'a [ModuleIdReadRef],
+        chunks: &'a [String],
+        issues: &'a [Issue<'a>],
+    ) -> Self {
+        Self::new(
+            resource,
+            ClientUpdateInstructionType::Removed { modules, chunks },
+            issues,
+        )
+    }
+
+    pub fn moved(
+        resource: &'a ResourceIdentifier,
+        modules: &'a [ModuleIdReadRef],
+        chunks: &'a [String],
+        issues: &'a [Issue<'a>],
+    ) -> Self {
+        Self::new(
+            resource,
+            ClientUpdateInstructionType::Moved { modules, chunks },
+            issues,
+        )
+    }
+
     pub fn issues(resource: &'a ResourceIdentifier, issues: &'a [Issue<'a>]) -> Self {
         Self::new(resource, ClientUpdateInstructionType::Issues, issues)
     }
@@ -85,7 +112,22 @@ impl<'a> ClientUpdateInstruction<'a> {
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum ClientUpdateInstructionType<'a> {
     Restart,
-    Partial { instruction: &'a Value },
+    Partial {
+        instruction: &'a Value,
+    },
+    /// The chunks were removed. The client should dispose of their modules,
+    /// unless they are contained in other chunks.
+    Removed {
+        modules: &'a [ModuleIdReadRef],
+        chunks: &'a [String],
+    },
+    /// The modules of a removed chunk are contained in the given chunks now.
+    /// The client should keep them when the chunk is removed. Always sent
+    /// before the corresponding `Removed` instruction.
+    Moved {
+        modules: &'a [ModuleIdReadRef],
+        chunks: &'a [String],
+    },
     Issues,
 }
 
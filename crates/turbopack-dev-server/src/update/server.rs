@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -10,11 +11,11 @@ use hyper_tungstenite::{tungstenite::Message, HyperWebsocket, WebSocketStream};
 use pin_project_lite::pin_project;
 use tokio::select;
 use tokio_stream::StreamMap;
-use turbo_tasks::{TransientInstance, TurboTasksApi, Value};
+use turbo_tasks::{TransientInstance, TryJoinIterExt, TurboTasksApi, Value};
 use turbopack_core::version::Update;
 
 use super::{
-    protocol::{ClientMessage, ClientUpdateInstruction, Issue, ResourceIdentifier},
+    protocol::{ClientMessage, ClientUpdateInstruction, Issue, ResourceIdentifier, EMPTY_ISSUES},
     stream::UpdateStream,
 };
 use crate::{update::stream::UpdateStreamItem, SourceProvider};
@@ -107,6 +108,39 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
                     .send(ClientUpdateInstruction::restart(&resource, &issues))
                     .await?;
             }
+            Update::Removed(removed) => {
+                let modules = removed.modules.await?.iter().copied().try_join().await?;
+                let chunk_group = removed
+                    .chunk_group
+                    .strongly_consistent()
+                    .await?
+                    .iter()
+                    .map(|&(ref path, ids)| async move {
+                        Ok((path.clone(), ids.await?.iter().copied().try_join().await?))
+                    })
+                    .try_join()
+                    .await?;
+
+                for (chunks, modules) in moved_modules(&resource.path, &modules, &chunk_group) {
+                    client
+                        .send(ClientUpdateInstruction::moved(
+                            &resource,
+                            &modules,
+                            &chunks,
+                            EMPTY_ISSUES,
+                        ))
+                        .await?;
+                }
+
+                client
+                    .send(ClientUpdateInstruction::removed(
+                        &resource,
+                        &modules,
+                        &[resource.path.clone()],
+                        &issues,
+                    ))
+                    .await?;
+            }
             Update::None => {
                 client
                     .send(ClientUpdateInstruction::issues(&resource, &issues))
@@ -118,6 +152,28 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
     }
 }
 
+/// Groups the modules of the removed chunk at `removed_path` by the chunks of
+/// its chunk group which contain them now. Modules which are not contained in
+/// any other chunk are left out, since they were removed with the chunk.
+fn moved_modules<T: Clone + PartialEq>(
+    removed_path: &str,
+    modules: &[T],
+    chunk_group: &[(String, Vec<T>)],
+) -> BTreeMap<Vec<String>, Vec<T>> {
+    let mut moved: BTreeMap<Vec<String>, Vec<T>> = BTreeMap::new();
+    for id in modules {
+        let chunks = chunk_group
+            .iter()
+            .filter(|(path, ids)| path != removed_path && ids.contains(id))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        if !chunks.is_empty() {
+            moved.entry(chunks).or_default().push(id.clone());
+        }
+    }
+    moved
+}
+
 pin_project! {
     struct UpdateClient {
         #[pin]
@@ -220,3 +276,85 @@ impl From<WebSocketStream<Upgraded>> for UpdateClient {
         Self { ws, ended: false }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use turbo_tasks::ReadRef;
+    use turbopack_core::chunk::ModuleId;
+
+    use super::moved_modules;
+    use crate::update::protocol::{ClientUpdateInstruction, ResourceIdentifier, EMPTY_ISSUES};
+
+    fn chunk(path: &str, modules: &[&'static str]) -> (String, Vec<&'static str>) {
+        (path.to_string(), modules.to_vec())
+    }
+
+    #[test]
+    fn moved() {
+        let chunk_group = vec![
+            // The removed chunk might still be part of the chunk group under its old path
+            chunk("a.js", &["a", "b", "c", "d"]),
+            chunk("b.js", &["b", "c"]),
+            chunk("c.js", &["c"]),
+        ];
+        let moved = moved_modules("a.js", &["a", "b", "c", "d"], &chunk_group);
+        assert_eq!(
+            moved,
+            BTreeMap::from([
+                (vec!["b.js".to_string()], vec!["b"]),
+                (vec!["b.js".to_string(), "c.js".to_string()], vec!["c"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn removed() {
+        // Modules of a chunk that left the chunk group aren't moved anywhere
+        let moved = moved_modules("a.js", &["a"], &[chunk("b.js", &["b"])]);
+        assert!(moved.is_empty());
+    }
+
+    #[test]
+    fn instructions() {
+        let resource = ResourceIdentifier {
+            path: "a.js".to_string(),
+            headers: None,
+        };
+        let modules = [
+            ReadRef::new(Arc::new(ModuleId::Number(1))),
+            ReadRef::new(Arc::new(ModuleId::String("./b.js".to_string()))),
+        ];
+        let chunks = ["b.js".to_string()];
+
+        let moved = ClientUpdateInstruction::moved(&resource, &modules, &chunks, EMPTY_ISSUES);
+        assert_eq!(
+            serde_json::to_value(&moved).unwrap(),
+            serde_json::json!({
+                "resource": { "path": "a.js", "headers": null },
+                "type": "moved",
+                "modules": [1, "./b.js"],
+                "chunks": ["b.js"],
+                "issues": [],
+            })
+        );
+
+        let removed = ClientUpdateInstruction::removed(
+            &resource,
+            &modules,
+            &[resource.path.clone()],
+            EMPTY_ISSUES,
+        );
+        assert_eq!(
+            serde_json::to_value(&removed).unwrap(),
+            serde_json::json!({
+                "resource": { "path": "a.js", "headers": null },
+                "type": "removed",
+                "modules": [1, "./b.js"],
+                "chunks": ["a.js"],
+                "issues": [],
+            })
+        );
+    }
+}
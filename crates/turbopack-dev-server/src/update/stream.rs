@@ -7,7 +7,10 @@ use tokio_stream::wrappers::ReceiverStream;
 use turbo_tasks::{CollectiblesSource, State, TransientInstance, Value};
 use turbopack_core::{
     issue::{IssueVc, PlainIssueReadRef},
-    version::{NotFoundVersionVc, PartialUpdate, TotalUpdate, Update, UpdateReadRef, VersionVc},
+    version::{
+        NotFoundVersionVc, PartialUpdate, RemovedUpdate, TotalUpdate, Update, UpdateReadRef,
+        VersionVc,
+    },
 };
 
 use super::protocol::ResourceIdentifier;
@@ -56,10 +59,21 @@ async fn get_content_wrapper(
             ContentSourceResult::NotFound => return Ok(ContentSourceContentVc::not_found()),
             ContentSourceResult::Result { get_content, .. } => {
                 let vary = get_content.vary().await?;
-                return Ok(get_content.get(Value::new(resource_to_data(
+                let result = get_content.get(Value::new(resource_to_data(
                     resource.clone().into_value(),
                     &vary,
-                ))));
+                )));
+                if let ContentSourceContent::NeedData(data) = &*result.await? {
+                    content = data
+                        .source
+                        .get(
+                            &data.path,
+                            Value::new(resource_to_data(resource.clone().into_value(), &data.vary)),
+                        )
+                        .await?;
+                    continue;
+                }
+                return Ok(result);
             }
         }
     }
@@ -91,13 +105,21 @@ async fn get_update_stream_item(
             let plain_issues = peek_issues(content).await?;
 
             let update = if plain_issues.is_empty() {
-                // Client requested a non-existing asset
-                // It might be removed in meantime, reload client
-                // TODO add special instructions for removed assets to handled it in a better
-                // way
-                Update::Total(TotalUpdate {
-                    to: NotFoundVersionVc::new().into(),
-                })
+                let from = from.get();
+                if NotFoundVersionVc::resolve_from(from).await?.is_some() {
+                    // Client requested a non-existing asset, reload client
+                    Update::Total(TotalUpdate {
+                        to: NotFoundVersionVc::new().into(),
+                    })
+                } else {
+                    // The asset was removed in the meantime, the client can dispose of its
+                    // modules
+                    Update::Removed(RemovedUpdate {
+                        to: NotFoundVersionVc::new().into(),
+                        modules: from.module_ids(),
+                        chunk_group: from.chunk_group_modules(),
+                    })
+                }
                 .cell()
             } else {
                 Update::None.cell()
@@ -173,7 +195,7 @@ impl UpdateStream {
         let content = get_content_wrapper(Value::new(resource.clone()), get_content.clone());
         // We can ignore issues reported in content here since [compute_update_stream]
         // will handle them
-        let version = match &*content.await? {
+        let version = match &*content.strongly_consistent().await? {
             ContentSourceContent::Static(content) => content.version(),
             _ => NotFoundVersionVc::new().into(),
         };
@@ -196,7 +218,8 @@ impl UpdateStream {
             async move {
                 match &*update.update {
                     Update::Partial(PartialUpdate { to, .. })
-                    | Update::Total(TotalUpdate { to }) => {
+                    | Update::Total(TotalUpdate { to })
+                    | Update::Removed(RemovedUpdate { to, .. }) => {
                         version_state
                             .set(*to)
                             .await
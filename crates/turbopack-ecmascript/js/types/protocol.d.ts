@@ -11,6 +11,16 @@ export type ServerMessage = {
       type: "partial";
       instruction: EcmascriptChunkUpdate;
     }
+  | {
+      type: "removed";
+      modules: ModuleId[];
+      chunks: ChunkPath[];
+    }
+  | {
+      type: "moved";
+      modules: ModuleId[];
+      chunks: ChunkPath[];
+    }
   | {
       type: "issues";
     }
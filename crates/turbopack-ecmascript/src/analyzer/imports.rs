@@ -1,4 +1,8 @@
-use std::{collections::BTreeMap, fmt::Display, mem::take};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    mem::take,
+};
 
 use indexmap::{IndexMap, IndexSet};
 use once_cell::sync::Lazy;
@@ -146,6 +150,25 @@ impl ImportMap {
         self.reexports.iter().map(|(i, r)| (*i, r))
     }
 
+    /// Returns the symbols imported from each reference, in the order of
+    /// `references`. `None` means that the namespace of the reference is
+    /// imported, so any symbol could be used.
+    ///
+    /// Re-exports are not included here, they are part of the exports of the
+    /// module.
+    pub fn imported_symbols(&self) -> Vec<Option<BTreeSet<String>>> {
+        let mut symbols = vec![Some(BTreeSet::new()); self.references.len()];
+        for (i, sym) in self.imports.values() {
+            if let Some(set) = &mut symbols[*i] {
+                set.insert(sym.to_string());
+            }
+        }
+        for i in self.namespace_imports.values() {
+            symbols[*i] = None;
+        }
+        symbols
+    }
+
     /// Analyze ES import
     pub(super) fn analyze(m: &Program) -> Self {
         let mut data = ImportMap::default();
@@ -37,7 +37,7 @@ use crate::{
 #[turbo_tasks::value]
 pub struct ManifestLoaderItem {
     context: ChunkingContextVc,
-    manifest: ManifestChunkAssetVc,
+    pub(super) manifest: ManifestChunkAssetVc,
 }
 
 #[turbo_tasks::value_impl]
@@ -160,7 +160,7 @@ impl ManifestChunkAssetVc {
     }
 
     #[turbo_tasks::function]
-    async fn chunks(self) -> Result<ChunksVc> {
+    pub(super) async fn chunks(self) -> Result<ChunksVc> {
         let this = self.await?;
         let chunk_group = ChunkGroupVc::from_asset(this.asset, this.chunking_context);
         Ok(chunk_group.chunks())
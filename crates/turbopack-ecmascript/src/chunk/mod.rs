@@ -2,14 +2,19 @@ pub mod loader;
 pub(crate) mod optimize;
 pub mod source_map;
 
-use std::{fmt::Write, io::Write as _, slice::Iter};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    io::Write as _,
+    slice::Iter,
+};
 
 use anyhow::{anyhow, bail, Result};
 use indexmap::{IndexMap, IndexSet};
 use indoc::indoc;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
-    primitives::{JsonValueVc, StringReadRef, StringVc, StringsVc, UsizeVc},
+    primitives::{BoolVc, JsonValueVc, StringReadRef, StringVc, StringsVc, U64Vc, UsizeVc},
     trace::TraceRawVcs,
     TryJoinIterExt, ValueToString, ValueToStringVc,
 };
@@ -23,8 +28,9 @@ use turbopack_core::{
         chunk_content, chunk_content_split,
         optimize::{ChunkOptimizerVc, OptimizableChunk, OptimizableChunkVc},
         Chunk, ChunkContentResult, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkItemVc,
-        ChunkReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc,
-        FromChunkableAsset, ModuleId, ModuleIdReadRef, ModuleIdVc, ModuleIdsVc,
+        ChunkModulesVc, ChunkReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc,
+        ChunkingContextVc, FromChunkableAsset, ModuleId, ModuleIdReadRef, ModuleIdVc, ModuleIdsVc,
+        RuntimeType, RuntimeTypeVc,
     },
     code_builder::{Code, CodeBuilder, CodeReadRef, CodeVc},
     environment::{ChunkLoading, EnvironmentVc},
@@ -46,6 +52,7 @@ use self::{
     source_map::EcmascriptChunkSourceMapAssetReferenceVc,
 };
 use crate::{
+    minify::minify,
     parse::ParseResultSourceMapVc,
     references::esm::EsmExportsVc,
     utils::{stringify_module_id, stringify_str, FormatIter},
@@ -346,7 +353,10 @@ pub struct EcmascriptChunkContent {
     chunk_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
     evaluate: Option<EcmascriptChunkContentEvaluateVc>,
+    chunk_group: ChunkGroupVc,
     environment: EnvironmentVc,
+    runtime_type: RuntimeTypeVc,
+    minify: BoolVc,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -444,6 +454,7 @@ impl EcmascriptChunkContentVc {
         omit_entries: Option<EcmascriptChunkPlaceablesVc>,
         chunk_path: FileSystemPathVc,
         evaluate: Option<EcmascriptChunkContentEvaluateVc>,
+        chunk_group: ChunkGroupVc,
     ) -> Result<Self> {
         // TODO(alexkirsz) All of this should be done in a transition, otherwise we run
         // the risks of values not being strongly consistent with each other.
@@ -456,7 +467,10 @@ impl EcmascriptChunkContentVc {
             chunk_path,
             output_root,
             evaluate,
+            chunk_group,
             environment: context.environment(),
+            runtime_type: context.runtime_type(),
+            minify: context.is_minification_enabled(),
         }
         .cell())
     }
@@ -558,14 +572,16 @@ struct EcmascriptChunkUpdate<'a> {
 impl EcmascriptChunkContentVc {
     #[turbo_tasks::function]
     async fn version(self) -> Result<EcmascriptChunkVersionVc> {
-        let module_factories_hashes = self
-            .await?
+        let this = self.await?;
+        let module_factories_hashes = this
             .module_factories
             .iter()
             .map(|entry| (entry.id.clone(), entry.hash))
             .collect();
         Ok(EcmascriptChunkVersion {
             module_factories_hashes,
+            chunk_group: this.chunk_group,
+            output_root: this.output_root,
         }
         .cell())
     }
@@ -656,7 +672,10 @@ impl EcmascriptChunkContentVc {
                 FileContent::Content(file) => code.push_source(file.content(), None),
             };
 
-            let shared_runtime_code = embed_file!("js/src/runtime.js").await?;
+            let shared_runtime_code = match *this.runtime_type.await? {
+                RuntimeType::Development => embed_file!("js/src/runtime.js").await?,
+                RuntimeType::Production => embed_file!("js/src/runtime.production.js").await?,
+            };
 
             match &*shared_runtime_code {
                 FileContent::NotFound => return Err(anyhow!("shared runtime code is not found")),
@@ -668,6 +687,12 @@ impl EcmascriptChunkContentVc {
             "# };
         }
 
+        if *this.minify.await? {
+            let minified = minify(this.chunk_path, code.build().cell()).await?;
+            code = CodeBuilder::default();
+            code.push_code(&minified);
+        }
+
         if code.has_source_map() {
             let filename = chunk_path.file_name();
             write!(code, "\n\n//# sourceMappingURL={}.map", filename)?;
@@ -822,6 +847,8 @@ impl<'a> HmrUpdateEntry<'a> {
 #[turbo_tasks::value(serialization = "none")]
 struct EcmascriptChunkVersion {
     module_factories_hashes: IndexMap<ModuleIdReadRef, u64>,
+    chunk_group: ChunkGroupVc,
+    output_root: FileSystemPathVc,
 }
 
 #[turbo_tasks::value_impl]
@@ -841,6 +868,32 @@ impl Version for EcmascriptChunkVersion {
         let hex_hash = encode_hex(hash);
         Ok(StringVc::cell(hex_hash))
     }
+
+    #[turbo_tasks::function]
+    fn module_ids(&self) -> ModuleIdsVc {
+        ModuleIdsVc::cell(
+            self.module_factories_hashes
+                .keys()
+                .map(|id| (**id).clone().cell())
+                .collect(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    async fn chunk_group_modules(&self) -> Result<ChunkModulesVc> {
+        let output_root = self.output_root.await?;
+        let mut chunks = Vec::new();
+        for chunk in self.chunk_group.chunks().await?.iter() {
+            if let Some(ecma_chunk) = EcmascriptChunkVc::resolve_from(chunk).await? {
+                let chunk_path = &*chunk.path().await?;
+                if let Some(chunk_server_path) = output_root.get_path_to(chunk_path) {
+                    let version: VersionVc = ecma_chunk.chunk_content().version().into();
+                    chunks.push((chunk_server_path.to_string(), version.module_ids()));
+                }
+            }
+        }
+        Ok(ChunkModulesVc::cell(chunks))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -935,12 +988,36 @@ impl EcmascriptChunkVc {
         ))
     }
 
+    /// Returns statistics about the chunk, including the reduction of its
+    /// content by tree shaking.
+    #[turbo_tasks::function]
+    pub async fn stats(self) -> Result<EcmascriptChunkStatsVc> {
+        let chunk_items = self.chunk_content_result().await?.chunk_items.await?;
+        let mut stats = EcmascriptChunkStats::default();
+        for chunk in chunk_items.iter() {
+            for item in chunk.await?.iter() {
+                let content = item.content().await?;
+                stats.chunk_items += 1;
+                stats.removed_exports += content.removed_exports;
+                stats.removed_imports += content.removed_imports;
+            }
+        }
+        Ok(stats.cell())
+    }
+
     #[turbo_tasks::function]
     async fn chunk_content(self) -> Result<EcmascriptChunkContentVc> {
         let this = self.await?;
         let evaluate = this
             .evaluate
             .map(|evaluate| evaluate.content(this.context, self));
+        // Chunks without an explicit chunk group are loaded in the group they
+        // are the entry of
+        let chunk_group = match this.evaluate {
+            Some(evaluate) => evaluate.await?.chunk_group,
+            None => None,
+        }
+        .unwrap_or_else(|| ChunkGroupVc::from_chunk(self.into()));
         let chunk_path = self.path();
         let content = EcmascriptChunkContentVc::new(
             this.context,
@@ -948,11 +1025,23 @@ impl EcmascriptChunkVc {
             this.omit_entries,
             chunk_path,
             evaluate,
+            chunk_group,
         );
         Ok(content)
     }
 }
 
+#[turbo_tasks::value]
+#[derive(Default)]
+pub struct EcmascriptChunkStats {
+    pub chunk_items: usize,
+    /// The number of exports which have been removed by tree shaking.
+    pub removed_exports: usize,
+    /// The number of imports of unused side effect free modules which have
+    /// been removed by tree shaking.
+    pub removed_imports: usize,
+}
+
 #[turbo_tasks::value_impl]
 impl Asset for EcmascriptChunk {
     #[turbo_tasks::function]
@@ -984,11 +1073,18 @@ impl Asset for EcmascriptChunk {
         // info.
         let mut path = if main_entries.len() == 1 {
             let main_entry = main_entries.iter().next().unwrap();
+            // Entries resolved from requests with a query share the path of the file,
+            // so the ident has to be part of the hashed info.
+            let ident = main_entry.ident().await?;
+            if *ident != *main_entry.path().to_string().await? {
+                hasher.write_value(ident);
+                need_hash = true;
+            }
             main_entry.path()
         } else {
             for entry in &main_entries {
-                let path = entry.path().to_string().await?;
-                hasher.write_value(path);
+                let ident = entry.ident().await?;
+                hasher.write_value(ident);
                 need_hash = true;
             }
             if let &Some(common_parent) = &*self_vc.common_parent().await? {
@@ -1002,6 +1098,29 @@ impl Asset for EcmascriptChunk {
             }
         };
 
+        // With content hashing the hash of all chunk items contributes to the hashed
+        // info, so the path changes whenever the content of the chunk changes.
+        // Manifest chunks contain the paths of the chunks they load instead, so
+        // they are hashed by the content of those chunks.
+        if *this.context.is_content_hashing_enabled().await? {
+            let mut is_manifest = false;
+            for entry in &main_entries {
+                if let Some(manifest) = ManifestChunkAssetVc::resolve_from(*entry).await? {
+                    hasher.write_value(*manifest_content_hash(manifest).await?);
+                    is_manifest = true;
+                    need_hash = true;
+                }
+            }
+            if !is_manifest {
+                let chunk_items = self_vc.chunk_content_result().await?.chunk_items;
+                for entry in chunk_items.to_entry_snapshot().await?.iter() {
+                    hasher.write_ref(entry.id());
+                    hasher.write_value(entry.hash);
+                    need_hash = true;
+                }
+            }
+        }
+
         if need_hash {
             let hash = hasher.finish();
             let hash = encode_hex(hash);
@@ -1079,6 +1198,14 @@ impl Introspectable for EcmascriptChunk {
                 writeln!(details, "- {}", item.to_string().await?)?;
             }
         }
+        let stats = self_vc.stats().await?;
+        if stats.removed_exports > 0 || stats.removed_imports > 0 {
+            writeln!(
+                details,
+                "\nTree shaking removed {} unused exports and {} imports of unused modules",
+                stats.removed_exports, stats.removed_imports
+            )?;
+        }
         details += "\nContent:\n\n";
         write!(details, "{}", content.await?)?;
         Ok(StringVc::cell(details))
@@ -1124,18 +1251,132 @@ impl EcmascriptChunkContextVc {
 
     #[turbo_tasks::function]
     pub async fn chunk_item_id(self, chunk_item: EcmascriptChunkItemVc) -> Result<ModuleIdVc> {
-        let layer = &*self.await?.context.layer().await?;
-        let mut s = chunk_item.to_string().await?.clone_value();
-        if !layer.is_empty() {
-            if s.ends_with(')') {
-                s.pop();
-                write!(s, ", {layer})")?;
-            } else {
-                write!(s, " ({layer})")?;
+        let context = self.await?.context;
+        let ident = chunk_item_ident(&context.layer().await?, chunk_item).await?;
+        if let Some(&id) = module_ids(context).await?.get(&ident) {
+            return Ok(ModuleId::Number(id).cell());
+        }
+        Ok(ModuleId::String(ident).cell())
+    }
+}
+
+/// A human readable identifier of a chunk item, which includes the layer.
+async fn chunk_item_ident(layer: &str, chunk_item: EcmascriptChunkItemVc) -> Result<String> {
+    let mut s = chunk_item.to_string().await?.clone_value();
+    if !layer.is_empty() {
+        if s.ends_with(')') {
+            s.pop();
+            write!(s, ", {layer})")?;
+        } else {
+            write!(s, " ({layer})")?;
+        }
+    }
+    Ok(s)
+}
+
+#[turbo_tasks::value(transparent)]
+struct ModuleIdMap(HashMap<String, u32>);
+
+/// Assigns short numeric ids to the chunk items of the module graph of
+/// [ChunkingContext::module_graph_entries]. The ids are derived from a hash of
+/// the identifiers, so they stay the same when other modules are added or
+/// removed. Chunk items outside of the module graph, e.g. manifest chunks, keep
+/// their readable identifier as id.
+#[turbo_tasks::function]
+async fn module_ids(context: ChunkingContextVc) -> Result<ModuleIdMapVc> {
+    let entries = context.module_graph_entries().await?;
+    if entries.is_empty() {
+        return Ok(ModuleIdMapVc::cell(HashMap::new()));
+    }
+    let layer = context.layer().await?;
+
+    let mut idents = BTreeSet::new();
+    let mut visited = HashSet::new();
+    let mut queue = entries.iter().copied().collect::<Vec<_>>();
+    while let Some(asset) = queue.pop() {
+        let asset = asset.resolve().await?;
+        if !visited.insert(asset) {
+            continue;
+        }
+        if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+            idents.insert(chunk_item_ident(&layer, placeable.as_chunk_item(context)).await?);
+        }
+        for reference in asset.references().await?.iter() {
+            queue.extend(reference.resolve_reference().primary_assets().await?.iter());
+        }
+    }
+
+    Ok(ModuleIdMapVc::cell(hashed_module_ids(idents)))
+}
+
+/// Derives an id from the hash of each identifier. The ids have one more digit
+/// than needed to count the identifiers, which keeps collisions rare. On a
+/// collision, the identifier that comes later in sort order is hashed again
+/// with a salt until it gets a free id.
+fn hashed_module_ids(idents: BTreeSet<String>) -> HashMap<String, u32> {
+    let mut range = 10;
+    while range < idents.len() as u64 * 10 {
+        range *= 10;
+    }
+    let mut used = HashSet::new();
+    idents
+        .into_iter()
+        .map(|ident| {
+            let mut salt = 0u32;
+            let id = loop {
+                let mut hasher = Xxh3Hash64Hasher::new();
+                hasher.write_ref(ident.as_str());
+                if salt > 0 {
+                    hasher.write_value(salt);
+                }
+                let id = (hasher.finish() % range) as u32;
+                if used.insert(id) {
+                    break id;
+                }
+                salt += 1;
+            };
+            (ident, id)
+        })
+        .collect()
+}
+
+/// Hashes the content of all chunks that are loaded through a manifest chunk,
+/// including the chunks of nested manifest chunks. The code of manifest
+/// loader items isn't included, as it contains the path of a manifest chunk,
+/// which is derived from this hash. Including it would create a cycle for
+/// modules that import each other dynamically.
+#[turbo_tasks::function]
+async fn manifest_content_hash(manifest: ManifestChunkAssetVc) -> Result<U64Vc> {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    let mut visited = HashSet::new();
+    let mut queue = vec![manifest.resolve().await?];
+    while let Some(manifest) = queue.pop() {
+        if !visited.insert(manifest) {
+            continue;
+        }
+        for &chunk in manifest.chunks().await?.iter() {
+            let Some(chunk) = EcmascriptChunkVc::resolve_from(chunk).await? else {
+                // Other chunks don't load manifest chunks, so their path
+                // already depends on their content
+                hasher.write_ref(&*chunk.path().to_string().await?);
+                continue;
+            };
+            let chunk_items = chunk.chunk_content_result().await?.chunk_items;
+            for items in chunk_items.await?.iter() {
+                for &item in items.await?.iter() {
+                    if let Some(loader) = ManifestLoaderItemVc::resolve_from(item).await? {
+                        hasher.write_ref(&*item.id().await?);
+                        queue.push(loader.await?.manifest.resolve().await?);
+                    } else {
+                        let entry = EcmascriptChunkContentEntryVc::new(item).await?;
+                        hasher.write_ref(entry.id());
+                        hasher.write_value(entry.hash);
+                    }
+                }
             }
         }
-        Ok(ModuleId::String(s).cell())
     }
+    Ok(U64Vc::cell(hasher.finish()))
 }
 
 #[turbo_tasks::value(shared)]
@@ -1169,6 +1410,11 @@ pub struct EcmascriptChunkItemContent {
     pub inner_code: Rope,
     pub source_map: Option<ParseResultSourceMapVc>,
     pub options: EcmascriptChunkItemOptions,
+    /// The number of exports which have been removed by tree shaking.
+    pub removed_exports: usize,
+    /// The number of imports of unused side effect free modules which have
+    /// been removed by tree shaking.
+    pub removed_imports: usize,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1268,3 +1514,31 @@ impl EcmascriptChunkItemsVc {
 
 #[turbo_tasks::value(transparent)]
 pub struct EcmascriptChunkItemsSet(IndexSet<EcmascriptChunkItemVc>);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::hashed_module_ids;
+
+    #[test]
+    fn hashed_module_ids_are_stable() {
+        let idents = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let ids = hashed_module_ids(idents(&["a.js", "b.js", "c.js"]));
+        let unique = ids.values().collect::<BTreeSet<_>>();
+        assert_eq!(unique.len(), 3);
+        assert!(ids.values().all(|&id| id < 100));
+
+        // Adding a module keeps the ids of the others, unless it collides with one
+        // of them
+        let more = hashed_module_ids(idents(&["a.js", "b.js", "c.js", "d.js"]));
+        for (ident, id) in &ids {
+            assert_eq!(more.get(ident), Some(id));
+        }
+
+        // Colliding identifiers get other ids
+        let many = (0..100).map(|i| format!("{i}.js")).collect::<BTreeSet<_>>();
+        let ids = hashed_module_ids(many);
+        assert_eq!(ids.values().collect::<BTreeSet<_>>().len(), 100);
+    }
+}
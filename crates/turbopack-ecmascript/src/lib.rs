@@ -13,12 +13,14 @@ pub mod chunk_group_files_asset;
 pub mod code_gen;
 mod errors;
 pub mod magic_identifier;
+pub mod minify;
 pub(crate) mod parse;
 mod path_visitor;
 pub(crate) mod references;
 pub mod resolve;
 pub(crate) mod special_cases;
 pub(crate) mod transform;
+pub mod tree_shake;
 pub mod typescript;
 pub mod utils;
 pub mod webpack;
@@ -65,7 +67,14 @@ use self::chunk::{
 pub use self::references::AnalyzeEcmascriptModuleResultVc;
 use crate::{
     chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
-    references::analyze_ecmascript_module,
+    references::{
+        analyze_ecmascript_module,
+        esm::{base::ReferencedAsset, EsmExportsVc},
+    },
+    tree_shake::{
+        export_usage, is_unused_module, unused_declarations::remove_unused_declarations,
+        ExportUsage, ModuleExportUsage,
+    },
 };
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -163,6 +172,11 @@ impl Asset for EcmascriptModuleAsset {
         self.source.path()
     }
 
+    #[turbo_tasks::function]
+    fn ident(&self) -> StringVc {
+        self.source.ident()
+    }
+
     #[turbo_tasks::function]
     fn content(&self) -> AssetContentVc {
         self.source.content()
@@ -243,7 +257,7 @@ impl ValueToString for ModuleChunkItem {
         // TODO include inner_assets in this name
         Ok(StringVc::cell(format!(
             "{} (ecmascript)",
-            self.module.await?.source.path().to_string().await?
+            self.module.await?.source.ident().await?
         )))
     }
 }
@@ -268,9 +282,17 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         let AnalyzeEcmascriptModuleResult {
             references,
             code_generation,
+            import_usages,
             ..
         } = &*self.module.analyze().await?;
         let context = self.context;
+        let usage = match &*export_usage(context, self.module.into()).await? {
+            ModuleExportUsage::Used(usage) => usage.clone(),
+            // Unused modules are not placed in chunks.
+            ModuleExportUsage::Unused => ExportUsage::All,
+        };
+        let mut removed_exports = 0;
+        let mut removed_imports = 0;
         let mut code_gens = Vec::new();
         for r in references.await?.iter() {
             if let Some(code_gen) = CodeGenerateableVc::resolve_from(r).await? {
@@ -279,7 +301,26 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         }
         for c in code_generation.await?.iter() {
             let c = c.resolve().await?;
-            code_gens.push(c.code_generation(context));
+            match (&usage, EsmExportsVc::resolve_from(c).await?) {
+                (ExportUsage::Named(_), Some(exports)) => {
+                    removed_exports = exports
+                        .await?
+                        .exports
+                        .keys()
+                        .filter(|export| !usage.is_used(export))
+                        .count();
+                    code_gens
+                        .push(exports.code_generation_with_usage(context, usage.clone().cell()));
+                }
+                _ => code_gens.push(c.code_generation(context)),
+            }
+        }
+        for (reference, _) in import_usages.await?.iter() {
+            if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                if is_unused_module(context, *asset).await? {
+                    removed_imports += 1;
+                }
+            }
         }
         // need to keep that around to allow references into that
         let code_gens = code_gens.into_iter().try_join().await?;
@@ -320,6 +361,9 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                 for visitor in root_visitors {
                     program.visit_mut_with(&mut visitor.create());
                 }
+                if removed_exports > 0 {
+                    remove_unused_declarations(&mut program);
+                }
                 program.visit_mut_with(&mut swc_core::ecma::transforms::base::hygiene::hygiene());
                 program.visit_mut_with(&mut swc_core::ecma::transforms::base::fixer::fixer(None));
             });
@@ -359,6 +403,8 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                         ..Default::default()
                     }
                 },
+                removed_exports,
+                removed_imports,
                 ..Default::default()
             }
             .into())
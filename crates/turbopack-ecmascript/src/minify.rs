@@ -0,0 +1,167 @@
+use std::{io::Write, sync::Arc};
+
+use anyhow::{bail, Result};
+use swc_core::{
+    common::{
+        input::StringInput, source_map::DefaultSourceMapGenConfig, BytePos, FileName, Globals,
+        LineCol, Mark, SourceMap, GLOBALS,
+    },
+    ecma::{
+        ast::{EsVersion, Program},
+        codegen::{text_writer::JsWriter, Emitter},
+        minifier::{
+            optimize,
+            option::{CompressOptions, ExtraOptions, MangleOptions, MinifyOptions},
+        },
+        parser::{lexer::Lexer, Parser, Syntax},
+        transforms::base::{fixer::fixer, hygiene::hygiene, resolver},
+        visit::FoldWith,
+    },
+};
+use turbo_tasks::ValueToString;
+use turbo_tasks_fs::{rope::Rope, FileSystemPathVc};
+use turbopack_core::{
+    code_builder::{CodeBuilder, CodeVc},
+    source_map::{GenerateSourceMap, SourceMapVc},
+};
+
+/// Minifies the code of a chunk. If the input has a source map, the minified
+/// code gets a source map to the original sources.
+#[turbo_tasks::function]
+pub async fn minify(path: FileSystemPathVc, code_vc: CodeVc) -> Result<CodeVc> {
+    let path = path.to_string().await?;
+    let code = code_vc.await?;
+    let source = code.source_code().to_str()?.into_owned();
+    let original_map = if code.has_source_map() {
+        let map = code_vc.generate_source_map().to_rope().await?;
+        match sourcemap::decode_slice(map.to_str()?.as_bytes())? {
+            sourcemap::DecodedMap::Regular(map) => Some(map),
+            sourcemap::DecodedMap::Index(map) => Some(map.flatten()?),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let cm = Arc::new(SourceMap::default());
+    let fm = cm.new_source_file(FileName::Custom(path.to_string()), source);
+
+    let lexer = Lexer::new(
+        Syntax::default(),
+        EsVersion::latest(),
+        StringInput::from(&*fm),
+        None,
+    );
+    let mut parser = Parser::new_from(lexer);
+    let program = match parser.parse_program() {
+        Ok(program) => program,
+        Err(err) => bail!(
+            "failed to parse chunk {} for minification: {}",
+            path,
+            err.kind().msg()
+        ),
+    };
+
+    let minified = GLOBALS.set(&Globals::new(), || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+
+        let program = program.fold_with(&mut resolver(unresolved_mark, top_level_mark, false));
+        let program = optimize(
+            program,
+            cm.clone(),
+            None,
+            None,
+            &MinifyOptions {
+                // The defaults of `CompressOptions` disable all compressions
+                compress: Some(CompressOptions {
+                    bools: true,
+                    collapse_vars: true,
+                    comparisons: true,
+                    conditionals: true,
+                    dead_code: true,
+                    evaluate: true,
+                    if_return: true,
+                    join_vars: true,
+                    loops: true,
+                    negate_iife: true,
+                    passes: 2,
+                    reduce_vars: true,
+                    side_effects: true,
+                    switches: true,
+                    unused: true,
+                    ..Default::default()
+                }),
+                mangle: Some(MangleOptions {
+                    top_level: false,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            &ExtraOptions {
+                unresolved_mark,
+                top_level_mark,
+            },
+        );
+        program
+            .fold_with(&mut hygiene())
+            .fold_with(&mut fixer(None))
+    });
+
+    let mut builder = CodeBuilder::default();
+    if let Some(original_map) = original_map {
+        let mut mappings = Vec::new();
+        let bytes = print_program(cm.clone(), &minified, Some(&mut mappings))?;
+        let map = cm.build_source_map_with_config(
+            &mappings,
+            Some(&original_map),
+            DefaultSourceMapGenConfig,
+        );
+        builder.push_source(
+            &Rope::from(bytes),
+            Some(
+                MinifiedSourceMap {
+                    map: SourceMapVc::new_regular(map),
+                }
+                .cell()
+                .into(),
+            ),
+        );
+    } else {
+        builder.write_all(&print_program(cm, &minified, None)?)?;
+    }
+    Ok(builder.build().cell())
+}
+
+fn print_program(
+    cm: Arc<SourceMap>,
+    program: &Program,
+    mappings: Option<&mut Vec<(BytePos, LineCol)>>,
+) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut emitter = Emitter {
+        cfg: swc_core::ecma::codegen::Config {
+            minify: true,
+            ..Default::default()
+        },
+        cm: cm.clone(),
+        comments: None,
+        wr: JsWriter::new(cm, "\n", &mut bytes, mappings),
+    };
+    emitter.emit_program(program)?;
+    Ok(bytes)
+}
+
+/// The source map of minified code, which maps to the sources of the input.
+#[turbo_tasks::value]
+struct MinifiedSourceMap {
+    map: SourceMapVc,
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for MinifiedSourceMap {
+    #[turbo_tasks::function]
+    fn generate_source_map(&self) -> SourceMapVc {
+        self.map
+    }
+}
@@ -25,6 +25,7 @@ use crate::{
     create_visitor, magic_identifier,
     references::util::{request_to_string, throw_module_not_found_expr},
     resolve::esm_resolve,
+    tree_shake::is_unused_module,
 };
 
 #[turbo_tasks::value]
@@ -107,7 +108,7 @@ impl EsmAssetReference {
 #[turbo_tasks::value_impl]
 impl EsmAssetReferenceVc {
     #[turbo_tasks::function]
-    pub(super) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
+    pub(crate) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
         let this = self.await?;
         Ok(ReferencedAssetVc::from_resolve_result(
             esm_resolve(this.get_origin(), this.request),
@@ -152,19 +153,31 @@ impl ValueToString for EsmAssetReference {
 #[turbo_tasks::value_impl]
 impl ChunkableAssetReference for EsmAssetReference {
     #[turbo_tasks::function]
-    fn chunking_type(&self, _context: ChunkingContextVc) -> Result<ChunkingTypeOptionVc> {
-        Ok(ChunkingTypeOptionVc::cell(
-            if let Some(chunking_type) = self.annotations.chunking_type() {
-                match chunking_type {
-                    "separate" => Some(ChunkingType::Separate),
-                    "parallel" => Some(ChunkingType::Parallel),
-                    "none" => None,
-                    _ => return Err(anyhow!("unknown chunking_type: {}", chunking_type)),
+    async fn chunking_type(
+        self_vc: EsmAssetReferenceVc,
+        context: ChunkingContextVc,
+    ) -> Result<ChunkingTypeOptionVc> {
+        let this = self_vc.await?;
+        let chunking_type = if let Some(chunking_type) = this.annotations.chunking_type() {
+            match chunking_type {
+                "separate" => Some(ChunkingType::Separate),
+                "parallel" => Some(ChunkingType::Parallel),
+                "none" => None,
+                _ => return Err(anyhow!("unknown chunking_type: {}", chunking_type)),
+            }
+        } else {
+            Some(ChunkingType::default())
+        };
+        // Side effect free modules which are not used after tree shaking don't need
+        // to be included.
+        if chunking_type.is_some() {
+            if let ReferencedAsset::Some(asset) = &*self_vc.get_referenced_asset().await? {
+                if is_unused_module(context, *asset).await? {
+                    return Ok(ChunkingTypeOptionVc::cell(None));
                 }
-            } else {
-                Some(ChunkingType::default())
-            },
-        ))
+            }
+        }
+        Ok(ChunkingTypeOptionVc::cell(chunking_type))
     }
 }
 
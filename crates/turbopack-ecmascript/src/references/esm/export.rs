@@ -26,6 +26,7 @@ use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
     references::esm::base::insert_hoisted_stmt,
+    tree_shake::{is_unused_module, ExportUsage, ExportUsageVc},
 };
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
@@ -135,11 +136,23 @@ pub struct EsmExports {
 #[turbo_tasks::value_impl]
 impl CodeGenerateable for EsmExports {
     #[turbo_tasks::function]
-    async fn code_generation(
-        self_vc: EsmExportsVc,
-        _context: ChunkingContextVc,
+    fn code_generation(self_vc: EsmExportsVc, context: ChunkingContextVc) -> CodeGenerationVc {
+        self_vc.code_generation_with_usage(context, ExportUsage::All.cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EsmExportsVc {
+    /// Generates the exports of the module, omitting all exports that are not
+    /// part of `usage`.
+    #[turbo_tasks::function]
+    pub async fn code_generation_with_usage(
+        self,
+        context: ChunkingContextVc,
+        usage: ExportUsageVc,
     ) -> Result<CodeGenerationVc> {
-        let this = self_vc.await?;
+        let this = self.await?;
+        let usage = usage.await?;
         let mut visitors = Vec::new();
 
         let mut all_exports: BTreeMap<Cow<str>, Cow<EsmExport>> = this
@@ -152,6 +165,9 @@ impl CodeGenerateable for EsmExports {
 
         for esm_ref in this.star_exports.iter() {
             if let ReferencedAsset::Some(asset) = &*esm_ref.get_referenced_asset().await? {
+                if is_unused_module(context, *asset).await? {
+                    continue;
+                }
                 let export_info = expand_star_exports(*asset).await?;
                 let export_names = &export_info.star_exports;
                 for export in export_names.iter() {
@@ -174,6 +190,9 @@ impl CodeGenerateable for EsmExports {
             }
         }
         for (exported, local) in all_exports.into_iter() {
+            if !usage.is_used(&exported) {
+                continue;
+            }
             let expr = match local.as_ref() {
                 EsmExport::Error => Some(quote!(
                     "(() => { throw new Error(\"Failed binding. See build errors!\"); })" as Expr,
@@ -91,6 +91,7 @@ use crate::{
         },
         esm::{module_id::EsmModuleIdAssetReferenceVc, EsmBindingVc, EsmExportsVc},
     },
+    tree_shake::{ExportUsage, ImportUsagesVc},
     typescript::resolve::tsconfig,
     EcmascriptInputTransformsVc,
 };
@@ -100,6 +101,7 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub references: AssetReferencesVc,
     pub code_generation: CodeGenerateablesVc,
     pub exports: EcmascriptExportsVc,
+    pub import_usages: ImportUsagesVc,
 }
 
 /// A temporary analysis result builder to pass around, to be turned into an
@@ -108,6 +110,7 @@ pub(crate) struct AnalyzeEcmascriptModuleResultBuilder {
     references: Vec<AssetReferenceVc>,
     code_gens: Vec<CodeGenerateableVc>,
     exports: EcmascriptExports,
+    import_usages: Vec<(EsmAssetReferenceVc, ExportUsage)>,
 }
 
 impl AnalyzeEcmascriptModuleResultBuilder {
@@ -116,6 +119,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             references: Vec::new(),
             code_gens: Vec::new(),
             exports: EcmascriptExports::None,
+            import_usages: Vec::new(),
         }
     }
 
@@ -135,6 +139,12 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.code_gens.push(code_gen.into());
     }
 
+    /// Records which exports of the module referenced by an ESM import are
+    /// used by the module.
+    pub fn add_import_usage(&mut self, reference: EsmAssetReferenceVc, usage: ExportUsage) {
+        self.import_usages.push((reference, usage));
+    }
+
     /// Sets the analysis result ES export.
     pub fn set_exports(&mut self, exports: EcmascriptExports) {
         self.exports = exports;
@@ -154,6 +164,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
                 references: AssetReferencesVc::cell(self.references),
                 code_generation: CodeGenerateablesVc::cell(self.code_gens),
                 exports: self.exports.into(),
+                import_usages: ImportUsagesVc::cell(self.import_usages),
             },
         ))
     }
@@ -297,6 +308,12 @@ pub(crate) async fn analyze_ecmascript_module(
                 *r = r.resolve().await?;
                 analysis.add_reference(*r);
             }
+            for (r, symbols) in import_references
+                .iter()
+                .zip(eval_context.imports.imported_symbols())
+            {
+                analysis.add_import_usage(*r, symbols.map_or(ExportUsage::All, ExportUsage::Named));
+            }
 
             let (
                 mut var_graph,
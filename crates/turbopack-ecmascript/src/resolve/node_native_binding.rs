@@ -131,7 +131,10 @@ pub async fn resolve_node_pre_gyp_files(
                     for (_, entry) in config_file_dir
                         .join(native_binding_path.as_ref())
                         .read_glob(
-                            GlobVc::new(format!("*.{}", compile_target.dylib_ext()).as_str()),
+                            // Also include versioned libraries, e. g. `libfoo.so.1`
+                            GlobVc::new(
+                                format!("*.{}{{,.*}}", compile_target.dylib_ext()).as_str(),
+                            ),
                             false,
                         )
                         .await?
@@ -0,0 +1,387 @@
+pub(crate) mod unused_declarations;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::Result;
+use turbo_tasks::primitives::BoolVc;
+use turbo_tasks_fs::{glob::Glob, FileJsonContent};
+use turbopack_core::{
+    asset::{Asset, AssetVc, AssetsVc},
+    chunk::ChunkingContextVc,
+    resolve::{find_context_file, package_json, FindContextFileResult},
+};
+
+use crate::{
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
+    references::esm::{base::ReferencedAsset, export::EsmExport, EsmAssetReferenceVc},
+    EcmascriptModuleAssetVc,
+};
+
+/// Describes which exports of a module are used.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Hash)]
+pub enum ExportUsage {
+    /// Every export could be used, e.g. because the namespace object is
+    /// used.
+    All,
+    /// Only the listed exports are used. An empty list means that the
+    /// module is only imported for its side effects.
+    Named(BTreeSet<String>),
+}
+
+impl ExportUsage {
+    pub fn none() -> Self {
+        ExportUsage::Named(BTreeSet::new())
+    }
+
+    pub fn is_used(&self, export: &str) -> bool {
+        match self {
+            ExportUsage::All => true,
+            ExportUsage::Named(names) => names.contains(export),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ExportUsage::All => false,
+            ExportUsage::Named(names) => names.is_empty(),
+        }
+    }
+
+    /// Adds the exports used by `other`. Returns true when this changed the
+    /// usage.
+    fn merge(&mut self, other: &ExportUsage) -> bool {
+        match (&mut *self, other) {
+            (ExportUsage::All, _) => false,
+            (_, ExportUsage::All) => {
+                *self = ExportUsage::All;
+                true
+            }
+            (ExportUsage::Named(names), ExportUsage::Named(other)) => {
+                let len = names.len();
+                names.extend(other.iter().cloned());
+                names.len() != len
+            }
+        }
+    }
+}
+
+/// The [ExportUsage] of the module referenced by each ESM import of a module.
+#[turbo_tasks::value(transparent)]
+pub struct ImportUsages(Vec<(EsmAssetReferenceVc, ExportUsage)>);
+
+/// The result of tree shaking for a single module.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum ModuleExportUsage {
+    /// The module is side effect free and none of its exports are used, so it
+    /// doesn't need to be included at all.
+    Unused,
+    Used(ExportUsage),
+}
+
+#[turbo_tasks::value(shared)]
+struct UsedExports {
+    /// Modules which are included in the output and the exports that are
+    /// used from them.
+    used: HashMap<EcmascriptChunkPlaceableVc, ExportUsage>,
+    /// Modules which are side effect free and unused.
+    unused: HashSet<EcmascriptChunkPlaceableVc>,
+}
+
+/// Returns the exports of `module` that are used within the module graph of
+/// the tree shaking entries of `context`. Modules outside of that module graph
+/// and all modules when tree shaking is disabled are considered fully used.
+#[turbo_tasks::function]
+pub async fn export_usage(
+    context: ChunkingContextVc,
+    module: EcmascriptChunkPlaceableVc,
+) -> Result<ModuleExportUsageVc> {
+    let entries = context.tree_shaking_entries();
+    if entries.await?.is_empty() {
+        return Ok(ModuleExportUsage::Used(ExportUsage::All).cell());
+    }
+    let used_exports = used_exports(entries).await?;
+    let module = module.resolve().await?;
+    Ok(if let Some(usage) = used_exports.used.get(&module) {
+        ModuleExportUsage::Used(usage.clone())
+    } else if used_exports.unused.contains(&module) {
+        ModuleExportUsage::Unused
+    } else {
+        ModuleExportUsage::Used(ExportUsage::All)
+    }
+    .cell())
+}
+
+/// Checks if `module` can be left out of the chunks, because it's side effect
+/// free and none of its exports are used. Only side effect free modules need
+/// the used exports of the whole module graph, and only when tree shaking is
+/// enabled, so other modules don't depend on the module graph, which would
+/// invalidate them whenever any module changes.
+pub(crate) async fn is_unused_module(
+    context: ChunkingContextVc,
+    module: EcmascriptChunkPlaceableVc,
+) -> Result<bool> {
+    if context.tree_shaking_entries().await?.is_empty() || *has_side_effects(module).await? {
+        return Ok(false);
+    }
+    Ok(matches!(
+        *export_usage(context, module).await?,
+        ModuleExportUsage::Unused
+    ))
+}
+
+#[derive(Default)]
+struct UsedExportsState {
+    used: HashMap<EcmascriptChunkPlaceableVc, ExportUsage>,
+    side_effects: HashMap<EcmascriptChunkPlaceableVc, bool>,
+    included: HashSet<EcmascriptChunkPlaceableVc>,
+    visited_assets: HashSet<AssetVc>,
+    queue: Vec<(EcmascriptChunkPlaceableVc, ExportUsage)>,
+}
+
+impl UsedExportsState {
+    /// Queues additional usage of a module.
+    fn add_usage(&mut self, module: EcmascriptChunkPlaceableVc, usage: ExportUsage) {
+        self.queue.push((module, usage));
+    }
+
+    /// Walks all references of an asset which is not an ecmascript module and
+    /// marks all ecmascript modules reachable from it as fully used.
+    async fn add_asset(&mut self, asset: AssetVc) -> Result<()> {
+        let asset = asset.resolve().await?;
+        if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+            self.add_usage(placeable, ExportUsage::All);
+            return Ok(());
+        }
+        if !self.visited_assets.insert(asset) {
+            return Ok(());
+        }
+        let mut queue = vec![asset];
+        while let Some(asset) = queue.pop() {
+            for reference in asset.references().await?.iter() {
+                for &asset in reference.resolve_reference().primary_assets().await?.iter() {
+                    let asset = asset.resolve().await?;
+                    if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await?
+                    {
+                        self.add_usage(placeable, ExportUsage::All);
+                    } else if self.visited_assets.insert(asset) {
+                        queue.push(asset);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn has_side_effects(&mut self, module: EcmascriptChunkPlaceableVc) -> Result<bool> {
+        if let Some(&side_effects) = self.side_effects.get(&module) {
+            return Ok(side_effects);
+        }
+        let side_effects = *has_side_effects(module).await?;
+        self.side_effects.insert(module, side_effects);
+        Ok(side_effects)
+    }
+
+    /// Adds the usages caused by including a module, i.e. by executing its
+    /// imports.
+    async fn include(&mut self, module: EcmascriptChunkPlaceableVc) -> Result<()> {
+        if !self.included.insert(module) {
+            return Ok(());
+        }
+        if let Some(esm_module) = EcmascriptModuleAssetVc::resolve_from(module).await? {
+            let analysis = esm_module.analyze().await?;
+            let import_usages = analysis.import_usages.await?;
+            let mut esm_references = HashSet::new();
+            for (reference, usage) in import_usages.iter() {
+                esm_references.insert(reference.resolve().await?.into());
+                if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                    self.add_usage(*asset, usage.clone());
+                }
+            }
+            for &reference in analysis.references.await?.iter() {
+                if esm_references.contains(&reference.resolve().await?) {
+                    continue;
+                }
+                for &asset in reference.resolve_reference().primary_assets().await?.iter() {
+                    self.add_asset(asset).await?;
+                }
+            }
+        } else {
+            for reference in module.references().await?.iter() {
+                for &asset in reference.resolve_reference().primary_assets().await?.iter() {
+                    self.add_asset(asset).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the usages caused by re-exports of a module.
+    async fn add_reexport_usages(
+        &mut self,
+        module: EcmascriptChunkPlaceableVc,
+        usage: &ExportUsage,
+    ) -> Result<()> {
+        let exports = module.get_exports().await?;
+        let EcmascriptExports::EsmExports(exports) = &*exports else {
+            return Ok(());
+        };
+        let exports = exports.await?;
+        for (name, export) in exports.exports.iter() {
+            if !usage.is_used(name) {
+                continue;
+            }
+            let (reference, usage) = match export {
+                EsmExport::ImportedBinding(reference, imported) => (
+                    reference,
+                    ExportUsage::Named(BTreeSet::from([imported.clone()])),
+                ),
+                EsmExport::ImportedNamespace(reference) => (reference, ExportUsage::All),
+                EsmExport::LocalBinding(_) | EsmExport::Error => continue,
+            };
+            if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                self.add_usage(*asset, usage);
+            }
+        }
+        // Exports that are not defined by the module itself could come from any
+        // of the star exports.
+        let star_usage = match usage {
+            ExportUsage::All => ExportUsage::All,
+            ExportUsage::Named(names) => ExportUsage::Named(
+                names
+                    .iter()
+                    .filter(|name| !exports.exports.contains_key(*name) && *name != "default")
+                    .cloned()
+                    .collect(),
+            ),
+        };
+        for reference in exports.star_exports.iter() {
+            if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                self.add_usage(*asset, star_usage.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_queue(&mut self) -> Result<()> {
+        while let Some((module, usage)) = self.queue.pop() {
+            let module = module.resolve().await?;
+            let (changed, usage) = match self.used.get_mut(&module) {
+                Some(current) => (current.merge(&usage), current.clone()),
+                None => {
+                    self.used.insert(module, usage.clone());
+                    (true, usage)
+                }
+            };
+            if !changed {
+                continue;
+            }
+            if !usage.is_empty() || self.has_side_effects(module).await? {
+                self.include(module).await?;
+            }
+            self.add_reexport_usages(module, &usage).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the used exports of all modules in the module graph of `entries`.
+/// Entries are considered to be fully used.
+#[turbo_tasks::function]
+async fn used_exports(entries: AssetsVc) -> Result<UsedExportsVc> {
+    let mut state = UsedExportsState::default();
+    for &entry in entries.await?.iter() {
+        state.add_asset(entry).await?;
+    }
+    state.process_queue().await?;
+
+    let UsedExportsState { used, included, .. } = state;
+    let (used, unused) = used
+        .into_iter()
+        .partition::<HashMap<_, _>, _>(|(module, _)| included.contains(module));
+    Ok(UsedExports {
+        used,
+        unused: unused.into_keys().collect(),
+    }
+    .cell())
+}
+
+/// Checks if a module could have side effects according to the `sideEffects`
+/// field of its package.json.
+#[turbo_tasks::function]
+async fn has_side_effects(module: EcmascriptChunkPlaceableVc) -> Result<BoolVc> {
+    let path = module.path();
+    let FindContextFileResult::Found(package_json_path, _) =
+        &*find_context_file(path.parent(), package_json()).await?
+    else {
+        return Ok(BoolVc::cell(true));
+    };
+    let FileJsonContent::Content(package_json) = &*package_json_path.read_json().await? else {
+        return Ok(BoolVc::cell(true));
+    };
+    Ok(BoolVc::cell(match package_json.get("sideEffects") {
+        Some(serde_json::Value::Bool(side_effects)) => *side_effects,
+        Some(serde_json::Value::Array(patterns)) => {
+            let package_dir = package_json_path.parent().await?;
+            let path = path.await?;
+            if let Some(path) = package_dir.get_path_to(&path) {
+                let patterns = patterns
+                    .iter()
+                    .filter_map(|p| p.as_str())
+                    .collect::<Vec<_>>();
+                side_effects_patterns_match(&patterns, path)?
+            } else {
+                true
+            }
+        }
+        _ => true,
+    }))
+}
+
+/// Matches a path relative to the package against the patterns in the
+/// `sideEffects` field. Patterns without a slash match the file name in any
+/// directory.
+fn side_effects_patterns_match(patterns: &[&str], path: &str) -> Result<bool> {
+    for pattern in patterns {
+        let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+        let glob = if pattern.contains('/') {
+            Glob::parse(pattern)?
+        } else {
+            Glob::parse(&format!("**/{pattern}"))?
+        };
+        if glob.execute(path) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{side_effects_patterns_match, ExportUsage};
+
+    #[test]
+    fn merge_usage() {
+        let mut usage = ExportUsage::none();
+        assert!(usage.is_empty());
+        assert!(usage.merge(&ExportUsage::Named(BTreeSet::from(["a".to_string()]))));
+        assert!(!usage.merge(&ExportUsage::Named(BTreeSet::from(["a".to_string()]))));
+        assert!(usage.is_used("a"));
+        assert!(!usage.is_used("b"));
+        assert!(usage.merge(&ExportUsage::All));
+        assert!(usage.is_used("b"));
+        assert!(!usage.merge(&ExportUsage::none()));
+    }
+
+    #[test]
+    fn side_effects_patterns() {
+        let patterns = ["*.css", "./src/polyfill.js"];
+        assert!(side_effects_patterns_match(&patterns, "dist/index.css").unwrap());
+        assert!(side_effects_patterns_match(&patterns, "index.css").unwrap());
+        assert!(side_effects_patterns_match(&patterns, "src/polyfill.js").unwrap());
+        assert!(!side_effects_patterns_match(&patterns, "src/index.js").unwrap());
+    }
+}
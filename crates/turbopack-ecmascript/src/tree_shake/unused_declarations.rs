@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use swc_core::ecma::{
+    ast::{
+        ClassMember, Decl, Expr, Ident, Module, ModuleItem, ObjectPatProp, Pat, Program, Prop,
+        PropOrSpread, Stmt, VarDeclarator,
+    },
+    atoms::JsWord,
+    visit::{Visit, VisitWith},
+};
+
+/// Removes top level declarations of a module which are never referenced and
+/// whose evaluation has no side effects. This is used to remove the code of
+/// exports which have been removed by tree shaking.
+///
+/// Identifiers are compared by name only, which keeps declarations around
+/// when a property or a shadowing binding has the same name.
+pub(crate) fn remove_unused_declarations(program: &mut Program) {
+    let Program::Module(Module { body, .. }) = program else {
+        return;
+    };
+    loop {
+        let mut counter = IdentCounter::default();
+        for item in body.iter() {
+            item.visit_with(&mut counter);
+        }
+        let len = body.len();
+        body.retain(|item| {
+            let ModuleItem::Stmt(Stmt::Decl(decl)) = item else {
+                return true;
+            };
+            let Some(names) = removable_declaration_names(decl) else {
+                return true;
+            };
+            // Each declared name is counted once for its declaration.
+            names
+                .iter()
+                .any(|name| counter.counts.get(*name).copied().unwrap_or_default() > 1)
+        });
+        if body.len() == len {
+            break;
+        }
+    }
+}
+
+#[derive(Default)]
+struct IdentCounter {
+    counts: HashMap<JsWord, usize>,
+}
+
+impl Visit for IdentCounter {
+    fn visit_ident(&mut self, ident: &Ident) {
+        *self.counts.entry(ident.sym.clone()).or_default() += 1;
+    }
+}
+
+/// Returns the names declared by a declaration, or `None` if the declaration
+/// can't be removed because evaluating it could have side effects.
+fn removable_declaration_names(decl: &Decl) -> Option<Vec<&JsWord>> {
+    match decl {
+        Decl::Fn(fn_decl) => Some(vec![&fn_decl.ident.sym]),
+        Decl::Class(class_decl) => {
+            let class = &class_decl.class;
+            let pure = class.decorators.is_empty()
+                && class
+                    .super_class
+                    .as_deref()
+                    .map_or(true, |super_class| matches!(super_class, Expr::Ident(_)))
+                && class.body.iter().all(|member| match member {
+                    ClassMember::Constructor(_) | ClassMember::Method(_) => true,
+                    ClassMember::ClassProp(prop) => !prop.is_static && !prop.key.is_computed(),
+                    ClassMember::PrivateMethod(_) => true,
+                    ClassMember::PrivateProp(prop) => !prop.is_static,
+                    ClassMember::Empty(_) => true,
+                    _ => false,
+                });
+            pure.then(|| vec![&class_decl.ident.sym])
+        }
+        Decl::Var(var_decl) => {
+            let mut names = Vec::new();
+            for decl in var_decl.decls.iter() {
+                let VarDeclarator { name, init, .. } = decl;
+                if !init.as_deref().map_or(true, is_pure_expr) {
+                    return None;
+                }
+                if !pat_names(name, &mut names) {
+                    return None;
+                }
+            }
+            Some(names)
+        }
+        _ => None,
+    }
+}
+
+/// Collects the names bound by a pattern. Returns false for patterns which
+/// could have side effects, like default values or computed keys.
+fn pat_names<'a>(pat: &'a Pat, names: &mut Vec<&'a JsWord>) -> bool {
+    match pat {
+        Pat::Ident(ident) => {
+            names.push(&ident.id.sym);
+            true
+        }
+        Pat::Array(array) => array
+            .elems
+            .iter()
+            .flatten()
+            .all(|elem| pat_names(elem, names)),
+        Pat::Object(object) => object.props.iter().all(|prop| match prop {
+            ObjectPatProp::KeyValue(kv) => !kv.key.is_computed() && pat_names(&kv.value, names),
+            ObjectPatProp::Assign(assign) => {
+                names.push(&assign.key.sym);
+                assign.value.is_none()
+            }
+            ObjectPatProp::Rest(rest) => pat_names(&rest.arg, names),
+        }),
+        Pat::Rest(rest) => pat_names(&rest.arg, names),
+        _ => false,
+    }
+}
+
+/// Checks if evaluating an expression has no side effects.
+fn is_pure_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) => true,
+        Expr::Fn(_) | Expr::Arrow(_) => true,
+        Expr::Paren(paren) => is_pure_expr(&paren.expr),
+        Expr::Tpl(tpl) => tpl.exprs.iter().all(|expr| is_pure_expr(expr)),
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .flatten()
+            .all(|elem| elem.spread.is_none() && is_pure_expr(&elem.expr)),
+        Expr::Object(object) => object.props.iter().all(|prop| match prop {
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::KeyValue(kv) => !kv.key.is_computed() && is_pure_expr(&kv.value),
+                Prop::Method(method) => !method.key.is_computed(),
+                Prop::Getter(getter) => !getter.key.is_computed(),
+                Prop::Setter(setter) => !setter.key.is_computed(),
+                _ => false,
+            },
+            PropOrSpread::Spread(_) => false,
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        common::{input::StringInput, FileName, SourceMap},
+        ecma::{
+            ast::EsVersion,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{lexer::Lexer, Parser, Syntax},
+        },
+    };
+
+    use super::remove_unused_declarations;
+
+    fn transform(code: &str) -> String {
+        let cm = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Anon, code.to_string());
+        let lexer = Lexer::new(
+            Syntax::default(),
+            EsVersion::latest(),
+            StringInput::from(&*fm),
+            None,
+        );
+        let mut program = Parser::new_from(lexer).parse_program().unwrap();
+        remove_unused_declarations(&mut program);
+
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm, "\n", &mut bytes, None),
+        };
+        emitter.emit_program(&program).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn removes_unused_declarations() {
+        let output = transform(
+            "import 'x'; function a() { return b; } const b = 1; const c = () => d; let d = {}; \
+             use(c);",
+        );
+        assert!(!output.contains("function a"));
+        assert!(!output.contains("b=1"));
+        assert!(output.contains("import"));
+        assert!(output.contains("const c="));
+        assert!(output.contains("let d="));
+    }
+
+    #[test]
+    fn keeps_side_effects() {
+        let output = transform("const a = sideEffect(); class B { static x = init(); }");
+        assert!(output.contains("const a="));
+        assert!(output.contains("class B"));
+    }
+}
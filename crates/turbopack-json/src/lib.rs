@@ -43,6 +43,11 @@ impl Asset for JsonModuleAsset {
         self.source.path()
     }
 
+    #[turbo_tasks::function]
+    fn ident(&self) -> StringVc {
+        self.source.ident()
+    }
+
     #[turbo_tasks::function]
     fn content(&self) -> AssetContentVc {
         self.source.content()
@@ -84,7 +89,7 @@ impl ValueToString for JsonChunkItem {
     async fn to_string(&self) -> Result<StringVc> {
         Ok(StringVc::cell(format!(
             "{} (json)",
-            self.module.await?.source.path().to_string().await?
+            self.module.await?.source.ident().await?
         )))
     }
 }
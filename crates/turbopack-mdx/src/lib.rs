@@ -93,6 +93,11 @@ impl Asset for MdxModuleAsset {
         self.source.path()
     }
 
+    #[turbo_tasks::function]
+    fn ident(&self) -> StringVc {
+        self.source.ident()
+    }
+
     #[turbo_tasks::function]
     fn content(&self) -> AssetContentVc {
         self.source.content()
@@ -152,7 +157,7 @@ impl ValueToString for MdxChunkItem {
     async fn to_string(&self) -> Result<StringVc> {
         Ok(StringVc::cell(format!(
             "{} (mdx)",
-            self.module.await?.source.path().to_string().await?
+            self.module.await?.source.ident().await?
         )))
     }
 }
@@ -11,6 +11,13 @@ type IpcOutgoingMessage =
       type: "jsonValue";
       data: string;
     }
+  | {
+      type: "bodyChunk";
+      data: number[];
+    }
+  | {
+      type: "bodyEnd";
+    }
   | {
       type: "fileDependency";
       path: string;
@@ -38,10 +45,27 @@ export const run = async (
       case "evaluate": {
         try {
           const value = await getValue(ipc, ...msg.args);
-          await ipc.send({
-            type: "jsonValue",
-            data: JSON.stringify(value),
-          });
+          if (
+            value != null &&
+            typeof value[Symbol.asyncIterator] === "function"
+          ) {
+            // Async iterators are streamed chunk by chunk. The next chunk is
+            // only produced after the previous one has been written.
+            for await (const chunk of value) {
+              await ipc.send({
+                type: "bodyChunk",
+                data: Buffer.from(chunk).toJSON().data,
+              });
+            }
+            await ipc.send({
+              type: "bodyEnd",
+            });
+          } else {
+            await ipc.send({
+              type: "jsonValue",
+              data: JSON.stringify(value),
+            });
+          }
         } catch (e) {
           await ipc.sendError(e as Error);
         }
@@ -1,12 +1,15 @@
-use std::{borrow::Cow, collections::HashMap, thread::available_parallelism};
+use std::{borrow::Cow, collections::HashMap, mem::take, thread::available_parallelism};
 
 use anyhow::Result;
+use futures::{stream, StreamExt};
 use turbo_tasks::{
     primitives::{JsonValueVc, StringVc},
-    CompletionVc, TryJoinIterExt, Value, ValueToString,
+    turbo_tasks, CompletionVc, TryJoinIterExt, Value, ValueToString,
 };
 use turbo_tasks_fs::{
-    glob::GlobVc, rope::Rope, to_sys_path, DirectoryEntry, File, FileSystemPathVc, ReadGlobResultVc,
+    glob::GlobVc,
+    rope::{Rope, RopeBuilder},
+    to_sys_path, DirectoryEntry, File, FileSystemPathVc, ReadGlobResultVc,
 };
 use turbopack_core::{
     asset::AssetVc,
@@ -16,6 +19,7 @@ use turbopack_core::{
     source_asset::SourceAssetVc,
     virtual_asset::VirtualAssetVc,
 };
+use turbopack_dev_server::source::stream::BodyStream;
 use turbopack_ecmascript::{
     chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
     EcmascriptModuleAssetType, EcmascriptModuleAssetVc, InnerAssetsVc,
@@ -25,7 +29,8 @@ use crate::{
     bootstrap::NodeJsBootstrapAsset,
     embed_js::embed_file_path,
     emit,
-    pool::{NodeJsPool, NodeJsPoolVc},
+    pool::{NodeJsOperation, NodeJsPool, NodeJsPoolVc},
+    stream::operation_body,
     EvalJavaScriptIncomingMessage, EvalJavaScriptOutgoingMessage, StructuredError,
 };
 
@@ -34,8 +39,26 @@ use crate::{
 pub enum JavaScriptValue {
     Error,
     Value(Rope),
-    // TODO, support stream in the future
-    Stream(#[turbo_tasks(trace_ignore)] Vec<u8>),
+    /// The chunks that the evaluated function streamed, e. g. when it returned
+    /// an async iterator, buffered until the stream ended. Use
+    /// [evaluate_stream] to read them while they are produced.
+    Stream(Rope),
+}
+
+/// The result of [evaluate_stream].
+pub enum StreamedJavaScriptValue {
+    Error,
+    Value(Rope),
+    /// A value that is read from the process on demand. It can only be read
+    /// once.
+    Stream(BodyStream),
+}
+
+/// The result of an evaluation before a streamed value has been read.
+enum EvaluationOutput {
+    Value(JavaScriptValue),
+    /// The value is streamed, starting with the first chunk.
+    Stream(Vec<u8>),
 }
 
 #[turbo_tasks::function]
@@ -140,6 +163,92 @@ pub async fn evaluate(
         debug,
     )
     .await?;
+    let one_off = args.is_empty();
+    // The result is cached, so a streamed value is buffered until it ended
+    let (operation, output) =
+        start_evaluation(&pool, cwd, context_path_for_issue, args, true).await?;
+    let EvaluationOutput::Value(value) = output else {
+        unreachable!("streamed values are buffered");
+    };
+    if one_off {
+        // Assume this is a one-off operation, so we can kill the process
+        // TODO use a better way to decide that.
+        operation.wait_or_kill().await?;
+    }
+    Ok(value.cell())
+}
+
+/// Like [evaluate], but a streamed value is read from the process while it is
+/// produced. This isn't cached, since a stream can only be read once, so it
+/// needs to be called for each use of the value, e. g. for each request.
+pub async fn evaluate_stream(
+    context_path: FileSystemPathVc,
+    module_asset: AssetVc,
+    cwd: FileSystemPathVc,
+    context_path_for_issue: FileSystemPathVc,
+    context: AssetContextVc,
+    intermediate_output_path: FileSystemPathVc,
+    runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+    args: Vec<JsonValueVc>,
+    debug: bool,
+) -> Result<StreamedJavaScriptValue> {
+    let pool = get_evaluate_pool(
+        context_path,
+        module_asset,
+        cwd,
+        context,
+        intermediate_output_path,
+        runtime_entries,
+        debug,
+    )
+    .await?;
+    let one_off = args.is_empty();
+    let (operation, output) =
+        start_evaluation(&pool, cwd, context_path_for_issue, args, false).await?;
+    Ok(match output {
+        EvaluationOutput::Value(value) => {
+            if one_off {
+                operation.wait_or_kill().await?;
+            }
+            match value {
+                JavaScriptValue::Error => StreamedJavaScriptValue::Error,
+                JavaScriptValue::Value(value) => StreamedJavaScriptValue::Value(value),
+                // Without buffering, a stream only ends here when it had no chunks
+                JavaScriptValue::Stream(_) => {
+                    StreamedJavaScriptValue::Stream(BodyStream::new(stream::empty()))
+                }
+            }
+        }
+        EvaluationOutput::Stream(first_chunk) => {
+            // The remaining chunks are received while the value is read
+            let rest = operation_body(turbo_tasks(), operation, move |error| async move {
+                let message = error.print(Default::default(), None).await?;
+                let issue = EvaluationIssue {
+                    error,
+                    context_path: context_path_for_issue,
+                }
+                .cell()
+                .as_issue();
+                Ok((message, issue))
+            });
+            StreamedJavaScriptValue::Stream(BodyStream::new(
+                stream::once(async move { Ok(first_chunk.into()) }).chain(rest.read()),
+            ))
+        }
+    })
+}
+
+/// Sends the arguments to a process of the pool and receives messages until
+/// it responded with a value. When `buffer` is false, it stops at the first
+/// chunk of a streamed value instead. Dependencies reported meanwhile are read
+/// to make them dependencies of the current task.
+async fn start_evaluation(
+    pool: &NodeJsPool,
+    cwd: FileSystemPathVc,
+    context_path_for_issue: FileSystemPathVc,
+    args: Vec<JsonValueVc>,
+    buffer: bool,
+) -> Result<(NodeJsOperation, EvaluationOutput)> {
     let mut operation = pool.operation().await?;
     let args = args.into_iter().try_join().await?;
     operation
@@ -149,6 +258,7 @@ pub async fn evaluate(
         .await?;
     let mut file_dependencies = Vec::new();
     let mut dir_dependencies = Vec::new();
+    let mut body = RopeBuilder::default();
     let output = loop {
         match operation.recv().await? {
             EvalJavaScriptIncomingMessage::Error(error) => {
@@ -159,10 +269,19 @@ pub async fn evaluate(
                 .cell()
                 .as_issue()
                 .emit();
-                break JavaScriptValue::Error;
+                break EvaluationOutput::Value(JavaScriptValue::Error);
             }
             EvalJavaScriptIncomingMessage::JsonValue { data } => {
-                break JavaScriptValue::Value(data.into())
+                break EvaluationOutput::Value(JavaScriptValue::Value(data.into()))
+            }
+            EvalJavaScriptIncomingMessage::BodyChunk { data } => {
+                if !buffer {
+                    break EvaluationOutput::Stream(data);
+                }
+                body.push_bytes(&data);
+            }
+            EvalJavaScriptIncomingMessage::BodyEnd => {
+                break EvaluationOutput::Value(JavaScriptValue::Stream(take(&mut body).build()))
             }
             EvalJavaScriptIncomingMessage::FileDependency { path } => {
                 // TODO We might miss some changes that happened during execution
@@ -194,12 +313,7 @@ pub async fn evaluate(
     for dep in dir_dependencies {
         dep.await?;
     }
-    if args.is_empty() {
-        // Assume this is a one-off operation, so we can kill the process
-        // TODO use a better way to decide that.
-        operation.wait_or_kill().await?;
-    }
-    Ok(output.cell())
+    Ok((operation, output))
 }
 
 /// An issue that occurred while evaluating node code.
@@ -40,6 +40,7 @@ mod node_entry;
 mod pool;
 pub mod render;
 pub mod source_map;
+mod stream;
 pub mod transforms;
 
 #[turbo_tasks::function]
@@ -249,6 +250,8 @@ enum EvalJavaScriptIncomingMessage {
     BuildDependency { path: String },
     DirDependency { path: String, glob: String },
     JsonValue { data: String },
+    BodyChunk { data: Vec<u8> },
+    BodyEnd,
     Error(StructuredError),
 }
 
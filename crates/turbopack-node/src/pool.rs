@@ -233,6 +233,7 @@ impl NodeJsPool {
 
         Ok(NodeJsOperation {
             process: Some(process.run().await?),
+            busy: false,
             permit,
             processes: self.processes.clone(),
         })
@@ -241,6 +242,11 @@ impl NodeJsPool {
 
 pub struct NodeJsOperation {
     process: Option<RunningNodeJsPoolProcess>,
+    /// A message is being sent or received. An operation that is dropped while
+    /// busy has been cancelled, e. g. because the task running it has been
+    /// invalidated. The process might still be working on it, so it can't be
+    /// reused.
+    busy: bool,
     // This is used for drop
     #[allow(dead_code)]
     permit: OwnedSemaphorePermit,
@@ -258,11 +264,10 @@ impl NodeJsOperation {
     where
         M: DeserializeOwned,
     {
-        let message = self
-            .process_mut()?
-            .recv()
-            .await
-            .context("receiving message")?;
+        self.busy = true;
+        let message = self.process_mut()?.recv().await;
+        self.busy = false;
+        let message = message.context("receiving message")?;
         serde_json::from_slice(&message).context("deserializing message")
     }
 
@@ -270,10 +275,11 @@ impl NodeJsOperation {
     where
         M: Serialize,
     {
-        self.process_mut()?
-            .send(serde_json::to_vec(&message).context("serializing message")?)
-            .await
-            .context("sending message")
+        let message = serde_json::to_vec(&message).context("serializing message")?;
+        self.busy = true;
+        let result = self.process_mut()?.send(message).await;
+        self.busy = false;
+        result.context("sending message")
     }
 
     pub async fn wait_or_kill(mut self) -> Result<ExitStatus> {
@@ -293,11 +299,22 @@ impl NodeJsOperation {
 
         Ok(status)
     }
+
+    /// Kills the process without waiting for it, e. g. when a streamed
+    /// response is dropped while the process is still sending it.
+    pub fn kill(mut self) {
+        // Dropping the process kills it
+        self.process.take();
+    }
 }
 
 impl Drop for NodeJsOperation {
     fn drop(&mut self) {
         if let Some(process) = self.process.take() {
+            if self.busy {
+                // Dropping the process kills it
+                return;
+            }
             self.processes
                 .lock()
                 .unwrap()
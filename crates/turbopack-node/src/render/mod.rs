@@ -2,9 +2,15 @@ use std::collections::BTreeMap;
 
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use turbopack_dev_server::source::{query::Query, HeaderValue};
+use turbo_tasks::{primitives::StringVc, turbo_tasks};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::asset::AssetVc;
+use turbopack_dev_server::source::{query::Query, stream::BodyStream, HeaderValue};
 
-use crate::{ResponseHeaders, StructuredError};
+use self::issue::RenderingIssue;
+use crate::{
+    pool::NodeJsOperation, stream::operation_body, trace_stack, ResponseHeaders, StructuredError,
+};
 
 pub mod issue;
 pub mod node_api_source;
@@ -40,14 +46,19 @@ enum RenderProxyOutgoingMessage<'a> {
 #[serde(tag = "type", rename_all = "camelCase")]
 enum RenderProxyIncomingMessage {
     Headers { data: ResponseHeaders },
-    Body { data: Vec<u8> },
     Error(StructuredError),
 }
 
 #[derive(Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 enum RenderStaticIncomingMessage {
-    Result { result: RenderResult },
+    Result {
+        result: RenderResult,
+    },
+    /// The response is streamed. The body follows in separate messages.
+    Headers {
+        data: ResponseHeaders,
+    },
     Error(StructuredError),
 }
 
@@ -61,3 +72,24 @@ pub enum RenderResult {
         content_type: Option<String>,
     },
 }
+
+/// Streams the rendered body from the operation while it's sent to the
+/// client. Errors during rendering are reported as [RenderingIssue]s.
+fn render_body(
+    path: FileSystemPathVc,
+    operation: NodeJsOperation,
+    intermediate_asset: AssetVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> BodyStream {
+    operation_body(turbo_tasks(), operation, move |error| async move {
+        let message = trace_stack(error, intermediate_asset, intermediate_output_path).await?;
+        let issue = RenderingIssue {
+            context: path,
+            message: StringVc::cell(message.clone()),
+            status: None,
+        }
+        .cell()
+        .as_issue();
+        Ok((message, issue))
+    })
+}
@@ -126,7 +126,7 @@ impl GetContentSourceContent for NodeApiGetContentResult {
             return Err(anyhow!("Missing request data"));
         };
         let entry = this.entry.entry(data.clone()).await?;
-        Ok(ContentSourceContent::HttpProxy(render_proxy(
+        Ok(ContentSourceContent::Stream(render_proxy(
             this.server_root.join(&self.path),
             entry.module,
             this.runtime_entries,
@@ -2,16 +2,20 @@ use anyhow::{bail, Result};
 use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{asset::AssetVc, chunk::ChunkingContextVc};
-use turbopack_dev_server::source::{BodyVc, ProxyResult, ProxyResultVc};
+use turbopack_dev_server::source::{
+    stream::{BodyStream, StreamedResponse, StreamedResponseVc},
+    BodyVc,
+};
 use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc};
 
 use super::{
-    issue::RenderingIssue, RenderDataVc, RenderProxyIncomingMessage, RenderProxyOutgoingMessage,
-    ResponseHeaders,
+    issue::RenderingIssue, render_body, RenderDataVc, RenderProxyIncomingMessage,
+    RenderProxyOutgoingMessage, ResponseHeaders,
 };
 use crate::{get_intermediate_asset, get_renderer_pool, pool::NodeJsOperation, trace_stack};
 
-/// Renders a module as static HTML in a node.js process.
+/// Renders a module in a node.js process and streams the response body while
+/// it's produced.
 #[turbo_tasks::function]
 pub async fn render_proxy(
     path: FileSystemPathVc,
@@ -21,7 +25,7 @@ pub async fn render_proxy(
     intermediate_output_path: FileSystemPathVc,
     data: RenderDataVc,
     body: BodyVc,
-) -> Result<ProxyResultVc> {
+) -> Result<StreamedResponseVc> {
     let intermediate_asset = get_intermediate_asset(
         module.as_evaluated_chunk(chunking_context, Some(runtime_entries)),
         intermediate_output_path,
@@ -39,7 +43,7 @@ pub async fn render_proxy(
         }
     };
 
-    match run_proxy_operation(
+    match start_proxy_operation(
         &mut operation,
         data,
         body,
@@ -48,18 +52,31 @@ pub async fn render_proxy(
     )
     .await
     {
-        Ok(proxy_result) => Ok(proxy_result.cell()),
+        Ok(ResponseHeaders { status, headers }) => Ok(StreamedResponse {
+            status,
+            headers,
+            // The body is streamed from the process while it's sent to the client
+            body: render_body(
+                path,
+                operation,
+                intermediate_asset,
+                intermediate_output_path,
+            ),
+        }
+        .cell()),
         Err(err) => Ok(proxy_error(path, err, Some(operation)).await?),
     }
 }
 
-async fn run_proxy_operation(
+/// Sends the request to the Node.js process and waits for the response
+/// headers.
+async fn start_proxy_operation(
     operation: &mut NodeJsOperation,
     data: RenderDataVc,
     body: BodyVc,
     intermediate_asset: AssetVc,
     intermediate_output_path: FileSystemPathVc,
-) -> Result<ProxyResult> {
+) -> Result<ResponseHeaders> {
     let data = data.await?;
     // First, send the render data.
     operation
@@ -78,40 +95,19 @@ async fn run_proxy_operation(
 
     operation.send(RenderProxyOutgoingMessage::BodyEnd).await?;
 
-    let (status, headers) = match operation.recv().await? {
-        RenderProxyIncomingMessage::Headers {
-            data: ResponseHeaders { status, headers },
-        } => (status, headers),
-        RenderProxyIncomingMessage::Error(error) => {
-            bail!(trace_stack(error, intermediate_asset, intermediate_output_path).await?)
-        }
-        _ => {
-            bail!("unexpected response from the Node.js process while reading response headers")
-        }
-    };
-
-    let body = match operation.recv().await? {
-        RenderProxyIncomingMessage::Body { data: body } => body,
+    match operation.recv().await? {
+        RenderProxyIncomingMessage::Headers { data } => Ok(data),
         RenderProxyIncomingMessage::Error(error) => {
             bail!(trace_stack(error, intermediate_asset, intermediate_output_path).await?)
         }
-        _ => {
-            bail!("unexpected response from the Node.js process while reading response body")
-        }
-    };
-
-    Ok(ProxyResult {
-        status,
-        headers,
-        body: body.into(),
-    })
+    }
 }
 
 async fn proxy_error(
     path: FileSystemPathVc,
     error: anyhow::Error,
     operation: Option<NodeJsOperation>,
-) -> Result<ProxyResultVc> {
+) -> Result<StreamedResponseVc> {
     let message = format!("{error:?}");
 
     let status = match operation {
@@ -138,13 +134,13 @@ async fn proxy_error(
     .as_issue()
     .emit();
 
-    Ok(ProxyResult {
+    Ok(StreamedResponse {
         status: 500,
         headers: vec![
             "content-type".to_string(),
             "text/html; charset=utf-8".to_string(),
         ],
-        body: body.into(),
+        body: BodyStream::from_bytes(body),
     }
     .cell())
 }
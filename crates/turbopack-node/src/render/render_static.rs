@@ -6,16 +6,48 @@ use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::ChunkingContextVc,
 };
-use turbopack_dev_server::html::DevHtmlAssetVc;
+use turbopack_dev_server::{
+    html::DevHtmlAssetVc,
+    source::{stream::StreamedResponse, ContentSourceContent, ContentSourceContentVc},
+};
 use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc};
 
 use super::{
-    issue::RenderingIssue, RenderDataVc, RenderResult, RenderStaticIncomingMessage,
-    RenderStaticOutgoingMessage,
+    issue::RenderingIssue, render_body, RenderDataVc, RenderResult, RenderStaticIncomingMessage,
+    RenderStaticOutgoingMessage, ResponseHeaders,
 };
 use crate::{get_intermediate_asset, get_renderer_pool, pool::NodeJsOperation, trace_stack};
 
-/// Renders a module as static HTML in a node.js process.
+#[turbo_tasks::value(shared)]
+pub enum StaticResult {
+    Content(AssetContentVc),
+    /// The renderer streams the response, e. g. for a dynamic page. A stream
+    /// can only be sent once, so it needs to be rendered for each request with
+    /// [render_static_stream].
+    Streamed,
+}
+
+/// The result of a render operation before the body of a streamed response
+/// has been read.
+enum StaticOperationResult {
+    Content(AssetContentVc),
+    Streamed(ResponseHeaders),
+}
+
+/// A started render operation.
+enum StartedRender {
+    /// The rendered content or an error page.
+    Content(AssetContentVc),
+    /// The response is streamed. Its body is received from the operation.
+    Streamed {
+        headers: ResponseHeaders,
+        operation: NodeJsOperation,
+        intermediate_asset: AssetVc,
+    },
+}
+
+/// Renders a module as static HTML in a node.js process. The renderer might
+/// decide to stream the response instead.
 #[turbo_tasks::function]
 pub async fn render_static(
     path: FileSystemPathVc,
@@ -25,7 +57,84 @@ pub async fn render_static(
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
     data: RenderDataVc,
-) -> Result<AssetContentVc> {
+) -> Result<StaticResultVc> {
+    let started = start_render(
+        path,
+        module,
+        runtime_entries,
+        fallback_page,
+        chunking_context,
+        intermediate_output_path,
+        data,
+    )
+    .await?;
+    Ok(match started {
+        StartedRender::Content(content) => StaticResult::Content(content),
+        StartedRender::Streamed { operation, .. } => {
+            // The body can't be kept in this cached result, so the operation
+            // is stopped and the page is rendered again for each request
+            operation.kill();
+            StaticResult::Streamed
+        }
+    }
+    .cell())
+}
+
+/// Renders a module in a node.js process and streams the response while it's
+/// rendered. Unlike [render_static] this isn't cached, since a streamed
+/// response can only be sent once, so it needs to be called for each request.
+pub async fn render_static_stream(
+    path: FileSystemPathVc,
+    module: EcmascriptModuleAssetVc,
+    runtime_entries: EcmascriptChunkPlaceablesVc,
+    fallback_page: DevHtmlAssetVc,
+    chunking_context: ChunkingContextVc,
+    intermediate_output_path: FileSystemPathVc,
+    data: RenderDataVc,
+) -> Result<ContentSourceContentVc> {
+    let started = start_render(
+        path,
+        module,
+        runtime_entries,
+        fallback_page,
+        chunking_context,
+        intermediate_output_path,
+        data,
+    )
+    .await?;
+    Ok(match started {
+        StartedRender::Content(content) => ContentSourceContent::Static(content.into()).cell(),
+        StartedRender::Streamed {
+            headers: ResponseHeaders { status, headers },
+            operation,
+            intermediate_asset,
+        } => ContentSourceContent::Stream(
+            StreamedResponse {
+                status,
+                headers,
+                // The body is streamed from the process while it's sent to the client
+                body: render_body(
+                    path,
+                    operation,
+                    intermediate_asset,
+                    intermediate_output_path,
+                ),
+            }
+            .cell(),
+        )
+        .cell(),
+    })
+}
+
+async fn start_render(
+    path: FileSystemPathVc,
+    module: EcmascriptModuleAssetVc,
+    runtime_entries: EcmascriptChunkPlaceablesVc,
+    fallback_page: DevHtmlAssetVc,
+    chunking_context: ChunkingContextVc,
+    intermediate_output_path: FileSystemPathVc,
+    data: RenderDataVc,
+) -> Result<StartedRender> {
     let intermediate_asset = get_intermediate_asset(
         module.as_evaluated_chunk(chunking_context, Some(runtime_entries)),
         intermediate_output_path,
@@ -40,20 +149,31 @@ pub async fn render_static(
     let pool = renderer_pool.strongly_consistent().await?;
     let mut operation = match pool.operation().await {
         Ok(operation) => operation,
-        Err(err) => return static_error(path, err, None, fallback_page).await,
+        Err(err) => {
+            return Ok(StartedRender::Content(
+                static_error(path, err, None, fallback_page).await?,
+            ))
+        }
     };
 
-    match run_static_operation(
+    let result = run_static_operation(
         &mut operation,
         data,
         intermediate_asset,
         intermediate_output_path,
     )
-    .await
-    {
-        Ok(asset) => Ok(asset),
-        Err(err) => static_error(path, err, Some(operation), fallback_page).await,
-    }
+    .await;
+    Ok(match result {
+        Ok(StaticOperationResult::Content(content)) => StartedRender::Content(content),
+        Ok(StaticOperationResult::Streamed(headers)) => StartedRender::Streamed {
+            headers,
+            operation,
+            intermediate_asset,
+        },
+        Err(err) => {
+            StartedRender::Content(static_error(path, err, Some(operation), fallback_page).await?)
+        }
+    })
 }
 
 async fn run_static_operation(
@@ -61,7 +181,7 @@ async fn run_static_operation(
     data: RenderDataVc,
     intermediate_asset: AssetVc,
     intermediate_output_path: FileSystemPathVc,
-) -> Result<AssetContentVc> {
+) -> Result<StaticOperationResult> {
     let data = data.await?;
 
     operation
@@ -75,14 +195,19 @@ async fn run_static_operation(
     {
         RenderStaticIncomingMessage::Result {
             result: RenderResult::Simple(body),
-        } => Ok(FileContent::Content(File::from(body).with_content_type(TEXT_HTML_UTF_8)).into()),
+        } => Ok(StaticOperationResult::Content(
+            FileContent::Content(File::from(body).with_content_type(TEXT_HTML_UTF_8)).into(),
+        )),
         RenderStaticIncomingMessage::Result {
             result: RenderResult::Advanced { body, content_type },
-        } => Ok(FileContent::Content(
-            File::from(body)
-                .with_content_type(content_type.map_or(Ok(TEXT_HTML_UTF_8), |c| c.parse())?),
-        )
-        .into()),
+        } => Ok(StaticOperationResult::Content(
+            FileContent::Content(
+                File::from(body)
+                    .with_content_type(content_type.map_or(Ok(TEXT_HTML_UTF_8), |c| c.parse())?),
+            )
+            .into(),
+        )),
+        RenderStaticIncomingMessage::Headers { data } => Ok(StaticOperationResult::Streamed(data)),
         RenderStaticIncomingMessage::Error(error) => {
             bail!(trace_stack(error, intermediate_asset, intermediate_output_path).await?)
         }
@@ -20,12 +20,15 @@ use turbopack_dev_server::{
         ContentSource, ContentSourceContent, ContentSourceContentVc, ContentSourceData,
         ContentSourceDataFilter, ContentSourceDataVary, ContentSourceDataVaryVc,
         ContentSourceResult, ContentSourceResultVc, ContentSourceVc, GetContentSourceContent,
-        GetContentSourceContentVc,
+        GetContentSourceContentVc, NeededData,
     },
 };
 use turbopack_ecmascript::chunk::EcmascriptChunkPlaceablesVc;
 
-use super::{render_static::render_static, RenderData};
+use super::{
+    render_static::{render_static, render_static_stream, StaticResult},
+    RenderData,
+};
 use crate::{
     external_asset_entrypoints, get_intermediate_asset, match_params::MatchParamsVc,
     node_entry::NodeEntryVc,
@@ -142,6 +145,7 @@ impl ContentSource for NodeRenderContentSource {
                 get_content: NodeRenderGetContentResult {
                     source: self_vc,
                     path: path.to_string(),
+                    streamed: false,
                 }
                 .cell()
                 .into(),
@@ -152,10 +156,15 @@ impl ContentSource for NodeRenderContentSource {
     }
 }
 
+/// Gets the rendered content. Results are cached by the request data unless
+/// the renderer streams the response, which can only be sent once. These are
+/// rendered again for each request by a [NodeRenderGetContentResult] with
+/// `streamed` set.
 #[turbo_tasks::value]
 struct NodeRenderGetContentResult {
     source: NodeRenderContentSourceVc,
     path: String,
+    streamed: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -167,14 +176,19 @@ impl GetContentSourceContent for NodeRenderGetContentResult {
             url: true,
             headers: Some(ContentSourceDataFilter::All),
             query: Some(ContentSourceDataFilter::All),
+            cache_buster: self.streamed,
             ..Default::default()
         }
         .cell()
     }
     #[turbo_tasks::function]
-    async fn get(&self, data: Value<ContentSourceData>) -> Result<ContentSourceContentVc> {
-        let this = self.source.await?;
-        let Some(params) = &*this.match_params.get_matches(&self.path).await? else {
+    async fn get(
+        self_vc: NodeRenderGetContentResultVc,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceContentVc> {
+        let this = self_vc.await?;
+        let source = this.source.await?;
+        let Some(params) = &*source.match_params.get_matches(&this.path).await? else {
             return Err(anyhow!("Non matching path provided"));
         };
         let ContentSourceData {
@@ -186,25 +200,62 @@ impl GetContentSourceContent for NodeRenderGetContentResult {
         } = &*data else {
             return Err(anyhow!("Missing request data"));
         };
-        let entry = this.entry.entry(data.clone()).await?;
-        let asset = render_static(
-            this.server_root.join(&self.path),
+        // The entry doesn't depend on the cache buster
+        let entry = source
+            .entry
+            .entry(Value::new(ContentSourceData {
+                cache_buster: 0,
+                ..(*data).clone()
+            }))
+            .await?;
+        let path = source.server_root.join(&this.path);
+        let render_data = RenderData {
+            params: params.clone(),
+            method: method.clone(),
+            url: url.clone(),
+            query: query.clone(),
+            headers: headers.clone(),
+            path: format!("/{}", source.pathname.await?),
+        }
+        .cell();
+        if this.streamed {
+            return render_static_stream(
+                path,
+                entry.module,
+                source.runtime_entries,
+                source.fallback_page,
+                entry.chunking_context,
+                entry.intermediate_output_path,
+                render_data,
+            )
+            .await;
+        }
+        let result = render_static(
+            path,
             entry.module,
-            this.runtime_entries,
-            this.fallback_page,
+            source.runtime_entries,
+            source.fallback_page,
             entry.chunking_context,
             entry.intermediate_output_path,
-            RenderData {
-                params: params.clone(),
-                method: method.clone(),
-                url: url.clone(),
-                query: query.clone(),
-                headers: headers.clone(),
-                path: format!("/{}", this.pathname.await?),
-            }
-            .cell(),
+            render_data,
         );
-        Ok(ContentSourceContent::Static(asset.into()).cell())
+        Ok(match *result.await? {
+            StaticResult::Content(content) => ContentSourceContent::Static(content.into()).cell(),
+            StaticResult::Streamed => {
+                let get_content = NodeRenderGetContentResult {
+                    source: this.source,
+                    path: this.path.clone(),
+                    streamed: true,
+                }
+                .cell();
+                ContentSourceContent::NeedData(NeededData {
+                    source: ContentSourceResultVc::exact(get_content.into()).into(),
+                    path: String::new(),
+                    vary: get_content.vary().await?.clone_value(),
+                })
+                .cell()
+            }
+        })
     }
 }
 
@@ -0,0 +1,95 @@
+use std::{future::Future, sync::Arc};
+
+use anyhow::Result;
+use futures::stream;
+use serde::Deserialize;
+use turbo_tasks::{run_once, CompletionVc, TurboTasksApi};
+use turbopack_core::issue::IssueVc;
+use turbopack_dev_server::source::stream::{BodyError, BodyStream};
+
+use crate::{pool::NodeJsOperation, StructuredError};
+
+/// The messages of a body that a Node.js process sends after it started to
+/// respond.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum BodyIncomingMessage {
+    BodyChunk { data: Vec<u8> },
+    BodyEnd,
+    Error(StructuredError),
+}
+
+/// Emits an issue in its own task. Issues of errors that occur while a body
+/// is streamed can't be emitted by the task that returned the body, since it
+/// has already finished.
+#[turbo_tasks::function]
+fn emit_issue(issue: IssueVc) -> CompletionVc {
+    issue.emit();
+    CompletionVc::new()
+}
+
+/// An operation that is killed when it's dropped before the body has ended,
+/// since the process would continue to send it.
+struct BodyOperation(Option<NodeJsOperation>);
+
+impl Drop for BodyOperation {
+    fn drop(&mut self) {
+        if let Some(operation) = self.0.take() {
+            operation.kill();
+        }
+    }
+}
+
+/// Streams a body from a Node.js operation while it's read. When the process
+/// reports an error, `issue` describes it with a message and an issue, which
+/// is emitted and attached to the error that ends the body.
+pub(crate) fn operation_body<F>(
+    turbo_tasks: Arc<dyn TurboTasksApi>,
+    operation: NodeJsOperation,
+    issue: impl Fn(StructuredError) -> F + Send + Sync + 'static,
+) -> BodyStream
+where
+    F: Future<Output = Result<(String, IssueVc)>> + Send + 'static,
+{
+    let issue = Arc::new(issue);
+    BodyStream::new(stream::unfold(
+        BodyOperation(Some(operation)),
+        move |mut body_operation| {
+            let turbo_tasks = turbo_tasks.clone();
+            let issue = issue.clone();
+            async move {
+                let operation = body_operation.0.as_mut()?;
+                let error = match operation.recv().await {
+                    Ok(BodyIncomingMessage::BodyChunk { data }) => {
+                        return Some((Ok(data.into()), body_operation));
+                    }
+                    Ok(BodyIncomingMessage::BodyEnd) => {
+                        // The operation has finished and the process can be reused
+                        drop(body_operation.0.take());
+                        return None;
+                    }
+                    Ok(BodyIncomingMessage::Error(error)) => {
+                        // Describing the error reads source maps, which needs to run in a task
+                        let result = run_once(turbo_tasks, async move {
+                            let (message, issue) = (*issue)(error).await?;
+                            let issues = emit_issue(issue);
+                            issues.await?;
+                            Ok(BodyError::new(message).with_issues(issues.into()))
+                        })
+                        .await;
+                        match result {
+                            Ok(error) => anyhow::Error::new(error),
+                            Err(err) => err,
+                        }
+                    }
+                    Err(err) => err,
+                };
+                // The process might be in any state after an error
+                if let Some(operation) = body_operation.0.take() {
+                    operation.kill();
+                }
+                Some((Err(error), body_operation))
+            }
+        },
+    ))
+}
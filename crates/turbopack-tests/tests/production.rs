@@ -0,0 +1,248 @@
+#![cfg(test)]
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{AssetContent, AssetsVc},
+    chunk::production::ProductionChunkingContextVc,
+    context::{AssetContext, AssetContextVc},
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    reference::all_referenced_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+
+const INDEX: &str = r#"
+import { kept } from "./lib.js";
+console.log(kept());
+import("./async.js").then((m) => console.log(m.value));
+"#;
+
+const LIB: &str = r#"
+export function kept() {
+  return "kept export";
+}
+export function removed() {
+  return "removed export";
+}
+"#;
+
+/// Writes `files` to a directory and builds `index.js` with the production
+/// chunking context. Returns the content of the emitted JavaScript files by
+/// their path in the output directory.
+fn build(
+    name: &str,
+    files: &[(&str, &str)],
+    tree_shaking: bool,
+) -> Result<BTreeMap<String, String>> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("production")
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    for (path, content) in files {
+        fs::write(dir.join(path), content)?;
+    }
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(async move {
+            turbopack::register();
+            let tt = TurboTasks::new(MemoryBackend::default());
+            tt.run_once(async move {
+                let fs = DiskFileSystemVc::new("project".to_string(), dir.to_string_lossy().into());
+                let root = fs.root();
+                let output = root.join("output");
+
+                let env = EnvironmentVc::new(
+                    Value::new(ExecutionEnvironment::Browser(
+                        BrowserEnvironment {
+                            dom: true,
+                            web_worker: false,
+                            service_worker: false,
+                            browserslist_query: "Chrome 102".to_string(),
+                        }
+                        .into(),
+                    )),
+                    Value::new(EnvironmentIntention::Client),
+                );
+                let context: AssetContextVc = ModuleAssetContextVc::new(
+                    TransitionsByNameVc::cell(HashMap::new()),
+                    env,
+                    ModuleOptionsContext::default().cell(),
+                    ResolveOptionsContext::default().cell(),
+                )
+                .into();
+
+                let module = context.process(
+                    SourceAssetVc::new(root.join("index.js")).into(),
+                    Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+                );
+                let mut builder =
+                    ProductionChunkingContextVc::builder(root, output, root.join("static"), env)
+                        .entries(AssetsVc::cell(vec![module]));
+                if tree_shaking {
+                    builder = builder.tree_shaking();
+                }
+                let chunking_context = builder.build();
+                let Some(module) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+                    bail!("index.js is not an ecmascript module");
+                };
+                let chunk = module.as_evaluated_chunk(chunking_context, None);
+
+                let output = output.await?;
+                let mut files = BTreeMap::new();
+                let mut seen = HashSet::new();
+                let mut queue = vec![chunk.as_asset()];
+                while let Some(asset) = queue.pop() {
+                    let path = asset.path().await?;
+                    if !seen.insert(path.path.clone()) {
+                        continue;
+                    }
+                    queue.extend(all_referenced_assets(asset).await?.iter().copied());
+                    let Some(path) = output.get_path_to(&path) else {
+                        continue;
+                    };
+                    if !path.ends_with(".js") {
+                        continue;
+                    }
+                    let AssetContent::File(content) = &*asset.content().await? else {
+                        bail!("{path} is not a file");
+                    };
+                    let FileContent::Content(content) = &*content.await? else {
+                        bail!("{path} has no content");
+                    };
+                    files.insert(path.to_string(), content.content().to_str()?.into_owned());
+                }
+                Ok(files)
+            })
+            .await
+        })
+}
+
+fn file_names(files: &BTreeMap<String, String>) -> BTreeSet<&str> {
+    files.keys().map(|path| path.as_str()).collect()
+}
+
+#[test]
+fn production_build() -> Result<()> {
+    let files = build(
+        "production_build",
+        &[
+            ("index.js", INDEX),
+            ("lib.js", LIB),
+            ("async.js", "export const value = 42;"),
+        ],
+        true,
+    )?;
+
+    // The entry chunk, the async chunk and its manifest chunk
+    assert_eq!(
+        files.len(),
+        3,
+        "unexpected output: {:?}",
+        file_names(&files)
+    );
+    for (path, content) in &files {
+        let (hash, extension) = path.split_at(path.len() - ".js".len());
+        assert_eq!(extension, ".js");
+        assert!(
+            hash.len() == 12 && hash.chars().all(|c| c.is_ascii_hexdigit()),
+            "{path} is not a content hashed chunk"
+        );
+        // Modules have short numeric ids instead of their path
+        assert!(!content.contains("(ecmascript)"), "{path} has a path as id");
+        // The runtime without hot module replacement is used
+        assert!(
+            !content.contains("HMR update"),
+            "{path} has the dev runtime"
+        );
+        // Minified
+        assert!(!content.contains("\n  "), "{path} is not minified");
+        // Tree shaken
+        assert!(
+            !content.contains("removed export"),
+            "{path} isn't tree shaken"
+        );
+    }
+    assert!(files
+        .values()
+        .any(|content| content.contains("kept export")));
+    // Minified chunks keep the source maps of their modules
+    assert!(files
+        .values()
+        .any(|content| content.contains("//# sourceMappingURL=")));
+
+    // Builds are deterministic
+    let same = build(
+        "production_build",
+        &[
+            ("index.js", INDEX),
+            ("lib.js", LIB),
+            ("async.js", "export const value = 42;"),
+        ],
+        true,
+    )?;
+    assert_eq!(files, same);
+
+    // Changing the async module changes the path of its chunk, of the manifest
+    // chunk that loads it and of the entry chunk that loads the manifest chunk
+    let changed = build(
+        "production_build",
+        &[
+            ("index.js", INDEX),
+            ("lib.js", LIB),
+            ("async.js", "export const value = 43;"),
+        ],
+        true,
+    )?;
+    assert_eq!(changed.len(), 3);
+    assert!(file_names(&files).is_disjoint(&file_names(&changed)));
+    Ok(())
+}
+
+#[test]
+fn side_effect_free_imports() -> Result<()> {
+    let files = [
+        (
+            "index.js",
+            r#"
+import "./unused.js";
+import "./effect.js";
+import { used } from "./used.js";
+console.log(used);
+"#,
+        ),
+        ("unused.js", r#"export const unused = "unused module";"#),
+        ("used.js", r#"export const used = "used module";"#),
+        ("effect.js", r#"console.log("effect module");"#),
+        ("package.json", r#"{ "sideEffects": ["./effect.js"] }"#),
+    ];
+    let contains = |output: &BTreeMap<String, String>, text: &str| {
+        output.values().any(|content| content.contains(text))
+    };
+
+    let output = build("side_effect_free_imports", &files, true)?;
+    assert!(!contains(&output, "unused module"));
+    assert!(contains(&output, "used module"));
+    assert!(contains(&output, "effect module"));
+
+    // Without tree shaking all imported modules are included
+    let output = build("side_effect_free_imports", &files, false)?;
+    assert!(contains(&output, "unused module"));
+    Ok(())
+}
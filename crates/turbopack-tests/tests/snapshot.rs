@@ -11,7 +11,9 @@ use once_cell::sync::Lazy;
 use serde::Deserialize;
 use similar::TextDiff;
 use test_generator::test_resources;
-use turbo_tasks::{debug::ValueDebug, NothingVc, TryJoinIterExt, TurboTasks, Value};
+use turbo_tasks::{
+    debug::ValueDebug, NothingVc, SchedulingMode, TryJoinIterExt, TurboTasks, Value,
+};
 use turbo_tasks_env::DotenvProcessEnvVc;
 use turbo_tasks_fs::{
     util::sys_to_unix, DirectoryContent, DirectoryEntry, DiskFileSystemVc, File, FileContent,
@@ -48,6 +50,15 @@ fn register() {
 // `UPDATE=1 cargo test -p turbopack -- test_my_pattern`
 static UPDATE: Lazy<bool> = Lazy::new(|| env::var("UPDATE").unwrap_or_default() == "1");
 
+// Starts tasks in a deterministic order derived from the seed, which helps to
+// reproduce flaky snapshots.
+// `TURBO_TASKS_SEED=42 cargo test -p turbopack-tests -- test_my_pattern`
+static SEED: Lazy<Option<u64>> = Lazy::new(|| {
+    env::var("TURBO_TASKS_SEED")
+        .ok()
+        .map(|seed| seed.parse().expect("TURBO_TASKS_SEED must be a number"))
+});
+
 static WORKSPACE_ROOT: Lazy<String> = Lazy::new(|| {
     let package_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     package_root
@@ -98,7 +109,13 @@ fn test(resource: &'static str) {
 async fn run(resource: &'static str) -> Result<()> {
     register();
 
-    let tt = TurboTasks::new(MemoryBackend::default());
+    let tt = match *SEED {
+        Some(seed) => TurboTasks::new_with_scheduling_mode(
+            MemoryBackend::default(),
+            SchedulingMode::Seeded(seed),
+        ),
+        None => TurboTasks::new(MemoryBackend::default()),
+    };
     let task = tt.spawn_once_task(async move {
         let out = run_test(resource.to_string());
         handle_issues(out).await?;
@@ -747,6 +747,51 @@ function applyUpdate(chunkPath, update) {
   applyPhase(chunkPath, outdatedSelfAcceptedModules, newModuleFactories);
 }
 
+/**
+ * Associates modules of a removed chunk with the chunks which contain them
+ * now, so they are kept when the chunk is removed.
+ *
+ * @param {Iterable<ModuleId>} modules
+ * @param {Iterable<ChunkPath>} chunkPaths
+ */
+function applyMoved(modules, chunkPaths) {
+  for (const moduleId of modules) {
+    for (const chunkPath of chunkPaths) {
+      addModuleToChunk(moduleId, chunkPath);
+    }
+  }
+}
+
+/**
+ * Disposes of the modules of removed chunks, unless they are still contained
+ * in other chunks.
+ *
+ * @param {Iterable<ModuleId>} modules
+ * @param {Iterable<ChunkPath>} chunkPaths
+ */
+function applyRemoved(modules, chunkPaths) {
+  for (const chunkPath of chunkPaths) {
+    for (const moduleId of modules) {
+      if (!moduleChunksMap.has(moduleId)) {
+        continue;
+      }
+
+      const noRemainingChunks = removeModuleFromChunk(moduleId, chunkPath);
+      const module = moduleCache[moduleId];
+
+      if (noRemainingChunks && module) {
+        disposeModule(module);
+
+        moduleHotData.delete(moduleId);
+      }
+    }
+
+    // The chunk needs to be loaded again if it's added back later.
+    loadedChunks.delete(chunkPath);
+    chunkLoaders.delete(chunkPath);
+  }
+}
+
 /**
  *
  * @param {ModuleId} moduleId
@@ -845,6 +890,12 @@ function handleApply(chunkPath, update) {
     case "restart":
       BACKEND.restart();
       break;
+    case "moved":
+      applyMoved(update.modules, update.chunks);
+      break;
+    case "removed":
+      applyRemoved(update.modules, update.chunks);
+      break;
     default:
       throw new Error(`Unknown update type: ${update.type}`);
   }
@@ -21,20 +21,23 @@ use ecmascript::{
 };
 use graph::{aggregate, AggregatedGraphNodeContent, AggregatedGraphVc};
 use lazy_static::lazy_static;
+use mime::Mime;
 use module_options::{
-    ModuleOptionsContextVc, ModuleOptionsVc, ModuleRuleEffect, ModuleType, ModuleTypeVc,
+    ModuleOptionsContextVc, ModuleOptionsVc, ModuleRequestInfo, ModuleRuleCondition,
+    ModuleRuleEffect, ModuleRuleRequest, ModuleType, ModuleTypeVc,
 };
 pub use resolve::resolve_options;
 use turbo_tasks::{
     primitives::{BoolVc, StringVc},
     CompletionVc, Value,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
 use turbopack_core::{
-    asset::AssetVc,
+    asset::{AssetContent, AssetVc},
     context::{AssetContext, AssetContextVc},
     environment::EnvironmentVc,
     issue::{unsupported_module::UnsupportedModuleIssue, Issue, IssueVc},
+    query_asset::QueryAssetVc,
     reference::all_referenced_assets,
     reference_type::ReferenceType,
     resolve::{
@@ -153,31 +156,148 @@ async fn apply_module_type(
         ModuleType::Mdx(transforms) => {
             MdxModuleAssetVc::new(source, context.into(), *transforms).into()
         }
-        ModuleType::Custom(_) => todo!(),
+        ModuleType::Custom(custom) => custom.create_module(source, context.into()),
     })
 }
 
+/// What module rules can tell apart about a request. It's the key of
+/// [module_with_rule_inputs] instead of the [ModuleRequestInfo], so requests
+/// which the rules can't tell apart share the module.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Default, PartialOrd, Ord, Hash)]
+struct ModuleRuleInputs {
+    query: Option<String>,
+    issuer_layer: Option<String>,
+    /// Whether the conditions returned by [issuer_path_conditions] match the
+    /// module that contains the request
+    issuer_path_matches: Vec<bool>,
+}
+
+/// All issuer path conditions of the custom rules in the context and the
+/// contexts of its rules, in a stable order.
+async fn issuer_path_conditions(
+    context: ModuleOptionsContextVc,
+) -> Result<Vec<ModuleRuleCondition>> {
+    let mut conditions = Vec::new();
+    let mut stack = vec![context];
+    while let Some(context) = stack.pop() {
+        let context = context.await?;
+        for rule in context.custom_rules.iter() {
+            rule.condition().visit(&mut |condition| {
+                if condition.is_issuer_path_condition() {
+                    conditions.push(condition.clone());
+                }
+            });
+        }
+        stack.extend(context.rules.iter().rev().map(|&(_, context)| context));
+    }
+    Ok(conditions)
+}
+
+/// The content type of the source, see
+/// [ModuleRuleCondition::ResourceContentType].
+async fn content_type(source: AssetVc) -> Result<Option<Mime>> {
+    if let AssetContent::File(content) = &*source.content().await? {
+        if let FileContent::Content(file) = &*content.await? {
+            if let Some(content_type) = file.content_type() {
+                return Ok(Some(content_type.clone()));
+            }
+        }
+    }
+    Ok(mime_guess::from_path(&source.path().await?.path).first())
+}
+
 #[turbo_tasks::function]
 async fn module(
     source: AssetVc,
     context: ModuleAssetContextVc,
     reference_type: Value<ReferenceType>,
+    request_info: Value<ModuleRequestInfo>,
+) -> Result<AssetVc> {
+    let ModuleRequestInfo {
+        query,
+        issuer,
+        issuer_layer,
+    } = request_info.into_value();
+    let issuer_path_matches = if let Some(issuer) = issuer {
+        let issuer = issuer.await?;
+        issuer_path_conditions(context.module_options_context())
+            .await?
+            .iter()
+            .map(|condition| condition.matches_issuer_path(&issuer))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(module_with_rule_inputs(
+        source,
+        context,
+        reference_type,
+        Value::new(ModuleRuleInputs {
+            query,
+            issuer_layer,
+            issuer_path_matches,
+        }),
+    ))
+}
+
+#[turbo_tasks::function]
+async fn module_with_rule_inputs(
+    source: AssetVc,
+    context: ModuleAssetContextVc,
+    reference_type: Value<ReferenceType>,
+    inputs: Value<ModuleRuleInputs>,
 ) -> Result<AssetVc> {
     let path = source.path().resolve().await?;
     let options = ModuleOptionsVc::new(path.parent(), context.module_options_context());
+    let options = options.await?;
 
     let reference_type = reference_type.into_value();
+    let inputs = inputs.into_value();
+    let matching_issuer_path_conditions = if inputs.issuer_path_matches.contains(&true) {
+        issuer_path_conditions(context.module_options_context())
+            .await?
+            .into_iter()
+            .zip(inputs.issuer_path_matches.iter())
+            .filter_map(|(condition, &matches)| matches.then_some(condition))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut uses_content_type = false;
+    for rule in options.rules.iter() {
+        rule.condition().visit(&mut |condition| {
+            uses_content_type |= matches!(condition, ModuleRuleCondition::ResourceContentType(_))
+        });
+    }
+    let resource_content_type = if uses_content_type {
+        content_type(source).await?
+    } else {
+        None
+    };
+    let request = ModuleRuleRequest {
+        query: inputs.query.as_deref(),
+        content_type: resource_content_type.as_ref(),
+        issuer_layer: inputs.issuer_layer.as_deref(),
+        matching_issuer_path_conditions: &matching_issuer_path_conditions,
+    };
+
     let mut current_source = source;
     let mut current_module_type = None;
-    for rule in options.await?.rules.iter() {
-        if rule.matches(&*path.await?, &reference_type) {
+    for rule in options.rules.iter() {
+        if rule.matches(&*path.await?, &reference_type, &request) {
             for effect in rule.effects() {
                 match effect {
                     ModuleRuleEffect::SourceTransforms(transforms) => {
                         current_source = transforms.transform(current_source);
                         if current_source.path().resolve().await? != path {
                             // The path has been changed, so we need to apply new rules.
-                            return Ok(module(current_source, context, Value::new(reference_type)));
+                            return Ok(module_with_rule_inputs(
+                                current_source,
+                                context,
+                                Value::new(reference_type),
+                                Value::new(inputs.clone()),
+                            ));
                         }
                     }
                     ModuleRuleEffect::ModuleType(module) => {
@@ -228,15 +348,15 @@ async fn module(
                             }
                         };
                     }
-                    ModuleRuleEffect::Custom => {
-                        todo!("Custom module rule effects are not yet supported");
-                    }
                 }
             }
         }
     }
 
     let module_type = current_module_type.unwrap_or(ModuleType::Raw).cell();
+    if let Some(query) = inputs.query {
+        current_source = QueryAssetVc::new(current_source, query).into();
+    }
 
     Ok(apply_module_type(current_source, context, module_type))
 }
@@ -285,6 +405,62 @@ impl ModuleAssetContextVc {
         })
     }
 
+    /// Like [AssetContext::process], but module rules can also match on the
+    /// request the asset was resolved from.
+    #[turbo_tasks::function]
+    pub async fn process_with_request_info(
+        self,
+        asset: AssetVc,
+        reference_type: Value<ReferenceType>,
+        request_info: Value<ModuleRequestInfo>,
+    ) -> Result<AssetVc> {
+        let this = self.await?;
+        if let Some(transition) = this.transition {
+            let asset = transition.process_source(asset);
+            let environment = transition.process_environment(this.environment);
+            let module_options_context =
+                transition.process_module_options_context(this.module_options_context);
+            let resolve_options_context =
+                transition.process_resolve_options_context(this.resolve_options_context);
+            let context = ModuleAssetContextVc::new(
+                this.transitions,
+                environment,
+                module_options_context,
+                resolve_options_context,
+            );
+            let m = module(asset, context, reference_type, request_info);
+            Ok(transition.process_module(m, context))
+        } else {
+            let context = ModuleAssetContextVc::new(
+                this.transitions,
+                this.environment,
+                this.module_options_context,
+                this.resolve_options_context,
+            );
+            Ok(module(asset, context, reference_type, request_info))
+        }
+    }
+
+    #[turbo_tasks::function]
+    async fn process_resolve_result_with_request_info(
+        self,
+        result: ResolveResultVc,
+        reference_type: Value<ReferenceType>,
+        request_info: Value<ModuleRequestInfo>,
+    ) -> Result<ResolveResultVc> {
+        Ok(result
+            .await?
+            .map(
+                |a| {
+                    self.process_with_request_info(a, reference_type.clone(), request_info.clone())
+                        .resolve()
+                },
+                |i| async move { Ok(i) },
+            )
+            .await?
+            .into())
+    }
+
     #[turbo_tasks::function]
     pub async fn module_options_context(self) -> Result<ModuleOptionsContextVc> {
         Ok(self.await?.module_options_context)
@@ -350,8 +526,21 @@ impl AssetContext for ModuleAssetContext {
 
         let context_path = origin_path.parent().resolve().await?;
 
-        let result = resolve(context_path, request, resolve_options);
-        let result = self_vc.process_resolve_result(result, reference_type);
+        let request_without_query = split_request_query(request).await?;
+        let result = resolve(context_path, request_without_query.request, resolve_options);
+        let result = self_vc.process_resolve_result_with_request_info(
+            result,
+            reference_type,
+            Value::new(ModuleRequestInfo {
+                query: request_without_query.query.clone(),
+                issuer: Some(origin_path),
+                issuer_layer: self_vc
+                    .module_options_context()
+                    .layer(context_path)
+                    .await?
+                    .clone_value(),
+            }),
+        );
 
         if *self_vc.is_types_resolving_enabled().await? {
             let types_reference = TypescriptTypesAssetReferenceVc::new(
@@ -366,52 +555,29 @@ impl AssetContext for ModuleAssetContext {
     }
 
     #[turbo_tasks::function]
-    async fn process_resolve_result(
+    fn process_resolve_result(
         self_vc: ModuleAssetContextVc,
         result: ResolveResultVc,
         reference_type: Value<ReferenceType>,
-    ) -> Result<ResolveResultVc> {
-        Ok(result
-            .await?
-            .map(
-                |a| self_vc.process(a, reference_type.clone()).resolve(),
-                |i| async move { Ok(i) },
-            )
-            .await?
-            .into())
+    ) -> ResolveResultVc {
+        self_vc.process_resolve_result_with_request_info(
+            result,
+            reference_type,
+            Value::new(ModuleRequestInfo::default()),
+        )
     }
 
     #[turbo_tasks::function]
-    async fn process(
+    fn process(
         self_vc: ModuleAssetContextVc,
         asset: AssetVc,
         reference_type: Value<ReferenceType>,
-    ) -> Result<AssetVc> {
-        let this = self_vc.await?;
-        if let Some(transition) = this.transition {
-            let asset = transition.process_source(asset);
-            let environment = transition.process_environment(this.environment);
-            let module_options_context =
-                transition.process_module_options_context(this.module_options_context);
-            let resolve_options_context =
-                transition.process_resolve_options_context(this.resolve_options_context);
-            let context = ModuleAssetContextVc::new(
-                this.transitions,
-                environment,
-                module_options_context,
-                resolve_options_context,
-            );
-            let m = module(asset, context, reference_type);
-            Ok(transition.process_module(m, context))
-        } else {
-            let context = ModuleAssetContextVc::new(
-                this.transitions,
-                this.environment,
-                this.module_options_context,
-                this.resolve_options_context,
-            );
-            Ok(module(asset, context, reference_type))
-        }
+    ) -> AssetVc {
+        self_vc.process_with_request_info(
+            asset,
+            reference_type,
+            Value::new(ModuleRequestInfo::default()),
+        )
     }
 
     #[turbo_tasks::function]
@@ -569,6 +735,90 @@ async fn print_references(list: ReferencesListVc) -> Result<()> {
     Ok(())
 }
 
+/// A request with the `?query` split off, see [split_request_query].
+#[turbo_tasks::value]
+struct RequestWithoutQuery {
+    request: RequestVc,
+    /// The query without the leading `?`
+    query: Option<String>,
+}
+
+/// Splits the `?query` from a request, so the request resolves to the file and
+/// module rules can match on the query. Module requests keep their parsed
+/// query, as the package resolution handles it.
+#[turbo_tasks::function]
+async fn split_request_query(request: RequestVc) -> Result<RequestWithoutQueryVc> {
+    let mut request_value = request.await?.clone_value();
+    let query = match &mut request_value {
+        Request::Raw { path, .. }
+        | Request::Relative { path, .. }
+        | Request::ServerRelative { path }
+        | Request::Windows { path }
+        | Request::PackageInternal { path }
+        | Request::Unknown { path } => split_pattern_query(path),
+        Request::Module { query, .. } => {
+            let query = (*query).await?.as_ref().map(|query| {
+                query
+                    .iter()
+                    .map(|(key, value)| {
+                        if value.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{key}={value}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("&")
+            });
+            return Ok(RequestWithoutQuery { request, query }.cell());
+        }
+        Request::Alternatives { requests } => {
+            // All alternatives are parsed from the same request, so they have the same
+            // query
+            let mut query = None;
+            for request in requests.iter_mut() {
+                let without_query = split_request_query(*request).await?;
+                *request = without_query.request;
+                query = query.or_else(|| without_query.query.clone());
+            }
+            query
+        }
+        // The query of a URI is part of the URI, data URIs and the other requests
+        // have no query
+        Request::Uri { .. } | Request::DataUri { .. } | Request::Empty | Request::Dynamic => None,
+    };
+    Ok(match query {
+        Some(query) => RequestWithoutQuery {
+            request: request_value.cell(),
+            query: Some(query),
+        },
+        None => RequestWithoutQuery {
+            request,
+            query: None,
+        },
+    }
+    .cell())
+}
+
+/// Removes the `?query` from the end of a pattern and returns the query
+/// without the leading `?`.
+fn split_pattern_query(pattern: &mut Pattern) -> Option<String> {
+    let query = match pattern {
+        Pattern::Constant(path) => {
+            let index = path.find('?')?;
+            let query = path[index + 1..].to_string();
+            path.truncate(index);
+            query
+        }
+        Pattern::Concatenation(list) => split_pattern_query(list.last_mut()?)?,
+        // Alternative patterns are turned into alternative requests, which are split
+        // separately
+        Pattern::Dynamic | Pattern::Alternatives(_) => return None,
+    };
+    pattern.normalize();
+    Some(query)
+}
+
 async fn warn_on_unsupported_modules(
     request: RequestVc,
     origin_path: FileSystemPathVc,
@@ -0,0 +1,11 @@
+use turbopack_core::{asset::AssetVc, context::AssetContextVc};
+
+/// A module type which is implemented outside of turbopack, e.g. GraphQL
+/// documents compiled to JavaScript. A [super::ModuleRule] can select it with
+/// [super::ModuleType::Custom].
+#[turbo_tasks::value_trait]
+pub trait CustomModuleType {
+    /// Creates the module asset for the `source` asset. References of the
+    /// created module should be resolved within `context`.
+    fn create_module(&self, source: AssetVc, context: AssetContextVc) -> AssetVc;
+}
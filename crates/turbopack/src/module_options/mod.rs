@@ -1,8 +1,10 @@
+pub mod custom_module_type;
 pub mod module_options_context;
 pub mod module_rule;
 pub mod rule_condition;
 
 use anyhow::{Context, Result};
+pub use custom_module_type::*;
 pub use module_options_context::*;
 pub use module_rule::*;
 pub use rule_condition::*;
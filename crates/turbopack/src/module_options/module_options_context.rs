@@ -1,6 +1,11 @@
+use anyhow::Result;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use turbo_tasks::{primitives::StringsVc, trace::TraceRawVcs};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringsVc},
+    trace::TraceRawVcs,
+};
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{environment::EnvironmentVc, resolve::options::ImportMappingVc};
 use turbopack_ecmascript::EcmascriptInputTransform;
 use turbopack_node::execution_context::ExecutionContextVc;
@@ -57,6 +62,10 @@ pub struct ModuleOptionsContext {
     /// A list of rules to use a different module option context for certain
     /// context paths. The first matching is used.
     pub rules: Vec<(ContextCondition, ModuleOptionsContextVc)>,
+    /// The layer of the modules in this context, e. g. `"server"`. Rules can
+    /// match on the layer of the module that contains a request with
+    /// [ModuleRuleCondition::IssuerLayer](super::ModuleRuleCondition::IssuerLayer).
+    pub layer: Option<String>,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -66,6 +75,22 @@ impl ModuleOptionsContextVc {
     pub fn default() -> Self {
         Self::cell(Default::default())
     }
+
+    /// The layer of the modules in the `context` directory, taking the
+    /// `rules` into account.
+    #[turbo_tasks::function]
+    pub async fn layer(self, context: FileSystemPathVc) -> Result<OptionStringVc> {
+        let this = self.await?;
+        if !this.rules.is_empty() {
+            let context_value = context.await?;
+            for (condition, new_context) in this.rules.iter() {
+                if condition.matches(&context_value) {
+                    return Ok(new_context.layer(context));
+                }
+            }
+        }
+        Ok(OptionStringVc::cell(this.layer.clone()))
+    }
 }
 
 impl Default for ModuleOptionsContextVc {
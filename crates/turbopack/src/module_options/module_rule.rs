@@ -6,7 +6,7 @@ use turbopack_core::{reference_type::ReferenceType, source_transform::SourceTran
 use turbopack_css::CssInputTransformsVc;
 use turbopack_ecmascript::EcmascriptInputTransformsVc;
 
-use super::ModuleRuleCondition;
+use super::{CustomModuleTypeVc, ModuleRuleCondition, ModuleRuleRequest};
 
 #[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
 pub struct ModuleRule {
@@ -19,12 +19,21 @@ impl ModuleRule {
         ModuleRule { condition, effects }
     }
 
+    pub fn condition(&self) -> &ModuleRuleCondition {
+        &self.condition
+    }
+
     pub fn effects(&self) -> impl Iterator<Item = &ModuleRuleEffect> {
         self.effects.iter()
     }
 
-    pub fn matches(&self, path: &FileSystemPath, reference_type: &ReferenceType) -> bool {
-        self.condition.matches(path, reference_type)
+    pub fn matches(
+        &self,
+        path: &FileSystemPath,
+        reference_type: &ReferenceType,
+        request: &ModuleRuleRequest,
+    ) -> bool {
+        self.condition.matches(path, reference_type, request)
     }
 }
 
@@ -34,7 +43,6 @@ pub enum ModuleRuleEffect {
     ModuleType(ModuleType),
     AddEcmascriptTransforms(EcmascriptInputTransformsVc),
     SourceTransforms(SourceTransformsVc),
-    Custom,
 }
 
 #[turbo_tasks::value(serialization = "auto_for_input", shared)]
@@ -50,6 +58,5 @@ pub enum ModuleType {
     Css(CssInputTransformsVc),
     CssModule(CssInputTransformsVc),
     Static,
-    // TODO allow custom function when we support function pointers
-    Custom(u8),
+    Custom(CustomModuleTypeVc),
 }
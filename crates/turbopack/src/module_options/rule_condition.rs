@@ -1,6 +1,7 @@
+use mime::Mime;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{primitives::Regex, trace::TraceRawVcs};
-use turbo_tasks_fs::{FileSystemPath, FileSystemPathReadRef};
+use turbo_tasks_fs::{FileSystemPath, FileSystemPathReadRef, FileSystemPathVc};
 use turbopack_core::reference_type::ReferenceType;
 
 #[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
@@ -15,6 +16,54 @@ pub enum ModuleRuleCondition {
     ResourcePathInDirectory(String),
     ResourcePathInExactDirectory(FileSystemPathReadRef),
     ResourcePathRegex(#[turbo_tasks(trace_ignore)] Regex),
+    /// The name of the package in `node_modules` the resource is part of
+    ResourcePackageName(String),
+    /// The content type of the resource, e.g. `image/png`, or `image/*` for
+    /// all images. It's the content type of the file if it has one, e.g. for
+    /// data URIs, and guessed from the extension otherwise.
+    ResourceContentType(String),
+    /// The query of the request has the parameter, e.g. `raw` for `?raw` or
+    /// `?raw&foo=bar`
+    ResourceQueryHasParam(String),
+    /// Matches the query of the request without the leading `?`
+    ResourceQueryRegex(#[turbo_tasks(trace_ignore)] Regex),
+    /// The module that contains the request is inside the directory
+    IssuerPathInExactDirectory(FileSystemPathReadRef),
+    /// Matches the path of the module that contains the request
+    IssuerPathRegex(#[turbo_tasks(trace_ignore)] Regex),
+    /// The layer of the module that contains the request, see
+    /// [ModuleOptionsContext::layer](super::ModuleOptionsContext::layer)
+    IssuerLayer(String),
+}
+
+/// Information about the request of a module which conditions can match on
+/// in addition to the path of the resource.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Default, PartialOrd, Ord, Hash)]
+pub struct ModuleRequestInfo {
+    /// The query of the request without the leading `?`
+    pub query: Option<String>,
+    /// The path of the module that contains the request. Only the results of
+    /// the issuer path conditions are part of the identity of the module, so
+    /// importers share the module unless a condition tells them apart.
+    pub issuer: Option<FileSystemPathVc>,
+    /// The layer of the module that contains the request
+    pub issuer_layer: Option<String>,
+}
+
+/// The request of a module as seen by [ModuleRuleCondition::matches].
+#[derive(Debug, Default)]
+pub struct ModuleRuleRequest<'a> {
+    /// The query of the request without the leading `?`
+    pub query: Option<&'a str>,
+    /// The content type of the resource, only needed when a condition matches
+    /// on it
+    pub content_type: Option<&'a Mime>,
+    /// The layer of the module that contains the request
+    pub issuer_layer: Option<&'a str>,
+    /// The issuer path conditions which match the module that contains the
+    /// request
+    pub matching_issuer_path_conditions: &'a [ModuleRuleCondition],
 }
 
 impl ModuleRuleCondition {
@@ -33,15 +82,56 @@ impl ModuleRuleCondition {
 }
 
 impl ModuleRuleCondition {
-    pub fn matches(&self, path: &FileSystemPath, reference_type: &ReferenceType) -> bool {
+    /// Calls `f` with this condition and all nested conditions.
+    pub fn visit(&self, f: &mut impl FnMut(&ModuleRuleCondition)) {
+        f(self);
         match self {
-            ModuleRuleCondition::All(conditions) => {
-                conditions.iter().all(|c| c.matches(path, reference_type))
+            ModuleRuleCondition::All(conditions) | ModuleRuleCondition::Any(conditions) => {
+                for condition in conditions {
+                    condition.visit(f);
+                }
             }
-            ModuleRuleCondition::Any(conditions) => {
-                conditions.iter().any(|c| c.matches(path, reference_type))
+            ModuleRuleCondition::Not(condition) => condition.visit(f),
+            _ => {}
+        }
+    }
+
+    pub fn is_issuer_path_condition(&self) -> bool {
+        matches!(
+            self,
+            ModuleRuleCondition::IssuerPathInExactDirectory(_)
+                | ModuleRuleCondition::IssuerPathRegex(_)
+        )
+    }
+
+    /// Evaluates an issuer path condition for the path of the module that
+    /// contains the request. Other conditions don't match.
+    pub fn matches_issuer_path(&self, issuer: &FileSystemPath) -> bool {
+        match self {
+            ModuleRuleCondition::IssuerPathInExactDirectory(parent_path) => {
+                issuer.is_inside(parent_path)
+            }
+            ModuleRuleCondition::IssuerPathRegex(regex) => regex.is_match(&issuer.path),
+            _ => false,
+        }
+    }
+
+    pub fn matches(
+        &self,
+        path: &FileSystemPath,
+        reference_type: &ReferenceType,
+        request: &ModuleRuleRequest,
+    ) -> bool {
+        match self {
+            ModuleRuleCondition::All(conditions) => conditions
+                .iter()
+                .all(|c| c.matches(path, reference_type, request)),
+            ModuleRuleCondition::Any(conditions) => conditions
+                .iter()
+                .any(|c| c.matches(path, reference_type, request)),
+            ModuleRuleCondition::Not(condition) => {
+                !condition.matches(path, reference_type, request)
             }
-            ModuleRuleCondition::Not(condition) => !condition.matches(path, reference_type),
             ModuleRuleCondition::ResourcePathEquals(other) => path == &**other,
             ModuleRuleCondition::ResourcePathEndsWith(end) => path.path.ends_with(end),
             ModuleRuleCondition::ResourcePathHasNoExtension => {
@@ -61,10 +151,89 @@ impl ModuleRuleCondition {
             ModuleRuleCondition::ResourcePathInExactDirectory(parent_path) => {
                 path.is_inside(parent_path)
             }
+            ModuleRuleCondition::ResourcePathRegex(regex) => regex.is_match(&path.path),
+            ModuleRuleCondition::ResourcePackageName(name) => {
+                package_name(&path.path) == Some(name.as_str())
+            }
+            ModuleRuleCondition::ResourceContentType(content_type) => request
+                .content_type
+                .map_or(false, |mime| content_type_matches(mime, content_type)),
+            ModuleRuleCondition::ResourceQueryHasParam(param) => {
+                request.query.map_or(false, |query| {
+                    query
+                        .split('&')
+                        .any(|pair| pair.split('=').next() == Some(param.as_str()))
+                })
+            }
+            ModuleRuleCondition::ResourceQueryRegex(regex) => {
+                request.query.map_or(false, |query| regex.is_match(query))
+            }
+            ModuleRuleCondition::IssuerPathInExactDirectory(_)
+            | ModuleRuleCondition::IssuerPathRegex(_) => {
+                request.matching_issuer_path_conditions.contains(self)
+            }
+            ModuleRuleCondition::IssuerLayer(layer) => request.issuer_layer == Some(layer.as_str()),
             ModuleRuleCondition::ReferenceType(condition_ty) => {
                 condition_ty.includes(reference_type)
             }
-            _ => todo!("not implemented yet"),
         }
     }
 }
+
+/// Matches a content type against `pattern`, which is either a full content
+/// type without parameters or `type/*`.
+fn content_type_matches(content_type: &Mime, pattern: &str) -> bool {
+    match pattern.split_once('/') {
+        Some((ty, "*")) => content_type.type_() == ty,
+        _ => content_type.essence_str() == pattern,
+    }
+}
+
+/// Extracts the name of the innermost package in `node_modules` from a path.
+fn package_name(path: &str) -> Option<&str> {
+    let start = match path.rfind("/node_modules/") {
+        Some(index) => index + "/node_modules/".len(),
+        None if path.starts_with("node_modules/") => "node_modules/".len(),
+        None => return None,
+    };
+    let rest = &path[start..];
+    let mut segments = rest.split('/');
+    let first = segments.next()?;
+    if first.starts_with('@') {
+        let second = segments.next()?;
+        Some(&rest[..first.len() + 1 + second.len()])
+    } else {
+        Some(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_type_matches, package_name};
+
+    #[test]
+    fn package_names() {
+        assert_eq!(package_name("src/index.js"), None);
+        assert_eq!(package_name("node_modules/react/index.js"), Some("react"));
+        assert_eq!(
+            package_name("node_modules/@next/env/dist/index.js"),
+            Some("@next/env")
+        );
+        assert_eq!(
+            package_name("node_modules/a/node_modules/b/lib/b.js"),
+            Some("b")
+        );
+    }
+
+    #[test]
+    fn content_types() {
+        let png = mime::IMAGE_PNG;
+        assert!(content_type_matches(&png, "image/png"));
+        assert!(content_type_matches(&png, "image/*"));
+        assert!(!content_type_matches(&png, "image/jpeg"));
+        assert!(!content_type_matches(&png, "text/*"));
+
+        let css = "text/css; charset=utf-8".parse().unwrap();
+        assert!(content_type_matches(&css, "text/css"));
+    }
+}
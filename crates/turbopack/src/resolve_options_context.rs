@@ -1,4 +1,5 @@
 use anyhow::Result;
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     environment::EnvironmentVc,
     resolve::options::{ImportMapVc, ResolvedMapVc},
@@ -37,6 +38,9 @@ pub struct ResolveOptionsContext {
     pub fallback_import_map: Option<ImportMapVc>,
     /// An additional resolved map to use after modules have been resolved.
     pub resolved_map: Option<ResolvedMapVc>,
+    /// The directory server relative requests (`/path`) are resolved in.
+    /// Defaults to the root of the filesystem.
+    pub root: Option<FileSystemPathVc>,
     /// A list of rules to use a different resolve option context for certain
     /// context paths. The first matching is used.
     pub rules: Vec<(ContextCondition, ResolveOptionsContextVc)>,
@@ -0,0 +1,265 @@
+#![feature(min_specialization)]
+
+use std::{collections::HashMap, future::Future, path::PathBuf};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{primitives::Regex, TurboTasks, Value, ValueToString};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileContent, FileSystem, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    condition::ContextCondition,
+    module_options::{
+        CustomModuleType, ModuleOptionsContext, ModuleRule, ModuleRuleCondition, ModuleRuleEffect,
+        ModuleType,
+    },
+    register,
+    resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetVc},
+    context::{AssetContext, AssetContextVc},
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    reference_type::ReferenceType,
+    resolve::parse::RequestVc,
+    source_asset::SourceAssetVc,
+    virtual_asset::VirtualAssetVc,
+};
+
+/// Runs `f` with the root of the `tests/module_rules` directory.
+fn run<F, Fut>(f: F)
+where
+    F: FnOnce(FileSystemPathVc) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async move {
+            register();
+            include!(concat!(env!("OUT_DIR"), "/register_test_module_rules.rs"));
+            let tt = TurboTasks::new(MemoryBackend::default());
+            tt.run_once(async move {
+                let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/module_rules");
+                let fs = DiskFileSystemVc::new(
+                    "fixture".to_string(),
+                    path.to_string_lossy().to_string(),
+                );
+                f(fs.root()).await
+            })
+            .await
+            .unwrap();
+        });
+}
+
+fn module_context(module_options_context: ModuleOptionsContext) -> ModuleAssetContextVc {
+    ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::NodeJsLambda(
+                NodeJsEnvironment::default().into(),
+            )),
+            Value::new(EnvironmentIntention::ServerRendering),
+        ),
+        module_options_context.cell(),
+        ResolveOptionsContext::default().cell(),
+    )
+}
+
+async fn read(asset: AssetVc) -> Result<String> {
+    let AssetContent::File(content) = &*asset.content().await? else {
+        bail!("{} is not a file", asset.path().to_string().await?);
+    };
+    let FileContent::Content(file) = &*content.await? else {
+        bail!("{} has no content", asset.path().to_string().await?);
+    };
+    Ok(file.content().to_str()?.to_string())
+}
+
+/// Turns the source into a module with the uppercased content.
+#[turbo_tasks::value]
+struct UppercaseModuleType {
+    extension: String,
+}
+
+#[turbo_tasks::value_impl]
+impl CustomModuleType for UppercaseModuleType {
+    #[turbo_tasks::function]
+    async fn create_module(&self, source: AssetVc, _context: AssetContextVc) -> Result<AssetVc> {
+        let content = read(source).await?.to_uppercase();
+        Ok(VirtualAssetVc::new(
+            source.path().append(&self.extension),
+            File::from(content).into(),
+        )
+        .into())
+    }
+}
+
+/// Resolves `request` from the module at `origin` to a single module.
+async fn resolve_module(
+    context: AssetContextVc,
+    root: FileSystemPathVc,
+    origin: &str,
+    request: &str,
+) -> Result<AssetVc> {
+    let origin = root.join(origin);
+    let reference_type = Value::new(ReferenceType::Undefined);
+    let result = context.resolve_asset(
+        origin,
+        RequestVc::parse(Value::new(request.to_string().into())),
+        context.resolve_options(origin, reference_type.clone()),
+        reference_type,
+    );
+    let assets = result.primary_assets().await?;
+    let [asset] = assets.as_slice() else {
+        bail!("expected a single module for {request}, got {}", assets.len());
+    };
+    Ok(asset.resolve().await?)
+}
+
+fn uppercase_module_type() -> ModuleType {
+    ModuleType::Custom(
+        UppercaseModuleType {
+            extension: ".upper".to_string(),
+        }
+        .cell()
+        .into(),
+    )
+}
+
+#[test]
+fn custom_module_type() {
+    run(|root| async move {
+        let context = module_context(ModuleOptionsContext {
+            custom_rules: vec![ModuleRule::new(
+                ModuleRuleCondition::ResourcePathEndsWith(".txt".to_string()),
+                vec![ModuleRuleEffect::ModuleType(uppercase_module_type())],
+            )],
+            ..Default::default()
+        });
+
+        let module = context.process(
+            SourceAssetVc::new(root.join("hello.txt")).into(),
+            Value::new(ReferenceType::Undefined),
+        );
+        assert_eq!(module.path().await?.path, "hello.txt.upper");
+        assert_eq!(read(module).await?, "HELLO\n");
+
+        // Other files keep their builtin module type
+        let module = context.process(
+            SourceAssetVc::new(root.join("other.json")).into(),
+            Value::new(ReferenceType::Undefined),
+        );
+        assert_eq!(module.path().await?.path, "other.json");
+        Ok(())
+    });
+}
+
+#[test]
+fn query_and_issuer_layer() {
+    run(|root| async move {
+        let custom_rules = vec![ModuleRule::new(
+            ModuleRuleCondition::all(vec![
+                ModuleRuleCondition::ResourcePathEndsWith(".txt".to_string()),
+                ModuleRuleCondition::IssuerLayer("server".to_string()),
+            ]),
+            vec![ModuleRuleEffect::ModuleType(uppercase_module_type())],
+        )];
+        let server = ModuleOptionsContext {
+            custom_rules: custom_rules.clone(),
+            layer: Some("server".to_string()),
+            ..Default::default()
+        };
+        let context: AssetContextVc = module_context(ModuleOptionsContext {
+            custom_rules,
+            rules: vec![(
+                ContextCondition::InDirectory("server".to_string()),
+                server.cell(),
+            )],
+            ..Default::default()
+        })
+        .into();
+
+        // Importers in the same layer share the module
+        let raw = resolve_module(context, root, "src/a.js", "../hello.txt?raw").await?;
+        let other_raw = resolve_module(context, root, "src/b.js", "../hello.txt?raw").await?;
+        assert_eq!(raw, other_raw);
+        assert_eq!(read(raw).await?, "hello\n");
+
+        // The query is part of the ident, but not of the path
+        assert_eq!(raw.path().await?.path, "hello.txt");
+        assert!(raw.ident().await?.ends_with("/hello.txt?raw"));
+
+        // Other queries get a different module
+        let url = resolve_module(context, root, "src/a.js", "../hello.txt?url").await?;
+        assert_ne!(raw, url);
+        assert!(url.ident().await?.ends_with("/hello.txt?url"));
+
+        let plain = resolve_module(context, root, "src/a.js", "../hello.txt").await?;
+        assert_ne!(raw, plain);
+        assert!(plain.ident().await?.ends_with("/hello.txt"));
+
+        // Rules can match on the layer of the importer
+        let server = resolve_module(context, root, "server/c.js", "../hello.txt?raw").await?;
+        assert_eq!(server.path().await?.path, "hello.txt.upper");
+        assert_eq!(read(server).await?, "HELLO\n");
+        Ok(())
+    });
+}
+
+#[test]
+fn issuer_path() {
+    run(|root| async move {
+        let context: AssetContextVc = module_context(ModuleOptionsContext {
+            custom_rules: vec![ModuleRule::new(
+                ModuleRuleCondition::all(vec![
+                    ModuleRuleCondition::ResourcePathEndsWith(".txt".to_string()),
+                    ModuleRuleCondition::IssuerPathRegex(Regex(
+                        regex::Regex::new(r"^src/a\.js$").unwrap(),
+                    )),
+                ]),
+                vec![ModuleRuleEffect::ModuleType(uppercase_module_type())],
+            )],
+            ..Default::default()
+        })
+        .into();
+
+        let a = resolve_module(context, root, "src/a.js", "../hello.txt").await?;
+        assert_eq!(read(a).await?, "HELLO\n");
+
+        // Importers which the condition doesn't match share the module
+        let b = resolve_module(context, root, "src/b.js", "../hello.txt").await?;
+        let c = resolve_module(context, root, "server/c.js", "../hello.txt").await?;
+        assert_eq!(b, c);
+        assert_eq!(read(b).await?, "hello\n");
+        Ok(())
+    });
+}
+
+#[test]
+fn content_type() {
+    run(|root| async move {
+        let context = module_context(ModuleOptionsContext {
+            custom_rules: vec![ModuleRule::new(
+                ModuleRuleCondition::ResourceContentType("text/*".to_string()),
+                vec![ModuleRuleEffect::ModuleType(uppercase_module_type())],
+            )],
+            ..Default::default()
+        });
+
+        let module = context.process(
+            SourceAssetVc::new(root.join("hello.txt")).into(),
+            Value::new(ReferenceType::Undefined),
+        );
+        assert_eq!(read(module).await?, "HELLO\n");
+
+        let module = context.process(
+            SourceAssetVc::new(root.join("other.json")).into(),
+            Value::new(ReferenceType::Undefined),
+        );
+        assert_eq!(module.path().await?.path, "other.json");
+        Ok(())
+    });
+}
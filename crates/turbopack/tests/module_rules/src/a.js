@@ -0,0 +1 @@
+import "../hello.txt?raw";
@@ -0,0 +1,261 @@
+#![feature(min_specialization)]
+
+use std::{future::Future, path::PathBuf};
+
+use anyhow::Result;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{register, resolve_options, resolve_options_context::ResolveOptionsContext};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    resolve::{
+        options::{LockedVersionsVc, ResolveModules, ResolveOptionsVc},
+        parse::RequestVc,
+        pattern::Pattern,
+        resolve,
+    },
+};
+
+/// Runs `f` with the root of the `tests/resolve/<fixture>` directory.
+fn run<F, Fut>(fixture: &'static str, f: F)
+where
+    F: FnOnce(FileSystemPathVc) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async move {
+            register();
+            let tt = TurboTasks::new(MemoryBackend::default());
+            tt.run_once(async move {
+                let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/resolve")
+                    .join(fixture);
+                let fs = DiskFileSystemVc::new(
+                    "fixture".to_string(),
+                    path.to_string_lossy().to_string(),
+                );
+                f(fs.root()).await
+            })
+            .await
+            .unwrap();
+        });
+}
+
+/// Resolves `request` in `context` and returns the sorted paths of the
+/// resolved assets, relative to the root of the fixture.
+async fn resolve_paths(
+    context: FileSystemPathVc,
+    request: &str,
+    options: ResolveOptionsVc,
+) -> Result<Vec<String>> {
+    resolve_pattern_paths(context, request.to_string().into(), options).await
+}
+
+async fn resolve_pattern_paths(
+    context: FileSystemPathVc,
+    request: Pattern,
+    options: ResolveOptionsVc,
+) -> Result<Vec<String>> {
+    let request = RequestVc::parse(Value::new(request));
+    let assets = resolve(context, request, options).primary_assets().await?;
+    let mut paths = Vec::new();
+    for asset in assets.iter() {
+        paths.push(asset.path().await?.path.clone());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[test]
+fn registry() {
+    run("registry", |root| async move {
+        let app = root.join("app/src");
+        let registry = root.join("registry");
+        let mut options = resolve_options(app, ResolveOptionsContext::default().cell())
+            .await?
+            .clone_value();
+        options.modules = vec![ResolveModules::Registry(
+            registry,
+            LockedVersionsVc::from_lockfile(root.join("app/package-lock.json")),
+        )];
+        let options = options.cell();
+
+        // The version is taken from the importer, other versions in the
+        // registry are ignored
+        assert_eq!(
+            resolve_paths(app, "a", options).await?,
+            vec!["registry/a/1.0.0/main.js"]
+        );
+        // Dependencies of registry packages use the versions locked for them
+        assert_eq!(
+            resolve_paths(registry.join("a/1.0.0"), "b", options).await?,
+            vec!["registry/b/2.0.0/index.js"]
+        );
+        // Packages that aren't locked don't resolve
+        assert!(resolve_paths(app, "c", options).await?.is_empty());
+        Ok(())
+    });
+}
+
+#[test]
+fn exports_wildcards() {
+    run("exports-imports", |root| async move {
+        let options = resolve_options(
+            root,
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let features = |rest: Pattern| {
+            Pattern::concatenation([Pattern::Constant("pkg/features/".to_string()), rest])
+        };
+
+        assert_eq!(
+            resolve_paths(root, "pkg", options).await?,
+            vec!["node_modules/pkg/src/index.js"]
+        );
+        assert_eq!(
+            resolve_paths(root, "pkg/features/a", options).await?,
+            vec!["node_modules/pkg/src/features/a.js"]
+        );
+        // A dynamic subpath resolves to every file the pattern matches
+        assert_eq!(
+            resolve_pattern_paths(root, features(Pattern::Dynamic), options).await?,
+            vec![
+                "node_modules/pkg/src/features/a.js",
+                "node_modules/pkg/src/features/b.js"
+            ]
+        );
+
+        // Captures can't escape the package
+        let escape = || {
+            Pattern::concatenation([Pattern::Constant("../../../".to_string()), Pattern::Dynamic])
+        };
+        assert!(resolve_paths(root, "pkg/features/../../../secret", options)
+            .await?
+            .is_empty());
+        assert!(resolve_pattern_paths(root, features(escape()), options)
+            .await?
+            .is_empty());
+        Ok(())
+    });
+}
+
+#[test]
+fn imports_wildcards() {
+    run("exports-imports", |root| async move {
+        let options = resolve_options(
+            root,
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let context = root.join("node_modules/pkg/src");
+        let utils = |rest: Pattern| {
+            Pattern::concatenation([Pattern::Constant("#utils/".to_string()), rest])
+        };
+
+        assert_eq!(
+            resolve_paths(context, "#utils/x", options).await?,
+            vec!["node_modules/pkg/src/utils/x.js"]
+        );
+        assert_eq!(
+            resolve_pattern_paths(context, utils(Pattern::Dynamic), options).await?,
+            vec![
+                "node_modules/pkg/src/utils/x.js",
+                "node_modules/pkg/src/utils/y.js"
+            ]
+        );
+        // Imports can point to other packages
+        assert_eq!(
+            resolve_paths(context, "#dep", options).await?,
+            vec!["node_modules/dep/index.js"]
+        );
+
+        // Captures can't escape the package
+        let escape = || {
+            Pattern::concatenation([Pattern::Constant("../../../".to_string()), Pattern::Dynamic])
+        };
+        assert!(resolve_paths(context, "#utils/../../../secret", options)
+            .await?
+            .is_empty());
+        assert!(resolve_pattern_paths(context, utils(escape()), options)
+            .await?
+            .is_empty());
+        Ok(())
+    });
+}
+
+#[test]
+fn server_relative() {
+    run("server-relative", |root| async move {
+        let context = root.join("app/src");
+        let options = resolve_options(
+            context,
+            ResolveOptionsContext {
+                root: Some(root.join("app/public")),
+                ..Default::default()
+            }
+            .cell(),
+        );
+        assert_eq!(
+            resolve_paths(context, "/logo.svg", options).await?,
+            vec!["app/public/logo.svg"]
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn data_uri() {
+    run("server-relative", |root| async move {
+        let options = resolve_options(root, ResolveOptionsContext::default().cell());
+        let resolve_data_uri = |uri: &'static str| async move {
+            let request = RequestVc::parse(Value::new(uri.to_string().into()));
+            let assets = resolve(root, request, options).primary_assets().await?;
+            let mut results = Vec::new();
+            for asset in assets.iter() {
+                let path = asset.path().await?.path.clone();
+                let AssetContent::File(content) = &*asset.content().await? else {
+                    panic!("{path} is not a file");
+                };
+                let FileContent::Content(file) = &*content.await? else {
+                    panic!("{path} has no content");
+                };
+                results.push((path, file.content().to_str()?.to_string()));
+            }
+            anyhow::Ok(results)
+        };
+
+        let results = resolve_data_uri("data:text/javascript,export%20default%201;").await?;
+        let [(path, content)] = results.as_slice() else {
+            panic!("expected a single asset, got {results:?}");
+        };
+        assert!(path.starts_with("__data_uri__/") && path.ends_with(".js"));
+        assert_eq!(content, "export default 1;");
+
+        let results = resolve_data_uri("data:application/json;base64,eyJhIjoxfQ==").await?;
+        let [(path, content)] = results.as_slice() else {
+            panic!("expected a single asset, got {results:?}");
+        };
+        assert!(path.ends_with(".json"));
+        assert_eq!(content, r#"{"a":1}"#);
+
+        // Different data gets a different path
+        let other = resolve_data_uri("data:application/json;base64,eyJhIjoyfQ==").await?;
+        assert_ne!(other[0].0, *path);
+
+        assert!(resolve_data_uri("data:text/plain;base64,not%base64")
+            .await?
+            .is_empty());
+        Ok(())
+    });
+}
@@ -0,0 +1 @@
+import "/logo.svg";